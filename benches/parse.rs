@@ -0,0 +1,33 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use jtml::{jtml_lexer::lexer, jtml_parser::parse};
+
+// 生成されたページを想定した、要素数sectionsに比例する大きさのドキュメント
+fn generate(sections: usize) -> String {
+    let mut jtml = String::from("html(lang=\"ja\"){\n    body(){\n");
+    for i in 0..sections {
+        jtml.push_str(&format!(
+            "        // section {}\n        div(class=\"section\" id=\"s{}\"){{\n            h2(){{\"title {}\"}}\n            p(){{\"body\" a(href=\"#s{}\"){{\"link\"}}}}\n            img(src=\"{}.png\")\n        }}\n",
+            i, i, i, i, i
+        ));
+    }
+    jtml.push_str("    }\n}\n");
+    jtml
+}
+
+// 要素数に対して処理時間が線形であれば、要素あたりのスループットはサイズによらず一定になる
+fn parse_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for sections in [1_000, 4_000, 16_000] {
+        let tokens = lexer(generate(sections)).unwrap();
+        group.throughput(Throughput::Elements(sections as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(sections),
+            &tokens,
+            |b, tokens| b.iter(|| parse(tokens).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse_benchmark);
+criterion_main!(benches);
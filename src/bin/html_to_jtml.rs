@@ -1,4 +1,4 @@
-use jtml::html_converter::convert;
+use jtml::html_to_jtml::convert;
 use std::fs;
 use std::io::Write;
 use std::path::Path;
@@ -29,23 +29,25 @@ fn main() -> Result<(), anyhow::Error> {
         };
 
         // convert
-        let html = match convert(file_text, true) {
+        let jtml = match convert(&file_text) {
             Ok(converted) => converted,
-            Err(e) => {
-                eprintln!("Error compiling '{}' ({})", filename, e);
+            Err(errors) => {
+                for error in errors {
+                    eprint!("{}", error.to_diagnostic().render(&filename, &file_text));
+                }
                 continue;
             }
         };
 
         // write to file
-        let mut file = match fs::File::create(Path::new(&filename).with_extension("html")) {
+        let mut file = match fs::File::create(Path::new(&filename).with_extension("jtml")) {
             Ok(file) => file,
             Err(_) => {
                 eprintln!("Error creating file {}", filename);
                 continue;
             }
         };
-        file.write(html.as_bytes())?;
+        file.write_all(jtml.as_bytes())?;
     }
     Ok(())
 }
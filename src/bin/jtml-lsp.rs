@@ -0,0 +1,11 @@
+use lsp_server::Connection;
+
+// 標準入出力でクライアントと通信するLSPサーバー
+fn main() -> anyhow::Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    jtml::lsp::run(&connection)?;
+    // 送信側を閉じないと書き込みのスレッドが終わらない
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
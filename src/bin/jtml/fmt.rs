@@ -0,0 +1,83 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use similar::TextDiff;
+
+use crate::input::Input;
+
+// 整形前と整形後のunified diff。差分がなければ空文字列
+pub fn unified_diff(input: &Input, original: &str, formatted: &str) -> String {
+    let name = input.to_string();
+    TextDiff::from_lines(original, formatted)
+        .unified_diff()
+        .header(
+            &format!("{}\t(original)", name),
+            &format!("{}\t(formatted)", name),
+        )
+        .to_string()
+}
+
+// 同じディレクトリの一時ファイルに書いてから置き換えるため、途中で失敗しても元のファイルは壊れない
+pub fn write_atomic(path: &Path, text: &str) -> io::Result<()> {
+    let temp = temp_path(path);
+    let result = fs::write(&temp, text).and_then(|()| {
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&temp, metadata.permissions())?;
+        }
+        fs::rename(&temp, path)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::PathBuf};
+
+    use crate::input::Input;
+
+    use super::{unified_diff, write_atomic};
+
+    #[test]
+    fn diff() {
+        let input = Input::File(PathBuf::from("index.jtml"));
+        let diff = unified_diff(&input, "p(){\"a\"}\n", "p(){\n    \"a\"\n}\n");
+        assert_eq!(
+            diff,
+            r#"--- index.jtml	(original)
++++ index.jtml	(formatted)
+@@ -1 +1,3 @@
+-p(){"a"}
++p(){
++    "a"
++}
+"#
+        );
+        assert_eq!(unified_diff(&input, "p(){\n}\n", "p(){\n}\n"), "");
+    }
+
+    #[test]
+    fn atomic() {
+        let dir = std::env::temp_dir().join(format!("jtml-fmt-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("index.jtml");
+        fs::write(&path, "p(){}").unwrap();
+        write_atomic(&path, "p(){\n}\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "p(){\n}\n");
+        // 一時ファイルは残らない
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
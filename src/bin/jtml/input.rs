@@ -0,0 +1,164 @@
+use std::{
+    fmt::{self, Display},
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use walkdir::WalkDir;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    Stdin,
+    File(PathBuf),
+}
+
+impl Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Input::Stdin => write!(f, "<stdin>"),
+            Input::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+impl Input {
+    pub fn read(&self) -> io::Result<String> {
+        match self {
+            Input::Stdin => {
+                let mut text = String::new();
+                io::stdin().read_to_string(&mut text)?;
+                Ok(text)
+            }
+            Input::File(path) => fs::read_to_string(path),
+        }
+    }
+}
+
+// コマンドライン引数を入力の一覧に展開する
+// 引数がない場合と"-"は標準入力、ディレクトリは中のextensionのファイル全て、globはそれに一致するファイル
+pub fn collect_inputs(args: &[String], extension: &str) -> anyhow::Result<Vec<Input>> {
+    if args.is_empty() {
+        return Ok(vec![Input::Stdin]);
+    }
+    let mut inputs: Vec<Input> = Vec::new();
+    for arg in args {
+        let path = Path::new(arg);
+        let expanded = if arg == "-" {
+            vec![Input::Stdin]
+        } else if path.is_dir() {
+            files_in(path, extension)?
+        } else if is_glob(arg) {
+            let mut files = Vec::new();
+            for entry in glob::glob(arg).with_context(|| format!("invalid pattern `{}`", arg))? {
+                let path = entry?;
+                match path.is_dir() {
+                    true => files.extend(files_in(&path, extension)?),
+                    false => files.push(Input::File(path)),
+                }
+            }
+            if files.is_empty() {
+                bail!("no files match `{}`", arg);
+            }
+            files
+        } else {
+            // 存在しないファイルは読み込み時にエラーにする
+            vec![Input::File(path.to_path_buf())]
+        };
+        for input in expanded {
+            if !inputs.contains(&input) {
+                inputs.push(input);
+            }
+        }
+    }
+    Ok(inputs)
+}
+
+fn files_in(dir: &Path, extension: &str) -> anyhow::Result<Vec<Input>> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_file()
+            && entry.path().extension().is_some_and(|ext| ext == extension)
+        {
+            files.push(Input::File(entry.into_path()));
+        }
+    }
+    Ok(files)
+}
+
+fn is_glob(arg: &str) -> bool {
+    arg.contains(['*', '?', '['])
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::PathBuf};
+
+    use super::{collect_inputs, Input};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jtml-input-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("pages/nested")).unwrap();
+        for file in [
+            "index.jtml",
+            "pages/a.jtml",
+            "pages/nested/b.jtml",
+            "pages/c.html",
+        ] {
+            fs::write(dir.join(file), "p(){}").unwrap();
+        }
+        dir
+    }
+
+    fn args(args: &[PathBuf]) -> Vec<String> {
+        args.iter().map(|arg| arg.display().to_string()).collect()
+    }
+
+    #[test]
+    fn stdin() {
+        assert_eq!(collect_inputs(&[], "jtml").unwrap(), vec![Input::Stdin]);
+        assert_eq!(
+            collect_inputs(&["-".to_string()], "jtml").unwrap(),
+            vec![Input::Stdin]
+        );
+    }
+
+    #[test]
+    fn directory() {
+        let dir = temp_dir("directory");
+        let inputs = collect_inputs(&args(&[dir.join("pages")]), "jtml").unwrap();
+        assert_eq!(
+            inputs,
+            vec![
+                Input::File(dir.join("pages/a.jtml")),
+                Input::File(dir.join("pages/nested/b.jtml")),
+            ]
+        );
+        let inputs = collect_inputs(&args(&[dir.join("pages")]), "html").unwrap();
+        assert_eq!(inputs, vec![Input::File(dir.join("pages/c.html"))]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn glob() {
+        let dir = temp_dir("glob");
+        let inputs = collect_inputs(
+            &args(&[dir.join("**/*.jtml"), dir.join("index.jtml")]),
+            "jtml",
+        )
+        .unwrap();
+        assert_eq!(
+            inputs,
+            vec![
+                Input::File(dir.join("index.jtml")),
+                Input::File(dir.join("pages/a.jtml")),
+                Input::File(dir.join("pages/nested/b.jtml")),
+            ]
+        );
+        assert!(collect_inputs(&args(&[dir.join("*.txt")]), "jtml").is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,341 @@
+mod fmt;
+mod input;
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use jtml::{
+    diagnostics::{Diagnostic, Severity},
+    formatter::Tab,
+    formatter::{self, FormatConfig},
+    highlight,
+    html_converter::{self, HtmlOptions, Minify, OutputMode},
+    html_to_jtml,
+    jtml_lexer::lexer,
+    jtml_parser::{parse_recovering, AstRoot},
+    linter,
+    template::Context,
+    transformer::{normalize, FileResolver},
+};
+use structopt::StructOpt;
+
+use input::{collect_inputs, Input};
+
+#[derive(StructOpt)]
+#[structopt(name = "jtml", about = "jtml toolchain")]
+enum Command {
+    /// Convert jtml files to html
+    Build {
+        /// Keep comments in the html output
+        #[structopt(long)]
+        keep_comments: bool,
+        /// How to write void elements and empty svg/math elements
+        #[structopt(long, default_value = "html5", possible_values = &OutputMode::NAMES)]
+        mode: OutputMode,
+        /// Put block elements on their own lines and indent them
+        #[structopt(long)]
+        pretty: bool,
+        /// Indent width for --pretty, or `tab`
+        #[structopt(long, default_value = "2", parse(try_from_str = parse_indent))]
+        indent: Tab,
+        /// Make the html as small as possible without changing what it means
+        #[structopt(long, conflicts_with_all = &["pretty", "keep-comments"])]
+        minify: bool,
+        /// Also minify the contents of style and script elements
+        #[structopt(long, requires = "minify")]
+        minify_inline: bool,
+        /// JSON or TOML file with the values for `{{ name }}`
+        #[structopt(long, parse(from_os_str))]
+        data: Option<PathBuf>,
+        /// Only write the outputs of inputs that are or include one of these files
+        #[structopt(long, parse(from_os_str), number_of_values = 1)]
+        changed: Vec<PathBuf>,
+        #[structopt(flatten)]
+        inputs: Inputs,
+    },
+    /// Format jtml files
+    Fmt {
+        /// Exit with an error if any file is not formatted, without writing anything
+        #[structopt(long)]
+        check: bool,
+        /// Print a unified diff of the changes
+        #[structopt(long)]
+        diff: bool,
+        /// Format files in place
+        #[structopt(long, conflicts_with = "check")]
+        write: bool,
+        #[structopt(flatten)]
+        inputs: Inputs,
+    },
+    /// Report syntax errors
+    Check {
+        #[structopt(flatten)]
+        inputs: Inputs,
+    },
+    /// Report syntax errors and suspicious code. Fails only on errors
+    Lint {
+        #[structopt(flatten)]
+        inputs: Inputs,
+    },
+    /// Parse jtml files
+    Parse {
+        /// Print the syntax tree
+        #[structopt(long)]
+        dump_ast: bool,
+        #[structopt(flatten)]
+        inputs: Inputs,
+    },
+    /// Convert html files to jtml
+    ImportHtml {
+        #[structopt(flatten)]
+        inputs: Inputs,
+    },
+    /// Print the TextMate grammar for editors
+    Grammar,
+}
+
+#[derive(StructOpt)]
+struct Inputs {
+    /// Files, directories or glob patterns. Reads stdin when omitted or `-`
+    inputs: Vec<String>,
+}
+
+impl Command {
+    fn inputs(&self) -> &[String] {
+        match self {
+            Command::Build { inputs, .. }
+            | Command::Fmt { inputs, .. }
+            | Command::Check { inputs }
+            | Command::Lint { inputs }
+            | Command::Parse { inputs, .. }
+            | Command::ImportHtml { inputs } => &inputs.inputs,
+            Command::Grammar => &[],
+        }
+    }
+
+    // ディレクトリから読むファイルの拡張子
+    fn extension(&self) -> &'static str {
+        match self {
+            Command::ImportHtml { .. } => "html",
+            _ => "jtml",
+        }
+    }
+}
+
+fn main() {
+    let command = Command::from_args();
+    // 入力を読まないコマンド
+    if let Command::Grammar = command {
+        print!("{}", highlight::textmate_grammar_file());
+        return;
+    }
+    let inputs = match collect_inputs(command.inputs(), command.extension()) {
+        Ok(inputs) => inputs,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    };
+
+    // データは全ての入力で共有する
+    let context = match &command {
+        Command::Build {
+            data: Some(path), ..
+        } => match Context::load(path) {
+            Ok(context) => Some(context),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+        },
+        _ => None,
+    };
+
+    let mut failed = false;
+    for input in &inputs {
+        let source = match input.read() {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("error: cannot read {}: {}", input, e);
+                failed = true;
+                continue;
+            }
+        };
+        if !run(&command, input, &source, context.as_ref()) {
+            failed = true;
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+// ファイル一つを処理し、失敗した場合はfalseを返す
+// contextは--dataを指定した場合のみ
+fn run(command: &Command, input: &Input, source: &str, context: Option<&Context>) -> bool {
+    match command {
+        Command::Build {
+            keep_comments,
+            mode,
+            pretty,
+            indent,
+            minify,
+            minify_inline,
+            changed,
+            ..
+        } => {
+            let options = HtmlOptions {
+                ignore_comment: !keep_comments,
+                mode: *mode,
+                pretty: pretty.then(|| indent.clone()),
+                minify: minify.then_some(Minify {
+                    inline_code: *minify_inline,
+                }),
+            };
+            // 標準入力はカレントディレクトリにあるものとして読み込む
+            let path = match input {
+                Input::Stdin => Path::new("<stdin>"),
+                Input::File(path) => path,
+            };
+            let converted = html_converter::convert_file(
+                source.to_string(),
+                path,
+                &FileResolver,
+                &options,
+                context,
+            );
+            match converted {
+                Ok(converted) => {
+                    let affected = changed.is_empty()
+                        || std::iter::once(path)
+                            .chain(converted.dependencies.iter().map(PathBuf::as_path))
+                            .any(|file| changed.iter().any(|edited| same_file(file, edited)));
+                    !affected || output(input, "html", &converted.html)
+                }
+                Err(e) => report(input, source, &e.to_diagnostics(source)),
+            }
+        }
+        Command::Fmt {
+            check, diff, write, ..
+        } => {
+            let config = match input {
+                Input::Stdin => FormatConfig::discover(Path::new("<stdin>")),
+                Input::File(path) => FormatConfig::discover(path),
+            };
+            let config = match config {
+                Ok(config) => config,
+                Err(e) => {
+                    eprintln!("error: invalid config {}", e);
+                    return false;
+                }
+            };
+            let formatted = match formatter::format_with_config(source.to_string(), &config) {
+                // ファイルは改行で終わるものを正しい形とする
+                Ok(formatted) => format!("{}\n", formatted),
+                Err(e) => return report(input, source, &e.to_diagnostics(source)),
+            };
+            let changed = formatted != source;
+            if *diff {
+                print!("{}", fmt::unified_diff(input, source, &formatted));
+            }
+            if *check {
+                if changed {
+                    eprintln!("error: {} is not formatted", input);
+                }
+                return !changed;
+            }
+            match (write, input) {
+                (true, Input::File(path)) if changed => match fmt::write_atomic(path, &formatted) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        eprintln!("error: cannot write {}: {}", input, e);
+                        false
+                    }
+                },
+                (true, Input::File(_)) => true,
+                _ if *diff => true,
+                _ => output(input, "formatted_jtml", &formatted),
+            }
+        }
+        Command::Check { .. } => match parse(source) {
+            Ok(_) => true,
+            Err(diagnostics) => report(input, source, &diagnostics),
+        },
+        Command::Lint { .. } => match parse(source) {
+            Ok(ast) => report(input, source, &linter::lint(&ast)),
+            Err(diagnostics) => report(input, source, &diagnostics),
+        },
+        Command::Parse { dump_ast, .. } => match parse(source) {
+            Ok(ast) => {
+                if *dump_ast {
+                    println!("{:#?}", ast);
+                }
+                true
+            }
+            Err(diagnostics) => report(input, source, &diagnostics),
+        },
+        Command::ImportHtml { .. } => match html_to_jtml::convert(source) {
+            Ok(jtml) => output(input, "jtml", &jtml),
+            Err(errors) => {
+                let diagnostics = errors.iter().map(|e| e.to_diagnostic()).collect::<Vec<_>>();
+                report(input, source, &diagnostics)
+            }
+        },
+        Command::Grammar => true,
+    }
+}
+
+fn parse(source: &str) -> Result<AstRoot, Vec<Diagnostic>> {
+    let tokens = lexer(source.to_string()).map_err(|e| vec![e.to_diagnostic()])?;
+    let (ast, errors) = parse_recovering(&tokens);
+    if !errors.is_empty() {
+        return Err(errors.iter().map(|e| e.to_diagnostic(source)).collect());
+    }
+    Ok(ast)
+}
+
+fn parse_indent(text: &str) -> Result<Tab, String> {
+    match text {
+        "tab" => Ok(Tab::Tabs),
+        _ => text
+            .parse()
+            .map(Tab::Spaces)
+            .map_err(|_| format!("invalid indent `{}`, expected a width or `tab`", text)),
+    }
+}
+
+// 書き方の違う同じファイルのパス(`./a.jtml`と`a.jtml`など)を同じとみなす
+fn same_file(a: &Path, b: &Path) -> bool {
+    let resolve = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| normalize(path));
+    resolve(a) == resolve(b)
+}
+
+// 診断を表示し、エラーがなければtrueを返す(警告だけの場合は失敗にしない)
+fn report(input: &Input, source: &str, diagnostics: &[Diagnostic]) -> bool {
+    let name = input.to_string();
+    for diagnostic in diagnostics {
+        eprint!("{}", diagnostic.render(&name, source));
+    }
+    !diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+}
+
+// ファイルは拡張子を変えた隣のファイルに、標準入力は標準出力に書き出す
+fn output(input: &Input, extension: &str, text: &str) -> bool {
+    let result = match input {
+        Input::Stdin => io::stdout().write_all(text.as_bytes()),
+        Input::File(path) => fs::write(Path::new(path).with_extension(extension), text),
+    };
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("error: cannot write output for {}: {}", input, e);
+            false
+        }
+    }
+}
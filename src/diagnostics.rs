@@ -0,0 +1,172 @@
+use std::fmt::{self, Display};
+
+use crate::span::{LineIndex, Span};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+// ファイル名・行番号・該当行・下線付きで表示するためのエラー情報
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+    // 下線の横に表示する説明 ("expected X, found Y" など)
+    pub label: Option<String>,
+    pub hint: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>, span: Span) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message: message.into(),
+            span,
+            label: None,
+            hint: None,
+        }
+    }
+
+    pub fn warning(message: impl Into<String>, span: Span) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            ..Diagnostic::error(message, span)
+        }
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    // rustcのような形式で表示する
+    //
+    // error: unexpected token
+    //  --> index.jtml:1:2
+    //   |
+    // 1 | p){}
+    //   |  ^ expected '(', found ')'
+    //   |
+    //   = hint: ...
+    pub fn render(&self, file_name: &str, source: &str) -> String {
+        let index = LineIndex::new(source);
+        let start = index.position(source, self.span.start);
+        let line_range = index.line_range(source, start.line);
+        let line_text = &source[line_range.clone()];
+
+        let line_number = (start.line + 1).to_string();
+        let gutter = " ".repeat(line_number.len());
+
+        // 複数行にまたがる場合は最初の行の末尾まで下線を引く
+        let underline_end = self.span.end.clamp(self.span.start, line_range.end);
+        let padding = line_text[..self.span.start.min(line_range.end) - line_range.start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        let carets = "^".repeat(
+            source[self.span.start.min(line_range.end)..underline_end]
+                .chars()
+                .count()
+                .max(1),
+        );
+
+        let mut rendered = format!(
+            "{}: {}\n{}--> {}:{}:{}\n{} |\n{} | {}\n{} | {}{}",
+            self.severity,
+            self.message,
+            gutter,
+            file_name,
+            start.line + 1,
+            start.column + 1,
+            gutter,
+            line_number,
+            line_text,
+            gutter,
+            padding,
+            carets,
+        );
+        if let Some(label) = &self.label {
+            rendered.push(' ');
+            rendered.push_str(label);
+        }
+        if let Some(hint) = &self.hint {
+            rendered.push_str(&format!("\n{} |\n{} = hint: {}", gutter, gutter, hint));
+        }
+        rendered.push('\n');
+        rendered
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)?;
+        if let Some(label) = &self.label {
+            write!(f, " ({})", label)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::span::Span;
+
+    use super::Diagnostic;
+
+    #[test]
+    fn render() {
+        let source = "html(){\n    p){}\n}";
+        let diagnostic = Diagnostic::error("unexpected token", Span::new(13, 14))
+            .with_label("expected '(', found ')'")
+            .with_hint("elements are written as `name(attributes){children}`");
+        assert_eq!(
+            diagnostic.render("index.jtml", source),
+            r#"error: unexpected token
+ --> index.jtml:2:6
+  |
+2 |     p){}
+  |      ^ expected '(', found ')'
+  |
+  = hint: elements are written as `name(attributes){children}`
+"#
+        );
+    }
+
+    #[test]
+    fn render_multi_character_span() {
+        let source = "p(){\n\t\"unterminated\n}";
+        let diagnostic = Diagnostic::error("invalid token", Span::new(6, source.len()));
+        assert_eq!(
+            diagnostic.render("a.jtml", source),
+            "error: invalid token\n --> a.jtml:2:2\n  |\n2 | \t\"unterminated\n  | \t^^^^^^^^^^^^^\n"
+        );
+    }
+
+    #[test]
+    fn render_end_of_file() {
+        let source = "p(){";
+        let diagnostic =
+            Diagnostic::error("unexpected end of file", Span::new(4, 4)).with_label("expected '}'");
+        assert_eq!(
+            diagnostic.render("a.jtml", source),
+            "error: unexpected end of file\n --> a.jtml:1:5\n  |\n1 | p(){\n  |     ^ expected '}'\n"
+        );
+    }
+}
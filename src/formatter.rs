@@ -1,6 +1,17 @@
-use std::collections::VecDeque;
+use serde::Deserialize;
 
-use crate::{html_converter::HtmlConverterError, jtml_lexer::lexer, jtml_parser};
+use crate::{
+    html_converter::HtmlConverterError,
+    jtml_lexer::{lossless_lexer, JtmlToken},
+    jtml_parser::{self, cst},
+};
+
+mod config;
+pub mod doc;
+mod editorconfig;
+mod printer;
+
+pub use config::{ConfigError, CONFIG_FILE_NAME};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Tab {
@@ -8,38 +19,92 @@ pub enum Tab {
     Tabs,
 }
 
+impl Tab {
+    pub fn text(&self, depth: usize) -> String {
+        match self {
+            Tab::Spaces(size) => " ".repeat(size * depth),
+            Tab::Tabs => "\t".repeat(depth),
+        }
+    }
+}
+
+// 属性の並べ方
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AttributeLayout {
+    // 一行に収まらない場合のみ一行に一つずつ並べる
+    Auto,
+    SingleLine,
+    // 属性が複数ある場合は常に一行に一つずつ並べる
+    MultiLine,
+}
+
+// `div.card#main`のような書き方を揃えるかどうか
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SelectorStyle {
+    // 書かれたままにする
+    Preserve,
+    // class, id属性を要素名の後ろに移す
+    Shorthand,
+    // 要素名の後ろのclass, idを属性に展開する
+    Attributes,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct FormatConfig {
     pub indent: Tab,
     pub ignore_comment: bool,
+    pub max_width: usize,
+    pub attribute_layout: AttributeLayout,
+    pub selector_style: SelectorStyle,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        FormatConfig {
+            indent: Tab::Spaces(4),
+            ignore_comment: false,
+            max_width: 100,
+            attribute_layout: AttributeLayout::Auto,
+            selector_style: SelectorStyle::Preserve,
+        }
+    }
 }
 
 impl FormatConfig {
     pub fn get_indent_text(&self, depth: usize) -> String {
-        match self.indent {
-            Tab::Spaces(size) => " ".repeat(size * depth),
-            Tab::Tabs => "\t".repeat(depth),
-        }
+        self.indent.text(depth)
     }
 }
 
 pub fn format(text: String) -> Result<String, HtmlConverterError> {
-    let mut tokens = VecDeque::from(match lexer(text) {
+    format_with_config(text, &FormatConfig::default())
+}
+
+// 書かれたコメントや空行を失わないように、空白も含めた構文木(CST)から整形する
+pub fn format_with_config(
+    text: String,
+    config: &FormatConfig,
+) -> Result<String, HtmlConverterError> {
+    let tokens = match lossless_lexer(&text) {
         Ok(tokens) => tokens,
         Err(e) => {
             return Err(HtmlConverterError::LexerError(e));
         }
-    });
-
-    let ast = match jtml_parser::parse(&mut tokens) {
-        Ok(ast) => ast,
-        Err(e) => return Err(HtmlConverterError::ParseError(e)),
     };
-    let config = FormatConfig {
-        indent: Tab::Spaces(4),
-        ignore_comment: false,
-    };
-    Ok(ast.to_jtml(false, &config))
+
+    // 構文エラーの報告はASTのパーサーに任せる
+    let significant = tokens
+        .iter()
+        .filter(|token| token.token != JtmlToken::Whitespace)
+        .cloned()
+        .collect::<Vec<_>>();
+    let (_, errors) = jtml_parser::parse_recovering(&significant);
+    if !errors.is_empty() {
+        return Err(HtmlConverterError::ParseError(errors));
+    }
+    Ok(printer::print(&cst::parse(&tokens), &text, config))
 }
 
 #[cfg(test)]
@@ -49,7 +114,7 @@ mod test {
     fn single_simple_element() {
         use super::*;
         let result = format("p(){}".to_string());
-        assert_eq!(result.unwrap(), "p(){\n}".to_string());
+        assert_eq!(result.unwrap(), "p(){}".to_string());
 
         let result = format("img()".to_string());
         assert_eq!(result.unwrap(), "img()".to_string());
@@ -65,7 +130,7 @@ mod test {
     fn single_element_with_attribute() {
         use super::*;
         let result = format("p(class=\"btn\"){}".to_string()).unwrap();
-        assert_eq!(result, "p(class=\"btn\"){\n}".to_string());
+        assert_eq!(result, "p(class=\"btn\"){}".to_string());
 
         let result = format("img(href=\"./images/img.png\")".to_string()).unwrap();
         assert_eq!(result, "img(href=\"./images/img.png\")".to_string());
@@ -75,12 +140,14 @@ mod test {
     fn single_element_with_child() {
         use super::*;
         let result = format(r#"p(){p(){"hello"}}"#.to_string()).unwrap();
+        assert_eq!(result, r#"p(){p(){"hello"}}"#.to_string());
+
+        let result = format(r#"p(){p(){"hello"} "world"}"#.to_string()).unwrap();
         assert_eq!(
             result,
             r#"p(){
-    p(){
-        "hello"
-    }
+    p(){"hello"}
+    "world"
 }"#
             .to_string()
         );
@@ -104,9 +171,7 @@ head(){
             r#"head(){
     meta(charset="UTF-8")
     meta(http-equiv="X-UA-Compatible" content="IE=edge")
-    title(){
-        "document"
-    }
+    title(){"document"}
 }"#
         )
     }
@@ -141,15 +206,11 @@ html(lang="ja"){
         meta(charset="UTF-8")
         meta(http-equiv="X-UA-Compatible" content="IE=edge")
         meta(name="viewport" content="width=device-width" initial-scale="1.0")
-        title(){
-            "document"
-        }
+        title(){"document"}
     }
     body(){
         main(){
-            h1(){
-                "Hello World!"
-            }
+            h1(){"Hello World!"}
             img(hoge="hoge" huga="huga")
         }
     }
@@ -157,4 +218,19 @@ html(lang="ja"){
             .to_string()
         )
     }
+
+    #[test]
+    fn remove_comments() {
+        use super::*;
+        let config = FormatConfig {
+            ignore_comment: true,
+            ..FormatConfig::default()
+        };
+        let result = format_with_config(
+            "// top\np(){\n    // inner\n    \"text\"\n}\ndiv(){// only\n}".to_string(),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(result, "p(){\"text\"}\ndiv(){}");
+    }
 }
@@ -0,0 +1,230 @@
+use std::{
+    fmt::{self, Display},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use super::{editorconfig, AttributeLayout, FormatConfig, SelectorStyle, Tab};
+
+pub const CONFIG_FILE_NAME: &str = "jtml.toml";
+
+// jtml.toml
+//
+// [format]
+// indent_style = "space"        # "space" または "tab"
+// indent_width = 4
+// max_width = 100
+// comments = "keep"             # "keep" または "remove"
+// attribute_layout = "auto"     # "auto", "single-line", "multi-line"
+// selector = "preserve"         # "preserve", "shorthand", "attributes"
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    format: FormatSection,
+}
+
+// 指定されていない項目はNoneのままにし、.editorconfigや既定値で補う
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct FormatSection {
+    pub(super) indent_style: Option<IndentStyle>,
+    pub(super) indent_width: Option<usize>,
+    pub(super) max_width: Option<usize>,
+    comments: Option<Comments>,
+    attribute_layout: Option<AttributeLayout>,
+    selector: Option<SelectorStyle>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum IndentStyle {
+    Space,
+    Tab,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Comments {
+    Keep,
+    Remove,
+}
+
+impl FormatSection {
+    // selfで指定されていない項目をotherで補う
+    fn or(self, other: FormatSection) -> FormatSection {
+        FormatSection {
+            indent_style: self.indent_style.or(other.indent_style),
+            indent_width: self.indent_width.or(other.indent_width),
+            max_width: self.max_width.or(other.max_width),
+            comments: self.comments.or(other.comments),
+            attribute_layout: self.attribute_layout.or(other.attribute_layout),
+            selector: self.selector.or(other.selector),
+        }
+    }
+
+    fn into_config(self) -> FormatConfig {
+        let default = FormatConfig::default();
+        let width = match default.indent {
+            Tab::Spaces(width) => width,
+            Tab::Tabs => 4,
+        };
+        FormatConfig {
+            indent: match self.indent_style {
+                Some(IndentStyle::Tab) => Tab::Tabs,
+                _ => Tab::Spaces(self.indent_width.unwrap_or(width)),
+            },
+            ignore_comment: self.comments == Some(Comments::Remove),
+            max_width: self.max_width.unwrap_or(default.max_width),
+            attribute_layout: self.attribute_layout.unwrap_or(default.attribute_layout),
+            selector_style: self.selector.unwrap_or(default.selector_style),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl FormatConfig {
+    pub fn from_toml(text: &str) -> Result<FormatConfig, String> {
+        Ok(parse_toml(text)?.into_config())
+    }
+
+    // fileのあるディレクトリから親へ向かって最初に見つかったjtml.tomlを使う
+    // jtml.tomlで指定されていない項目は.editorconfigの設定で補う
+    pub fn discover(file: &Path) -> Result<FormatConfig, ConfigError> {
+        let file = match file.canonicalize() {
+            Ok(file) => file,
+            Err(_) => std::env::current_dir()
+                .map(|dir| dir.join(file))
+                .unwrap_or_else(|_| file.to_path_buf()),
+        };
+        let mut section = FormatSection::default();
+        for dir in file.ancestors().skip(1) {
+            let path = dir.join(CONFIG_FILE_NAME);
+            if !path.is_file() {
+                continue;
+            }
+            let text = fs::read_to_string(&path).map_err(|e| ConfigError {
+                path: path.clone(),
+                message: e.to_string(),
+            })?;
+            section = parse_toml(&text).map_err(|message| ConfigError { path, message })?;
+            break;
+        }
+        let editorconfig = editorconfig::settings(&file)
+            .map_err(|(path, message)| ConfigError { path, message })?;
+        Ok(section.or(editorconfig).into_config())
+    }
+}
+
+fn parse_toml(text: &str) -> Result<FormatSection, String> {
+    toml::from_str::<ConfigFile>(text)
+        .map(|config| config.format)
+        .map_err(|e| e.message().to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::PathBuf};
+
+    use crate::formatter::{AttributeLayout, FormatConfig, SelectorStyle, Tab, CONFIG_FILE_NAME};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jtml-config-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("pages")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn from_toml() {
+        let config = FormatConfig::from_toml(
+            r#"
+[format]
+indent_style = "tab"
+max_width = 80
+comments = "remove"
+attribute_layout = "multi-line"
+selector = "shorthand"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            FormatConfig {
+                indent: Tab::Tabs,
+                ignore_comment: true,
+                max_width: 80,
+                attribute_layout: AttributeLayout::MultiLine,
+                selector_style: SelectorStyle::Shorthand,
+            }
+        );
+
+        let config = FormatConfig::from_toml("[format]\nindent_width = 2").unwrap();
+        assert_eq!(config.indent, Tab::Spaces(2));
+        assert_eq!(
+            FormatConfig::from_toml("").unwrap(),
+            FormatConfig::default()
+        );
+    }
+
+    #[test]
+    fn invalid_toml() {
+        assert!(FormatConfig::from_toml("[format]\nindent = 2").is_err());
+        assert!(FormatConfig::from_toml("[format]\nindent_style = \"tabs\"").is_err());
+    }
+
+    #[test]
+    fn discover() {
+        let dir = temp_dir("discover");
+        let file = dir.join("pages/index.jtml");
+        assert_eq!(
+            FormatConfig::discover(&file).unwrap(),
+            FormatConfig::default()
+        );
+
+        fs::write(
+            dir.join(".editorconfig"),
+            "root = true\n[*.jtml]\nindent_style = tab\n",
+        )
+        .unwrap();
+        assert_eq!(FormatConfig::discover(&file).unwrap().indent, Tab::Tabs);
+
+        // jtml.tomlの設定が優先される
+        fs::write(
+            dir.join(CONFIG_FILE_NAME),
+            "[format]\nindent_style = \"space\"\nindent_width = 2\n",
+        )
+        .unwrap();
+        assert_eq!(
+            FormatConfig::discover(&file).unwrap().indent,
+            Tab::Spaces(2)
+        );
+
+        fs::write(
+            dir.join(CONFIG_FILE_NAME),
+            "[format]\nmax_width = \"wide\"\n",
+        )
+        .unwrap();
+        let error = FormatConfig::discover(&file).unwrap_err();
+        assert_eq!(
+            error.path,
+            dir.join(CONFIG_FILE_NAME).canonicalize().unwrap()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,288 @@
+use super::{AttributeLayout, FormatConfig};
+
+// Wadlerの"A prettier printer"に基づく文書の代数
+// 整形の規則をDocとして組み立て、幅に応じた改行の判断はrenderに任せる
+#[derive(Debug, Clone, PartialEq)]
+pub enum Doc {
+    Nil,
+    Text(String),
+    // 一行に収まる場合は空白、収まらない場合は改行
+    Line,
+    // 一行に収まる場合は何も出力せず、収まらない場合は改行
+    SoftLine,
+    // 常に改行する
+    HardLine,
+    // 何も出力しないが、囲んでいるグループを必ず改行させる
+    BreakParent,
+    Concat(Vec<Doc>),
+    // 中の改行の後のインデントを一段深くする
+    Nest(Box<Doc>),
+    // 中身が一行に収まる場合は中の改行を全て一行の形で出力する
+    Group(Box<Doc>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+impl Doc {
+    pub fn text(text: impl Into<String>) -> Self {
+        Doc::Text(text.into())
+    }
+
+    pub fn concat(docs: Vec<Doc>) -> Self {
+        Doc::Concat(docs)
+    }
+
+    pub fn nest(doc: Doc) -> Self {
+        Doc::Nest(Box::new(doc))
+    }
+
+    pub fn group(doc: Doc) -> Self {
+        Doc::Group(Box::new(doc))
+    }
+
+    pub fn join(docs: Vec<Doc>, separator: Doc) -> Self {
+        let mut joined = Vec::new();
+        for (i, doc) in docs.into_iter().enumerate() {
+            if i > 0 {
+                joined.push(separator.clone());
+            }
+            joined.push(doc);
+        }
+        Doc::Concat(joined)
+    }
+
+    pub fn is_nil(&self) -> bool {
+        *self == Doc::Nil
+    }
+
+    // indent_depthの深さから始めて、config.max_widthに収まるように出力する
+    pub fn render(&self, indent_depth: usize, config: &FormatConfig) -> String {
+        let mut output = String::new();
+        // 空行に空白が残らないよう、インデントは次の文字列を出力する時に書く
+        let mut pending_indent = Some(indent_depth);
+        let mut column = config.get_indent_text(indent_depth).chars().count();
+        let mut stack = vec![(indent_depth, Mode::Break, self)];
+        while let Some((depth, mode, doc)) = stack.pop() {
+            let text = match (doc, mode) {
+                (Doc::Nil | Doc::BreakParent, _) => continue,
+                (Doc::Text(text), _) => text.as_str(),
+                (Doc::Line, Mode::Flat) => " ",
+                (Doc::SoftLine, Mode::Flat) => continue,
+                (Doc::Line | Doc::SoftLine | Doc::HardLine, _) => {
+                    output.push('\n');
+                    pending_indent = Some(depth);
+                    column = config.get_indent_text(depth).chars().count();
+                    continue;
+                }
+                (Doc::Concat(docs), _) => {
+                    stack.extend(docs.iter().rev().map(|doc| (depth, mode, doc)));
+                    continue;
+                }
+                (Doc::Nest(doc), _) => {
+                    stack.push((depth + 1, mode, doc));
+                    continue;
+                }
+                (Doc::Group(doc), Mode::Flat) => {
+                    stack.push((depth, Mode::Flat, doc));
+                    continue;
+                }
+                (Doc::Group(doc), Mode::Break) => {
+                    let width = config.max_width as isize - column as isize;
+                    let mode = if fits(width, doc, &stack) {
+                        Mode::Flat
+                    } else {
+                        Mode::Break
+                    };
+                    stack.push((depth, mode, doc));
+                    continue;
+                }
+            };
+            if let Some(depth) = pending_indent.take() {
+                output.push_str(&config.get_indent_text(depth));
+            }
+            output.push_str(text);
+            column += text.chars().count();
+        }
+        output
+    }
+}
+
+// docを一行で出力した場合に、その後ろの次の改行までを含めてwidthに収まるか
+fn fits(mut width: isize, doc: &Doc, rest: &[(usize, Mode, &Doc)]) -> bool {
+    let mut stack = vec![(Mode::Flat, doc)];
+    let mut rest = rest.iter().rev();
+    loop {
+        if width < 0 {
+            return false;
+        }
+        let (mode, doc) = match stack.pop() {
+            Some(next) => next,
+            None => match rest.next() {
+                Some((_, mode, doc)) => (*mode, *doc),
+                None => return true,
+            },
+        };
+        match (doc, mode) {
+            (Doc::Nil, _) | (Doc::BreakParent, Mode::Break) => (),
+            (Doc::Text(text), _) => width -= text.chars().count() as isize,
+            (Doc::Line, Mode::Flat) => width -= 1,
+            (Doc::SoftLine, Mode::Flat) => (),
+            (Doc::HardLine | Doc::BreakParent, Mode::Flat) => return false,
+            (Doc::Line | Doc::SoftLine | Doc::HardLine, Mode::Break) => return true,
+            (Doc::Concat(docs), _) => stack.extend(docs.iter().rev().map(|doc| (mode, doc))),
+            (Doc::Nest(doc) | Doc::Group(doc), _) => stack.push((mode, doc)),
+        }
+    }
+}
+
+// 属性リストを設定に応じて並べる
+// コメントを含む場合は常に一行に一つずつ並べる
+pub(crate) fn attribute_list(
+    entries: Vec<Doc>,
+    attribute_count: usize,
+    has_comment: bool,
+    config: &FormatConfig,
+) -> Doc {
+    if entries.is_empty() {
+        return Doc::text("()");
+    }
+    let multi_line = has_comment
+        || (config.attribute_layout == AttributeLayout::MultiLine && attribute_count > 1);
+    if multi_line {
+        return Doc::concat(vec![
+            Doc::text("("),
+            Doc::nest(Doc::concat(vec![
+                Doc::HardLine,
+                Doc::join(entries, Doc::HardLine),
+            ])),
+            Doc::HardLine,
+            Doc::text(")"),
+        ]);
+    }
+    match config.attribute_layout {
+        AttributeLayout::SingleLine | AttributeLayout::MultiLine => Doc::concat(vec![
+            Doc::text("("),
+            Doc::join(entries, Doc::text(" ")),
+            Doc::text(")"),
+        ]),
+        AttributeLayout::Auto => Doc::group(Doc::concat(vec![
+            Doc::text("("),
+            Doc::nest(Doc::concat(vec![
+                Doc::SoftLine,
+                Doc::join(entries, Doc::Line),
+            ])),
+            Doc::SoftLine,
+            Doc::text(")"),
+        ])),
+    }
+}
+
+// 要素の本体を並べる
+// 中身が一つだけで一行に収まる場合は`{`と同じ行に置き、それ以外は一行に一つずつ並べる
+// Nilは空行として出力する
+pub(crate) fn body(items: Vec<Doc>) -> Doc {
+    match items.len() {
+        0 => Doc::text("{}"),
+        1 => Doc::group(Doc::concat(vec![
+            Doc::text("{"),
+            Doc::nest(Doc::concat(vec![
+                Doc::SoftLine,
+                items.into_iter().next().unwrap_or(Doc::Nil),
+            ])),
+            Doc::SoftLine,
+            Doc::text("}"),
+        ])),
+        _ => Doc::concat(vec![
+            Doc::text("{"),
+            Doc::nest(Doc::concat(vec![Doc::HardLine, lines(items)])),
+            Doc::HardLine,
+            Doc::text("}"),
+        ]),
+    }
+}
+
+// 一行に一つずつ並べる。Nilは空行になる
+pub(crate) fn lines(items: Vec<Doc>) -> Doc {
+    Doc::join(items, Doc::HardLine)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::formatter::{FormatConfig, Tab};
+
+    use super::Doc;
+
+    fn config(max_width: usize) -> FormatConfig {
+        FormatConfig {
+            indent: Tab::Spaces(2),
+            max_width,
+            ..FormatConfig::default()
+        }
+    }
+
+    fn list() -> Doc {
+        Doc::group(Doc::concat(vec![
+            Doc::text("["),
+            Doc::nest(Doc::concat(vec![
+                Doc::SoftLine,
+                Doc::join(
+                    vec![Doc::text("aaa"), Doc::text("bbb"), Doc::text("ccc")],
+                    Doc::concat(vec![Doc::text(","), Doc::Line]),
+                ),
+            ])),
+            Doc::SoftLine,
+            Doc::text("]"),
+        ]))
+    }
+
+    #[test]
+    fn group() {
+        assert_eq!(list().render(0, &config(15)), "[aaa, bbb, ccc]");
+        assert_eq!(list().render(0, &config(14)), "[\n  aaa,\n  bbb,\n  ccc\n]");
+        // 開始位置のインデントも幅に含める
+        assert_eq!(
+            list().render(1, &config(16)),
+            "  [\n    aaa,\n    bbb,\n    ccc\n  ]"
+        );
+    }
+
+    #[test]
+    fn nested_group() {
+        let doc = Doc::group(Doc::concat(vec![
+            Doc::text("f("),
+            Doc::nest(Doc::concat(vec![Doc::SoftLine, list()])),
+            Doc::SoftLine,
+            Doc::text(")"),
+        ]));
+        assert_eq!(doc.render(0, &config(20)), "f([aaa, bbb, ccc])");
+        // 外側のグループが改行すると内側のグループは改めて幅を判断する
+        assert_eq!(doc.render(0, &config(17)), "f(\n  [aaa, bbb, ccc]\n)");
+    }
+
+    #[test]
+    fn break_parent() {
+        let doc = Doc::group(Doc::concat(vec![
+            Doc::text("a"),
+            Doc::Line,
+            Doc::text("b"),
+            Doc::BreakParent,
+        ]));
+        assert_eq!(doc.render(0, &config(100)), "a\nb");
+    }
+
+    #[test]
+    fn blank_line_without_indent() {
+        let doc = Doc::nest(Doc::concat(vec![
+            Doc::text("a"),
+            Doc::HardLine,
+            Doc::Nil,
+            Doc::HardLine,
+            Doc::text("b"),
+        ]));
+        assert_eq!(doc.render(0, &config(100)), "a\n\n  b");
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use glob::{MatchOptions, Pattern};
+
+use super::config::{FormatSection, IndentStyle};
+
+const EDITORCONFIG_FILE_NAME: &str = ".editorconfig";
+
+// fileに適用される.editorconfigのインデントと行幅の設定を読む
+// root = true のファイルまで親へ向かって読み、ファイルに近いものほど優先する
+pub(super) fn settings(file: &Path) -> Result<FormatSection, (PathBuf, String)> {
+    let mut files = Vec::new();
+    for dir in file.ancestors().skip(1) {
+        let path = dir.join(EDITORCONFIG_FILE_NAME);
+        if !path.is_file() {
+            continue;
+        }
+        let text = fs::read_to_string(&path).map_err(|e| (path.clone(), e.to_string()))?;
+        let editorconfig = parse(&text);
+        let root = editorconfig.root;
+        files.push((dir, editorconfig));
+        if root {
+            break;
+        }
+    }
+
+    let mut properties: Vec<(String, String)> = Vec::new();
+    for (dir, editorconfig) in files.iter().rev() {
+        let Ok(relative) = file.strip_prefix(dir) else {
+            continue;
+        };
+        for (glob, section) in &editorconfig.sections {
+            if matches(glob, relative) {
+                properties.extend(section.iter().cloned());
+            }
+        }
+    }
+
+    let mut settings = FormatSection::default();
+    let mut tab_width = None;
+    for (key, value) in properties {
+        match (key.as_str(), value.as_str()) {
+            ("indent_style", "space") => settings.indent_style = Some(IndentStyle::Space),
+            ("indent_style", "tab") => settings.indent_style = Some(IndentStyle::Tab),
+            ("indent_size", "tab") => settings.indent_width = tab_width,
+            ("indent_size", size) => settings.indent_width = size.parse().ok(),
+            ("tab_width", width) => tab_width = width.parse().ok(),
+            ("max_line_length", "off") => settings.max_width = None,
+            ("max_line_length", width) => settings.max_width = width.parse().ok(),
+            _ => (),
+        }
+    }
+    Ok(settings)
+}
+
+struct EditorConfig {
+    root: bool,
+    // セクションのglobとそのプロパティ
+    sections: Vec<(String, Vec<(String, String)>)>,
+}
+
+fn parse(text: &str) -> EditorConfig {
+    let mut editorconfig = EditorConfig {
+        root: false,
+        sections: Vec::new(),
+    };
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with(['#', ';']) {
+            continue;
+        }
+        if let Some(glob) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            editorconfig.sections.push((glob.to_string(), Vec::new()));
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim().to_ascii_lowercase();
+        match editorconfig.sections.last_mut() {
+            Some((_, properties)) => properties.push((key, value)),
+            // 最初のセクションより前はrootのみ有効
+            None => editorconfig.root |= key == "root" && value == "true",
+        }
+    }
+    editorconfig
+}
+
+// "/"を含まないglobはどの階層のファイル名にも一致する
+fn matches(glob: &str, relative: &Path) -> bool {
+    let glob = match glob.contains('/') {
+        true => glob.trim_start_matches('/').to_string(),
+        false => format!("**/{}", glob),
+    };
+    let options = MatchOptions {
+        require_literal_separator: true,
+        ..MatchOptions::new()
+    };
+    expand_braces(&glob).iter().any(|glob| {
+        Pattern::new(glob).is_ok_and(|pattern| pattern.matches_path_with(relative, options))
+    })
+}
+
+// "*.{jtml,html}" を "*.jtml", "*.html" に展開する
+fn expand_braces(glob: &str) -> Vec<String> {
+    let (Some(start), Some(end)) = (glob.find('{'), glob.find('}')) else {
+        return vec![glob.to_string()];
+    };
+    if end < start {
+        return vec![glob.to_string()];
+    }
+    glob[start + 1..end]
+        .split(',')
+        .flat_map(|choice| {
+            expand_braces(&format!("{}{}{}", &glob[..start], choice, &glob[end + 1..]))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::Path};
+
+    use crate::formatter::config::IndentStyle;
+
+    use super::{expand_braces, matches, settings};
+
+    #[test]
+    fn glob() {
+        assert!(matches("*", Path::new("pages/index.jtml")));
+        assert!(matches("*.jtml", Path::new("index.jtml")));
+        assert!(matches("*.{html,jtml}", Path::new("pages/index.jtml")));
+        assert!(!matches("*.html", Path::new("index.jtml")));
+        assert!(matches("pages/*.jtml", Path::new("pages/index.jtml")));
+        assert!(!matches("/*.jtml", Path::new("pages/index.jtml")));
+        assert_eq!(
+            expand_braces("{a,b}.{c,d}"),
+            vec!["a.c", "a.d", "b.c", "b.d"]
+        );
+    }
+
+    #[test]
+    fn nested_files() {
+        let dir = std::env::temp_dir().join(format!("jtml-editorconfig-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("pages")).unwrap();
+        fs::write(
+            dir.join(".editorconfig"),
+            "root = true\n\n[*]\nindent_style = space\nindent_size = 2\nmax_line_length = 80\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("pages/.editorconfig"),
+            "# pages\n[*.jtml]\nindent_size = 3\n",
+        )
+        .unwrap();
+
+        let settings = settings(&dir.join("pages/index.jtml")).unwrap();
+        assert_eq!(settings.indent_style, Some(IndentStyle::Space));
+        assert_eq!(settings.indent_width, Some(3));
+        assert_eq!(settings.max_width, Some(80));
+
+        let settings = super::settings(&dir.join("index.jtml")).unwrap();
+        assert_eq!(settings.indent_width, Some(2));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
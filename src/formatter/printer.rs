@@ -0,0 +1,442 @@
+use crate::{
+    jtml_lexer::{JtmlToken, Kind, Selector, Token},
+    jtml_parser::{
+        cst::{SyntaxElement, SyntaxKind, SyntaxNode},
+        is_self_terminating_tag, DEFINE_TAG,
+    },
+};
+
+use super::{
+    doc::{self, Doc},
+    FormatConfig, SelectorStyle,
+};
+
+// CSTから整形済みのjtmlを作る
+// コメントは属性リストの中も含めて全て残し、空行は一行までにまとめて残す
+pub(super) fn print(document: &SyntaxNode, source: &str, config: &FormatConfig) -> String {
+    let printer = Printer { source, config };
+    doc::lines(printer.items(&document.children)).render(0, config)
+}
+
+struct Printer<'a> {
+    source: &'a str,
+    config: &'a FormatConfig,
+}
+
+// 要素名の書き方を揃えるために属性リストに加える変更
+#[derive(Default)]
+struct Rewrite<'n> {
+    // 先頭に加える属性
+    leading: Vec<String>,
+    // 書かれているclass属性の値の前に加えるclass
+    classes: Option<String>,
+    // 要素名に移した属性
+    removed: Vec<&'n SyntaxNode>,
+}
+
+impl<'a> Printer<'a> {
+    fn text(&self, token: &Token) -> &'a str {
+        &self.source[token.span.range()]
+    }
+
+    // 行末までがコメントになるため、囲んでいるグループは必ず改行させる
+    fn comment(&self, token: &Token) -> Doc {
+        let text = match &token.token {
+            JtmlToken::Comment(text) => format!("// {}", text).trim_end().to_string(),
+            _ => self.text(token).to_string(),
+        };
+        Doc::concat(vec![Doc::text(text), Doc::BreakParent])
+    }
+
+    // 後ろに同じ行のコメントを付ける
+    fn trailing_comment(&self, item: &mut Doc, token: &Token) {
+        let commented = Doc::concat(vec![
+            std::mem::replace(item, Doc::Nil),
+            Doc::text(" "),
+            self.comment(token),
+        ]);
+        *item = commented;
+    }
+
+    // ドキュメントや本体の中身を並べる。空行はNilになる
+    fn items(&self, children: &[SyntaxElement]) -> Vec<Doc> {
+        let mut items: Vec<Doc> = Vec::new();
+        // 直前の項目からの改行の数
+        let mut newlines = 0;
+        for child in children {
+            let item = match child {
+                SyntaxElement::Token(token) => match &token.token {
+                    JtmlToken::Whitespace => {
+                        newlines += self.text(token).matches('\n').count();
+                        continue;
+                    }
+                    JtmlToken::LeftBracket | JtmlToken::RightBracket => continue,
+                    JtmlToken::Comment(_) if self.config.ignore_comment => {
+                        // コメントの行の改行を数えないようにする
+                        newlines = newlines.saturating_sub(1);
+                        continue;
+                    }
+                    // 前の項目と同じ行にあるコメントはその行の末尾に置く
+                    JtmlToken::Comment(_) if newlines == 0 && !items.is_empty() => {
+                        if let Some(last) = items.last_mut() {
+                            self.trailing_comment(last, token);
+                        }
+                        continue;
+                    }
+                    JtmlToken::Comment(_) => self.comment(token),
+                    _ => Doc::text(self.text(token)),
+                },
+                SyntaxElement::Node(node) => match node.kind {
+                    SyntaxKind::Element => self.element(node),
+                    _ => Doc::text(node.text(self.source)),
+                },
+            };
+            if newlines >= 2 && !items.is_empty() {
+                items.push(Doc::Nil);
+            }
+            items.push(item);
+            newlines = 0;
+        }
+        items
+    }
+
+    fn element(&self, element: &SyntaxNode) -> Doc {
+        let mut docs = Vec::new();
+        // 名前と'('の間などにあるコメントは要素の前の行に置く
+        for child in &element.children {
+            if let SyntaxElement::Token(token) = child {
+                if token.kind() == Kind::Comment && !self.config.ignore_comment {
+                    docs.push(self.comment(token));
+                    docs.push(Doc::HardLine);
+                }
+            }
+        }
+        let attributes = element.child_node(SyntaxKind::AttributeList);
+        let mut rewrite = Rewrite::default();
+        let name = element
+            .child_token(Kind::Identifier)
+            .or_else(|| element.child_token(Kind::Selector));
+        let mut tag_name = None;
+        // `define name`の二つ目の識別子
+        let component = element
+            .children
+            .iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Token(token) if token.kind() == Kind::Identifier => Some(token),
+                _ => None,
+            })
+            .nth(1);
+        if let Some(component) = component {
+            docs.push(Doc::text(format!(
+                "{} {}",
+                DEFINE_TAG,
+                self.text(component)
+            )));
+        } else if let Some(name) = name {
+            let (text, selector) = self.name(name, attributes, &mut rewrite);
+            docs.push(Doc::text(text));
+            tag_name = Some(selector.tag_name().to_string());
+        }
+        if let Some(attributes) = attributes {
+            docs.push(self.attributes(attributes, &rewrite));
+        }
+        if let Some(body) = element.child_node(SyntaxKind::Body) {
+            let items = self.items(&body.children);
+            // 空要素の空の'{}'は省略する
+            let void = tag_name.is_some_and(|name| is_self_terminating_tag(&name));
+            if !(void && items.is_empty()) {
+                docs.push(doc::body(items));
+            }
+        }
+        Doc::concat(docs)
+    }
+
+    // 設定に応じてclass, idを要素名と属性リストの間で移す
+    fn name<'n>(
+        &self,
+        name: &Token,
+        attributes: Option<&'n SyntaxNode>,
+        rewrite: &mut Rewrite<'n>,
+    ) -> (String, Selector) {
+        let mut selector = match &name.token {
+            JtmlToken::Selector(selector) => selector.clone(),
+            _ => Selector::new(self.text(name)),
+        };
+        let attribute_nodes = attributes
+            .into_iter()
+            .flat_map(|list| list.child_nodes())
+            .filter(|node| node.kind == SyntaxKind::Attribute);
+        match self.config.selector_style {
+            SelectorStyle::Preserve => return (self.text(name).to_string(), selector),
+            SelectorStyle::Attributes => {
+                let expanded = Selector::new(selector.tag_name());
+                if let Some(id) = &selector.id {
+                    rewrite.leading.push(format!("id=\"{}\"", id));
+                }
+                if !selector.classes.is_empty() {
+                    let classes = selector.classes.join(" ");
+                    let mut attribute_nodes = attribute_nodes;
+                    if attribute_nodes.any(|node| attribute_value(node, "class").is_some()) {
+                        rewrite.classes = Some(classes);
+                    } else {
+                        rewrite.leading.push(format!("class=\"{}\"", classes));
+                    }
+                }
+                return (expanded.to_string(), expanded);
+            }
+            SelectorStyle::Shorthand => {
+                for node in attribute_nodes {
+                    // コメントを含む属性は移さない
+                    if node.child_token(Kind::Comment).is_some() {
+                        continue;
+                    }
+                    let absorbed = ["id", "class"].iter().any(|name| {
+                        attribute_value(node, name)
+                            .is_some_and(|value| selector.absorb(name, value))
+                    });
+                    if absorbed {
+                        rewrite.removed.push(node);
+                    }
+                }
+            }
+        }
+        (selector.to_string(), selector)
+    }
+
+    fn attributes(&self, list: &SyntaxNode, rewrite: &Rewrite) -> Doc {
+        // 属性とコメントを一つずつ並べたもの
+        let mut entries: Vec<Doc> = rewrite.leading.iter().map(Doc::text).collect();
+        let mut attribute_count = entries.len();
+        // 最初のclass属性にだけ加える
+        let mut classes = rewrite.classes.as_deref();
+        let mut has_comment = false;
+        let mut newlines = 0;
+        for child in &list.children {
+            match child {
+                SyntaxElement::Token(token) => match &token.token {
+                    JtmlToken::Whitespace => newlines += self.text(token).matches('\n').count(),
+                    JtmlToken::Comment(_) if self.config.ignore_comment => (),
+                    JtmlToken::Comment(_) => {
+                        has_comment = true;
+                        match entries.last_mut() {
+                            Some(last) if newlines == 0 => self.trailing_comment(last, token),
+                            _ => entries.push(self.comment(token)),
+                        }
+                        newlines = 0;
+                    }
+                    _ => (),
+                },
+                SyntaxElement::Node(node)
+                    if rewrite.removed.iter().any(|n| std::ptr::eq(*n, node)) =>
+                {
+                    newlines = 0;
+                }
+                SyntaxElement::Node(node) => {
+                    let (mut attribute, comments) = self.attribute(node);
+                    if let (Some(prefix), Some(value)) =
+                        (classes, node.child_token(Kind::StringLiteral))
+                    {
+                        if attribute_value(node, "class").is_some() {
+                            attribute = class_attribute(self.text(value), prefix);
+                            classes = None;
+                        }
+                    }
+                    entries.push(attribute);
+                    attribute_count += 1;
+                    if !comments.is_empty() {
+                        has_comment = true;
+                        entries.extend(comments);
+                    }
+                    newlines = 0;
+                }
+            }
+        }
+        doc::attribute_list(entries, attribute_count, has_comment, self.config)
+    }
+
+    // 属性と、その途中に書かれたコメント
+    fn attribute(&self, attribute: &SyntaxNode) -> (Doc, Vec<Doc>) {
+        let mut text = String::new();
+        let mut comments = Vec::new();
+        for token in attribute.tokens() {
+            match token.token {
+                JtmlToken::Whitespace => (),
+                JtmlToken::Comment(_) if self.config.ignore_comment => (),
+                JtmlToken::Comment(_) => comments.push(self.comment(token)),
+                _ => text.push_str(self.text(token)),
+            }
+        }
+        (Doc::text(text), comments)
+    }
+}
+
+// nameという名前の属性であれば、その値
+fn attribute_value<'n>(attribute: &'n SyntaxNode, name: &str) -> Option<&'n str> {
+    match &attribute.child_token(Kind::Identifier)?.token {
+        JtmlToken::Identifier(identifier) if identifier == name => (),
+        _ => return None,
+    }
+    match &attribute.child_token(Kind::StringLiteral)?.token {
+        JtmlToken::StringLiteral(value) => Some(value),
+        _ => None,
+    }
+}
+
+// 文字列リテラルのまま、値の前にclassesを加える
+fn class_attribute(literal: &str, classes: &str) -> Doc {
+    let rest = &literal[1..];
+    let separator = if rest == "\"" { "" } else { " " };
+    Doc::text(format!("class=\"{}{}{}", classes, separator, rest))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        formatter::{format_with_config, FormatConfig, SelectorStyle},
+        html_converter::HtmlConverterError,
+    };
+
+    fn format(source: &str) -> Result<String, HtmlConverterError> {
+        format_with_config(source.to_string(), &FormatConfig::default())
+    }
+
+    #[test]
+    fn blank_lines() {
+        let result =
+            format("\n\np(){}\n\n\n\ndiv(){\n\n    \"a\"\n\n    \"b\"\n    \"c\"\n\n}\n").unwrap();
+        assert_eq!(
+            result,
+            "p(){}\n\ndiv(){\n    \"a\"\n\n    \"b\"\n    \"c\"\n}"
+        );
+    }
+
+    #[test]
+    fn comments() {
+        let result = format(
+            "// head\np(){ // after brace\n    \"a\" // trailing\n}  // after element\n//last",
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            "// head\np(){\n    // after brace\n    \"a\" // trailing\n} // after element\n// last"
+        );
+    }
+
+    #[test]
+    fn comment_in_attributes() {
+        let result =
+            format("img(// required\nalt=\"\" src=\"a.png\" // path\n  width=\"10\")").unwrap();
+        assert_eq!(
+            result,
+            "img(\n    // required\n    alt=\"\"\n    src=\"a.png\" // path\n    width=\"10\"\n)"
+        );
+    }
+
+    #[test]
+    fn comments_between_tokens() {
+        // 名前と'('、')'と'{'の間のコメントは要素の前の行に、'='の前後のコメントは属性の後に置く
+        assert_eq!(
+            format("p // a\n(b // b\n= // c\n\"1\") // d\n{\"x\"}").unwrap(),
+            "// a\n// d\np(\n    b=\"1\"\n    // b\n    // c\n){\"x\"}"
+        );
+        assert_eq!(
+            format("p(a=\"1\") // c\n{\"x\"}").unwrap(),
+            "// c\np(a=\"1\"){\"x\"}"
+        );
+        assert_eq!(format("p // a\n(){}").unwrap(), "// a\np(){}");
+        assert_eq!(
+            format("p(a= // b\n\"1\"){}").unwrap(),
+            "p(\n    a=\"1\"\n    // b\n){}"
+        );
+    }
+
+    #[test]
+    fn remove_comments() {
+        let config = FormatConfig {
+            ignore_comment: true,
+            ..FormatConfig::default()
+        };
+        let result = format_with_config(
+            "p(){}\n// removed\ndiv(a=\"b\" // removed\n){}\n\n// removed\nspan(){}".to_string(),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(result, "p(){}\ndiv(a=\"b\"){}\n\nspan(){}");
+    }
+
+    #[test]
+    fn width() {
+        let config = FormatConfig {
+            max_width: 30,
+            ..FormatConfig::default()
+        };
+        let format = |source: &str| format_with_config(source.to_string(), &config).unwrap();
+        assert_eq!(
+            format("li(){a(href=\"/\"){\"Home\"}}"),
+            "li(){a(href=\"/\"){\"Home\"}}"
+        );
+        // 本体が収まらない場合は子を次の行に置く
+        assert_eq!(
+            format("p(class=\"lead\"){\"a long sentence\"}"),
+            "p(class=\"lead\"){\n    \"a long sentence\"\n}"
+        );
+        // 属性が収まらない場合は一行に一つずつ並べる
+        assert_eq!(
+            format("div(class=\"container\" id=\"main\"){}"),
+            "div(\n    class=\"container\"\n    id=\"main\"\n){}"
+        );
+    }
+
+    #[test]
+    fn void_element_body() {
+        // 空の'{}'は省略し、コメントがある場合は残す
+        let result = format("meta(charset=\"UTF-8\"){}\nbr(){ }\nhr(){ // c\n}").unwrap();
+        assert_eq!(result, "meta(charset=\"UTF-8\")\nbr()\nhr(){\n    // c\n}");
+    }
+
+    #[test]
+    fn boolean_attributes() {
+        let result = format("input( type=\"checkbox\"   checked // c\n disabled )").unwrap();
+        assert_eq!(
+            result,
+            "input(\n    type=\"checkbox\"\n    checked // c\n    disabled\n)"
+        );
+    }
+
+    #[test]
+    fn selector_style() {
+        let source = "div.card#main(class=\"x\" title=\"t\"){\n    p(\n        id=\"a\"\n        class=\"b c\" // c\n    ){}\n    .d(){}\n    span(class=\"a:b\"){}\n}";
+        let format = |selector_style| {
+            let config = FormatConfig {
+                selector_style,
+                ..FormatConfig::default()
+            };
+            format_with_config(source.to_string(), &config).unwrap()
+        };
+        assert_eq!(format(SelectorStyle::Preserve), source);
+        // コメントは残し、classに使えない文字を含む値は属性のままにする
+        assert_eq!(
+            format(SelectorStyle::Shorthand),
+            ".card.x#main(title=\"t\"){\n    p.b.c#a(\n        // c\n    ){}\n    .d(){}\n    span(class=\"a:b\"){}\n}"
+        );
+        assert_eq!(
+            format(SelectorStyle::Attributes),
+            "div(id=\"main\" class=\"card x\" title=\"t\"){\n    p(\n        id=\"a\"\n        class=\"b c\" // c\n    ){}\n    div(class=\"d\"){}\n    span(class=\"a:b\"){}\n}"
+        );
+    }
+
+    #[test]
+    fn define() {
+        let source = "define   card(title){div(){slot()}}\ncard(title=\"a\"){}";
+        assert_eq!(
+            format_with_config(source.to_string(), &FormatConfig::default()).unwrap(),
+            "define card(title){div(){slot()}}\ncard(title=\"a\"){}"
+        );
+    }
+
+    #[test]
+    fn keep_escapes() {
+        let result = format(r#"p(title="\u{41}\"b\""){"a\tb\\"}"#).unwrap();
+        assert_eq!(result, "p(title=\"\\u{41}\\\"b\\\"\"){\"a\\tb\\\\\"}");
+    }
+}
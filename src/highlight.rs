@@ -0,0 +1,176 @@
+use serde_json::{json, Value};
+
+use crate::{
+    jtml_lexer::{Kind, Token},
+    span::Span,
+};
+
+// JtmlTokenの正規表現と同じもの。ずれていないことはテストで確かめる
+pub const STRING_PATTERN: &str = r#""([^"\\]|\\[\s\S])*""#;
+pub const ESCAPE_PATTERN: &str = r#"\\[\s\S]"#;
+pub const COMMENT_PATTERN: &str = r#"//.*"#;
+pub const IDENTIFIER_PATTERN: &str = r#"[0-9A-Za-z\-]+"#;
+pub const SELECTOR_PATTERN: &str = r#"[0-9A-Za-z\-]*([.#][0-9A-Za-z\-]+)+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightKind {
+    Tag,
+    AttributeName,
+    AttributeValue,
+    Text,
+    Comment,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Highlight {
+    pub kind: HighlightKind,
+    pub span: Span,
+}
+
+// トークンの種類と、属性リスト('('から')'まで)の中かどうかで色分けする
+pub fn highlight(tokens: &[Token]) -> Vec<Highlight> {
+    let mut in_attributes = false;
+    let mut highlights = Vec::new();
+    for token in tokens {
+        let kind = match token.kind() {
+            Kind::Identifier if in_attributes => HighlightKind::AttributeName,
+            Kind::Identifier | Kind::Selector => HighlightKind::Tag,
+            Kind::StringLiteral if in_attributes => HighlightKind::AttributeValue,
+            Kind::StringLiteral => HighlightKind::Text,
+            Kind::Comment => HighlightKind::Comment,
+            Kind::LeftParen => {
+                in_attributes = true;
+                continue;
+            }
+            Kind::RightParen | Kind::LeftBracket | Kind::RightBracket => {
+                in_attributes = false;
+                continue;
+            }
+            Kind::Equal | Kind::Whitespace => continue,
+        };
+        highlights.push(Highlight {
+            kind,
+            span: token.span,
+        });
+    }
+    highlights
+}
+
+fn string(name: &str) -> Value {
+    json!({
+        "name": name,
+        "begin": "\"",
+        "end": "\"",
+        "beginCaptures": { "0": { "name": "punctuation.definition.string.begin.jtml" } },
+        "endCaptures": { "0": { "name": "punctuation.definition.string.end.jtml" } },
+        "patterns": [{ "name": "constant.character.escape.jtml", "match": ESCAPE_PATTERN }],
+    })
+}
+
+// VS Code向けのTextMate文法。highlightと同じ規則で色分けする
+// 文字列は複数行にまたがれるようにbegin/endで表す
+pub fn textmate_grammar() -> Value {
+    json!({
+        "$schema": "https://raw.githubusercontent.com/martinring/tmlanguage/master/tmlanguage.json",
+        "name": "jtml",
+        "scopeName": "source.jtml",
+        "fileTypes": ["jtml"],
+        "patterns": [
+            { "include": "#comment" },
+            { "include": "#attributes" },
+            { "include": "#body" },
+            { "include": "#selector" },
+            { "include": "#tag" },
+            { "include": "#text" },
+        ],
+        "repository": {
+            "comment": { "name": "comment.line.double-slash.jtml", "match": COMMENT_PATTERN },
+            "tag": { "name": "entity.name.tag.jtml", "match": IDENTIFIER_PATTERN },
+            "selector": { "name": "entity.name.tag.jtml", "match": SELECTOR_PATTERN },
+            "text": string("string.quoted.double.jtml"),
+            "attributes": {
+                "begin": "\\(",
+                "end": "\\)",
+                "beginCaptures": { "0": { "name": "punctuation.section.parens.begin.jtml" } },
+                "endCaptures": { "0": { "name": "punctuation.section.parens.end.jtml" } },
+                "patterns": [
+                    { "include": "#comment" },
+                    { "name": "entity.other.attribute-name.jtml", "match": IDENTIFIER_PATTERN },
+                    { "name": "punctuation.separator.key-value.jtml", "match": "=" },
+                    string("string.quoted.double.attribute-value.jtml"),
+                ],
+            },
+            "body": {
+                "begin": "\\{",
+                "end": "\\}",
+                "beginCaptures": { "0": { "name": "punctuation.section.block.begin.jtml" } },
+                "endCaptures": { "0": { "name": "punctuation.section.block.end.jtml" } },
+                "patterns": [{ "include": "$self" }],
+            },
+        },
+    })
+}
+
+// `jtml grammar`が出力し、syntaxes/jtml.tmLanguage.jsonに保存する文字列
+pub fn textmate_grammar_file() -> String {
+    let grammar = serde_json::to_string_pretty(&textmate_grammar()).expect("grammar is valid json");
+    format!("{}\n", grammar)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        highlight, textmate_grammar_file, HighlightKind, COMMENT_PATTERN, ESCAPE_PATTERN,
+        IDENTIFIER_PATTERN, SELECTOR_PATTERN, STRING_PATTERN,
+    };
+    use crate::jtml_lexer::lossless_lexer;
+
+    #[test]
+    fn patterns_match_lexer() {
+        let lexer = include_str!("jtml_lexer.rs");
+        for pattern in [
+            STRING_PATTERN,
+            COMMENT_PATTERN,
+            IDENTIFIER_PATTERN,
+            SELECTOR_PATTERN,
+        ] {
+            assert!(
+                lexer.contains(&format!("#[regex(r#\"{}\"#", pattern)),
+                "{} is not a token regex",
+                pattern
+            );
+        }
+        assert!(STRING_PATTERN.contains(ESCAPE_PATTERN));
+    }
+
+    #[test]
+    fn grammar_is_up_to_date() {
+        assert_eq!(
+            include_str!("../syntaxes/jtml.tmLanguage.json"),
+            textmate_grammar_file(),
+            "run `jtml grammar > syntaxes/jtml.tmLanguage.json`"
+        );
+    }
+
+    #[test]
+    fn kinds() {
+        let source = "// c\ndiv(id=\"a\"){\"b\" br() .c(){}}";
+        let tokens = lossless_lexer(source).unwrap();
+        let highlights = highlight(&tokens)
+            .iter()
+            .map(|highlight| (highlight.kind, &source[highlight.span.range()]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            highlights,
+            vec![
+                (HighlightKind::Comment, "// c"),
+                (HighlightKind::Tag, "div"),
+                (HighlightKind::AttributeName, "id"),
+                (HighlightKind::AttributeValue, "\"a\""),
+                (HighlightKind::Text, "\"b\""),
+                (HighlightKind::Tag, "br"),
+                (HighlightKind::Tag, ".c"),
+            ]
+        );
+    }
+}
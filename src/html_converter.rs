@@ -1,29 +1,125 @@
-use std::collections::VecDeque;
-
 mod convert;
 mod errors;
-use crate::{jtml_lexer::lexer, jtml_parser::parse};
+mod escape;
+mod minify;
+mod options;
+mod output_mode;
+mod pretty;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    jtml_lexer::lexer,
+    jtml_parser::{parse_recovering, AstRoot},
+    template::{self, Context},
+    transformer::{self, Resolver},
+};
 pub use convert::Convert;
 pub use errors::HtmlConverterError;
+pub use escape::{escape_attribute, escape_raw_text, escape_text, is_raw_text_element};
+pub(crate) use minify::{
+    closes_paragraph, collapse_whitespace, is_unquotable, minify_raw_text, next_output,
+    omits_end_tag,
+};
+pub use options::{HtmlOptions, Minify};
+pub use output_mode::OutputMode;
+pub(crate) use pretty::{has_block, is_inline, is_whitespace_sensitive, lines};
 
 pub fn convert(jtml: String, ignore_comment: bool) -> Result<String, HtmlConverterError> {
-    let mut tokens = VecDeque::from(match lexer(jtml) {
+    convert_with_options(
+        jtml,
+        &HtmlOptions {
+            ignore_comment,
+            ..HtmlOptions::default()
+        },
+    )
+}
+
+pub fn convert_with_options(
+    jtml: String,
+    options: &HtmlOptions,
+) -> Result<String, HtmlConverterError> {
+    let ast = template::render_without_context(&parse(jtml)?)
+        .map_err(HtmlConverterError::TemplateError)?;
+    Ok(ast.to_html(options))
+}
+
+// 文字列リテラルと属性値の中の`{{ name }}`をcontextの値で置き換えてから変換する
+// 値はHTMLに出力する際にエスケープされる
+pub fn convert_with_context(
+    jtml: String,
+    options: &HtmlOptions,
+    context: &Context,
+) -> Result<String, HtmlConverterError> {
+    let ast =
+        template::render(&parse(jtml)?, context).map_err(HtmlConverterError::TemplateError)?;
+    Ok(ast.to_html(options))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Converted {
+    pub html: String,
+    // include, importで読み込んだファイル。これらが変わった場合は変換し直す
+    pub dependencies: Vec<PathBuf>,
+}
+
+// pathにあるjtmlとして、include, importをresolverで読み込んでから変換する
+// contextがない場合、制御構文はエラーになる
+pub fn convert_file(
+    jtml: String,
+    path: &Path,
+    resolver: &dyn Resolver,
+    options: &HtmlOptions,
+    context: Option<&Context>,
+) -> Result<Converted, HtmlConverterError> {
+    let transformed = transformer::transform_file(&parse_source(jtml)?, path, resolver)
+        .map_err(HtmlConverterError::TransformError)?;
+    let ast = match context {
+        Some(context) => template::render(&transformed.ast, context),
+        None => template::render_without_context(&transformed.ast),
+    }
+    .map_err(|errors| {
+        HtmlConverterError::TemplateError(
+            errors
+                .into_iter()
+                .map(|error| error.in_included(&transformed.sources))
+                .collect(),
+        )
+    })?;
+    Ok(Converted {
+        html: ast.to_html(options),
+        dependencies: transformed.dependencies,
+    })
+}
+
+fn parse(jtml: String) -> Result<AstRoot, HtmlConverterError> {
+    transformer::transform(&parse_source(jtml)?).map_err(HtmlConverterError::TransformError)
+}
+
+fn parse_source(jtml: String) -> Result<AstRoot, HtmlConverterError> {
+    let tokens = match lexer(jtml) {
         Ok(tokens) => tokens,
         Err(e) => {
             return Err(HtmlConverterError::LexerError(e));
         }
-    });
-
-    let ast = match parse(&mut tokens) {
-        Ok(ast) => ast,
-        Err(e) => return Err(HtmlConverterError::ParseError(e)),
     };
-    Ok(ast.to_html(ignore_comment))
+
+    let (ast, errors) = parse_recovering(&tokens);
+    if !errors.is_empty() {
+        return Err(HtmlConverterError::ParseError(errors));
+    }
+    Ok(ast)
 }
 
 #[cfg(test)]
 mod test {
-    use super::convert;
+    use std::collections::HashMap;
+
+    use crate::formatter::Tab;
+
+    use super::{
+        convert, convert_file, convert_with_context, convert_with_options, Context, HtmlOptions,
+        Minify, OutputMode,
+    };
 
     #[test]
     fn single_simple_element() {
@@ -32,7 +128,7 @@ mod test {
         assert_eq!(result.unwrap(), "<p></p>".to_string());
 
         let result = convert("img()".to_string(), false);
-        assert_eq!(result.unwrap(), "<img/>".to_string());
+        assert_eq!(result.unwrap(), "<img>".to_string());
 
         let result = convert("\"string literal\"".to_string(), false);
         assert_eq!(result.unwrap(), "string literal".to_string());
@@ -41,6 +137,344 @@ mod test {
         assert_eq!(result.unwrap(), "<!--comment-->".to_string());
     }
 
+    #[test]
+    fn output_mode() {
+        let source = r#"p(){br() wbr(){}} svg(){circle(r="1"){} foreignObject(){br() p(){}}}"#;
+        let html = |mode| {
+            let options = HtmlOptions {
+                mode,
+                ..HtmlOptions::default()
+            };
+            convert_with_options(source.to_string(), &options).unwrap()
+        };
+        assert_eq!(
+            html(OutputMode::Html5),
+            r#"<p><br><wbr></p><svg><circle r="1"></circle><foreignObject><br><p></p></foreignObject></svg>"#
+        );
+        assert_eq!(
+            html(OutputMode::Xhtml),
+            r#"<p><br /><wbr /></p><svg><circle r="1" /><foreignObject><br /><p /></foreignObject></svg>"#
+        );
+        assert_eq!(
+            html(OutputMode::Polyglot),
+            r#"<p><br /><wbr /></p><svg><circle r="1" /><foreignObject><br /><p></p></foreignObject></svg>"#
+        );
+    }
+
+    #[test]
+    fn boolean_attribute() {
+        let source = r#"input(type="checkbox" checked)"#;
+        let html = |mode| {
+            let options = HtmlOptions {
+                mode,
+                ..HtmlOptions::default()
+            };
+            convert_with_options(source.to_string(), &options).unwrap()
+        };
+        assert_eq!(
+            html(OutputMode::Html5),
+            r#"<input type="checkbox" checked>"#
+        );
+        assert_eq!(
+            html(OutputMode::Xhtml),
+            r#"<input type="checkbox" checked="checked" />"#
+        );
+        assert_eq!(
+            html(OutputMode::Polyglot),
+            r#"<input type="checkbox" checked="" />"#
+        );
+    }
+
+    #[test]
+    fn pretty() {
+        let source = r#"// top
+html(){ head(){ title(){"t"} } body(){ ul(){ li(){ a(href="/"){"home"} " " b(){"x"} } } div(){"a" p(){"b"} pre(){"  c\n d"}} a(){ div(){"e"} } } }"#;
+        let options = HtmlOptions {
+            pretty: Some(Tab::Spaces(2)),
+            ..HtmlOptions::default()
+        };
+        assert_eq!(
+            convert_with_options(source.to_string(), &options).unwrap(),
+            r#"<!--top-->
+<html>
+  <head>
+    <title>t</title>
+  </head>
+  <body>
+    <ul>
+      <li><a href="/">home</a> <b>x</b></li>
+    </ul>
+    <div>
+      a
+      <p>b</p>
+      <pre>  c
+ d</pre>
+    </div>
+    <a><div>e</div></a>
+  </body>
+</html>"#
+        );
+        let options = HtmlOptions {
+            ignore_comment: true,
+            pretty: Some(Tab::Tabs),
+            ..HtmlOptions::default()
+        };
+        assert_eq!(
+            convert_with_options("// a\ndiv(){p(){}}".to_string(), &options).unwrap(),
+            "<div>\n\t<p></p>\n</div>"
+        );
+        assert_eq!(
+            convert_with_options(
+                r#"main(){ section(){ h1(){"a"} p(){"b"} } }"#.to_string(),
+                &options
+            )
+            .unwrap(),
+            "<main>\n\t<section>\n\t\t<h1>a</h1>\n\t\t<p>b</p>\n\t</section>\n</main>"
+        );
+
+        // pre, textareaと行内の要素の中は、子孫のブロック要素も字下げしない
+        let options = HtmlOptions {
+            pretty: Some(Tab::Spaces(2)),
+            ..HtmlOptions::default()
+        };
+        assert_eq!(
+            convert_with_options(
+                r#"body(){ pre(){ div(){ p(){"x"} p(){"y"} } } textarea(){ div(){ p(){"z"} } } a(){ div(){ p(){"x"} p(){"y"} } } }"#.to_string(),
+                &options
+            )
+            .unwrap(),
+            "<body>\n  <pre><div><p>x</p><p>y</p></div></pre>\n  <textarea><div><p>z</p></div></textarea><a><div><p>x</p><p>y</p></div></a>\n</body>"
+        );
+    }
+
+    #[test]
+    fn minify() {
+        let source = r#"// top
+html(){ head(){ title(){"a   b"} style(){"p {\n  color: red;\n}"} } body(){ ul(class="list" id=""){ li(){"x"} li(){ p(){"y"} } } p(){"  one\n  two "} div(){} table(){ tr(){ td(){"1"} td(){"2"} } tr(){ td(){"3"} } } pre(){"  keep\n  this"} svg(){ g(){} } } }"#;
+        let options = HtmlOptions {
+            minify: Some(Minify::default()),
+            ..HtmlOptions::default()
+        };
+        assert_eq!(
+            convert_with_options(source.to_string(), &options).unwrap(),
+            r#"<html><head><title>a b</title><style>p {
+  color: red;
+}</style></head><body><ul class=list id=""><li>x<li><p>y</p></ul><p> one two <div></div><table><tr><td>1<td>2</td><tr><td>3</td></table><pre>  keep
+  this</pre><svg><g></g></svg>"#
+        );
+
+        // style, scriptの中身も縮める
+        let options = HtmlOptions {
+            minify: Some(Minify { inline_code: true }),
+            pretty: Some(Tab::Spaces(2)),
+            ..HtmlOptions::default()
+        };
+        assert_eq!(
+            convert_with_options(
+                r#"style(){"p {\n  color: red;\n}"} script(){"\n  a()\n  b()\n"}"#.to_string(),
+                &options
+            )
+            .unwrap(),
+            "<style>p{color:red}</style><script>a()\nb()</script>"
+        );
+
+        // xhtmlとpolyglotでは引用符と終了タグを省かない
+        let options = HtmlOptions {
+            mode: OutputMode::Xhtml,
+            minify: Some(Minify::default()),
+            ..HtmlOptions::default()
+        };
+        assert_eq!(
+            convert_with_options(
+                r#"ul(class="a"){ li(){"x  y"} } br()"#.to_string(),
+                &options
+            )
+            .unwrap(),
+            r#"<ul class="a"><li>x y</li></ul><br />"#
+        );
+    }
+
+    #[test]
+    fn minify_end_tags() {
+        let options = HtmlOptions {
+            minify: Some(Minify::default()),
+            ..HtmlOptions::default()
+        };
+        let minify = |source: &str| convert_with_options(source.to_string(), &options).unwrap();
+        assert_eq!(
+            minify(
+                r#"ul(){ li(){"a"} li(){ p(){"b"} } } dl(){ dt(){"c"} dd(){ p(){"d"} p(){"e"} } dt(){"f"} dd(){} }"#
+            ),
+            "<ul><li>a<li><p>b</p></ul><dl><dt>c<dd><p>d<p>e</p><dt>f<dd></dl>"
+        );
+        // a, videoの中の最後のpと、tableの前のpは閉じる
+        assert_eq!(
+            minify(r#"a(){ p(){"x"} } video(){ p(){"y"} } div(){ p(){"z"} " " } p(){"1"} div(){} p(){"2"} table(){}"#),
+            "<a><p>x</p></a><video><p>y</p></video><div><p>z</p> </div><p>1<div></div><p>2</p><table></table>"
+        );
+        assert_eq!(
+            minify(r#"table(){ thead(){ tr(){ th(){"h"} } } tbody(){ tr(){ td(){"1"} td(){"2"} } tr(){ td(){} } } tfoot(){ tr(){ td(){} } } }"#),
+            "<table><thead><tr><th>h</tr><tbody><tr><td>1<td>2</td><tr><td></tr><tfoot><tr><td></tr></table>"
+        );
+        assert_eq!(
+            minify(r#"select(){ optgroup(label="g"){ option(value="1" selected){"x"} option(){"y"} } optgroup(){ option(){} } } ruby(){"漢" rp(){"("} rt(){"kan"} rp(){")"} }"#),
+            "<select><optgroup label=g><option value=1 selected>x<option>y</option><optgroup><option></option></select><ruby>漢<rp>(<rt>kan<rp>)</ruby>"
+        );
+    }
+
+    #[test]
+    fn file() {
+        use crate::transformer::MemoryResolver;
+        use std::path::{Path, PathBuf};
+
+        let mut resolver = MemoryResolver::new();
+        resolver.insert("site/head.jtml", r#"title(){"{{ title }}"}"#);
+        let source = r#"head(){ include(src="head.jtml") }"#;
+        let path = Path::new("site/index.jtml");
+        let context = Context::from(HashMap::from([("title", "a & b")]));
+        let converted = convert_file(
+            source.to_string(),
+            path,
+            &resolver,
+            &HtmlOptions::default(),
+            Some(&context),
+        )
+        .unwrap();
+        assert_eq!(converted.html, "<head><title>a &amp; b</title></head>");
+        assert_eq!(
+            converted.dependencies,
+            vec![PathBuf::from("site/head.jtml")]
+        );
+
+        // contextがない場合は`{{ }}`をそのまま残す
+        let converted = convert_file(
+            source.to_string(),
+            path,
+            &resolver,
+            &HtmlOptions::default(),
+            None,
+        );
+        assert_eq!(
+            converted.unwrap().html,
+            "<head><title>{{ title }}</title></head>"
+        );
+        // インラインのスクリプトやスタイルの`{{`も書いたとおりに出力する
+        assert_eq!(
+            convert(
+                r#"script(){"if(a){{b}}"} style(){"a{{}}"}"#.to_string(),
+                false
+            )
+            .unwrap(),
+            "<script>if(a){{b}}</script><style>a{{}}</style>"
+        );
+        let error = convert(source.to_string(), false).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`include` requires the path of the file being converted"
+        );
+
+        // 読み込んだファイルの中のテンプレートのエラーは、そのファイルの中の位置と共にincludeを指す
+        resolver.insert("site/part.jtml", "p(){\n  \"a {{ missing }}\"\n}");
+        let source = "p(){}\ninclude(src=\"part.jtml\")";
+        let error = convert_file(
+            source.to_string(),
+            path,
+            &resolver,
+            &HtmlOptions::default(),
+            Some(&context),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "error in site/part.jtml:2:6: undefined variable `missing`"
+        );
+        assert_eq!(
+            error.to_diagnostics(source)[0].render("site/index.jtml", source),
+            r#"error: error in included file
+ --> site/index.jtml:2:1
+  |
+2 | include(src="part.jtml")
+  | ^^^^^^^^^^^^^^^^^^^^^^^^ site/part.jtml:2:6: undefined variable `missing`
+"#
+        );
+    }
+
+    #[test]
+    fn context() {
+        let source = r#"title(){"{{ title }} - {{ version }}"} a(href="{{ url }}"){"{{title}}"}"#;
+        let context = Context::from(HashMap::from([
+            ("title", "Tom & Jerry"),
+            ("version", "1.0"),
+            ("url", "/?a=1&b=\"2\""),
+        ]));
+        let html = convert_with_context(source.to_string(), &HtmlOptions::default(), &context);
+        assert_eq!(
+            html.unwrap(),
+            r#"<title>Tom &amp; Jerry - 1.0</title><a href="/?a=1&amp;b=&quot;2&quot;">Tom &amp; Jerry</a>"#
+        );
+
+        let error =
+            convert_with_context(source.to_string(), &HtmlOptions::default(), &Context::new())
+                .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "undefined variable `title`\nundefined variable `version`\nundefined variable `url`\nundefined variable `title`"
+        );
+        // contextなしでは制御構文を評価できない
+        let source = r#"if(cond="a"){"b"}"#;
+        let error = convert(source.to_string(), false).unwrap_err();
+        assert_eq!(error.to_string(), "`if` requires a data context");
+        let span = error.to_diagnostics(source)[0].span;
+        assert_eq!(&source[span.range()], r#"cond="a""#);
+        let context = Context::from(HashMap::from([("a", true)]));
+        assert_eq!(
+            convert_with_context(source.to_string(), &HtmlOptions::default(), &context).unwrap(),
+            "b"
+        );
+        // 変数を使わない場合はconvertと同じ結果になる
+        assert_eq!(
+            convert_with_context(
+                "p(){\"a\"}".to_string(),
+                &HtmlOptions::default(),
+                &Context::new()
+            ),
+            convert("p(){\"a\"}".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn escape() {
+        let result = convert(r#"p(title="a<b"){"a < b & c"}"#.to_string(), false).unwrap();
+        assert_eq!(result, r#"<p title="a&lt;b">a &lt; b &amp; c</p>"#);
+
+        let result = convert(
+            r#"script(){"if (a < b && c) { x = '</script>' }"}"#.to_string(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            r#"<script>if (a < b && c) { x = '<\/script>' }</script>"#
+        );
+
+        let result = convert(r#"div(){raw(){"<b>trusted</b>"}}"#.to_string(), false).unwrap();
+        assert_eq!(result, "<div><b>trusted</b></div>");
+    }
+
+    #[test]
+    fn multiple_errors() {
+        let source = "p){}\ndiv(a \"b\"){}\n}";
+        let error = convert(source.to_string(), false).unwrap_err();
+        let diagnostics = error.to_diagnostics(source);
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| d.span.start)
+                .collect::<Vec<usize>>(),
+            vec![1, 11, 18]
+        );
+    }
+
     #[test]
     fn single_element_with_attribute() {
         use super::*;
@@ -48,7 +482,7 @@ mod test {
         assert_eq!(result, "<p class=\"btn\"></p>".to_string());
 
         let result = convert("img(href=\"./images/img.png\")".to_string(), false).unwrap();
-        assert_eq!(result, "<img href=\"./images/img.png\"/>".to_string());
+        assert_eq!(result, "<img href=\"./images/img.png\">".to_string());
     }
 
     #[test]
@@ -73,7 +507,7 @@ head(){
         .unwrap();
         assert_eq!(
             result,
-            r#"<head><meta charset="UTF-8"/><meta http-equiv="X-UA-Compatible" content="IE=edge"/><title>document</title></head>"#
+            r#"<head><meta charset="UTF-8"><meta http-equiv="X-UA-Compatible" content="IE=edge"><title>document</title></head>"#
         )
     }
 
@@ -102,7 +536,7 @@ html(lang="ja"){
 
         assert_eq!(
             result,
-            r#"<html lang="ja"><head><meta charset="UTF-8"/><meta http-equiv="X-UA-Compatible" content="IE=edge"/><meta name="viewport" content="width=device-width" initial-scale="1.0"/><title>document</title></head><body><main><h1>Hello World!</h1><img hoge="hoge" huga="huga"/></main></body></html>"#
+            r#"<html lang="ja"><head><meta charset="UTF-8"><meta http-equiv="X-UA-Compatible" content="IE=edge"><meta name="viewport" content="width=device-width" initial-scale="1.0"><title>document</title></head><body><main><h1>Hello World!</h1><img hoge="hoge" huga="huga"></main></body></html>"#
                 .replace("\n", "")
                 .to_string()
         )
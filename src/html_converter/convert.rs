@@ -1,6 +1,13 @@
-use crate::formatter::FormatConfig;
+use crate::formatter::{doc::Doc, FormatConfig};
+
+use super::HtmlOptions;
 
 pub trait Convert {
-    fn to_html(&self, ignore_comment: bool) -> String;
-    fn to_jtml(&self, ignore_comment: bool, indent_depth: usize, config: &FormatConfig) -> String;
+    fn to_html(&self, options: &HtmlOptions) -> String;
+    fn to_doc(&self, ignore_comment: bool, config: &FormatConfig) -> Doc;
+
+    fn to_jtml(&self, ignore_comment: bool, indent_depth: usize, config: &FormatConfig) -> String {
+        self.to_doc(ignore_comment, config)
+            .render(indent_depth, config)
+    }
 }
@@ -1,18 +1,69 @@
 use std::fmt::{Display, Formatter, Result};
 
-use crate::{jtml_lexer::LexerError, jtml_parser::ParserError};
+use crate::{
+    diagnostics::Diagnostic, jtml_lexer::LexerError, jtml_parser::ParserError,
+    template::TemplateError, transformer::TransformError,
+};
 
 #[derive(Debug, PartialEq)]
 pub enum HtmlConverterError {
-    ParseError(ParserError),
+    // 構文エラーはファイル内の全てを保持する
+    ParseError(Vec<ParserError>),
     LexerError(LexerError),
+    // テンプレートのエラーもファイル内の全てを保持する
+    TemplateError(Vec<TemplateError>),
+    TransformError(Vec<TransformError>),
 }
 
 impl Display for HtmlConverterError {
     fn fmt(&self, f: &mut Formatter) -> Result {
         match self {
-            HtmlConverterError::ParseError(e) => write!(f, "{:?}", e),
-            HtmlConverterError::LexerError(e) => write!(f, "{:?}", e),
+            HtmlConverterError::ParseError(errors) => write!(
+                f,
+                "{}",
+                errors
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            ),
+            HtmlConverterError::LexerError(e) => write!(f, "{}", e),
+            HtmlConverterError::TransformError(errors) => write!(
+                f,
+                "{}",
+                errors
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            ),
+            HtmlConverterError::TemplateError(errors) => write!(
+                f,
+                "{}",
+                errors
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            ),
+        }
+    }
+}
+
+impl HtmlConverterError {
+    // sourceはエラーが発生したjtmlの全文
+    pub fn to_diagnostics(&self, source: &str) -> Vec<Diagnostic> {
+        match self {
+            HtmlConverterError::ParseError(errors) => {
+                errors.iter().map(|e| e.to_diagnostic(source)).collect()
+            }
+            HtmlConverterError::LexerError(e) => vec![e.to_diagnostic()],
+            HtmlConverterError::TemplateError(errors) => {
+                errors.iter().map(|e| e.to_diagnostic(source)).collect()
+            }
+            HtmlConverterError::TransformError(errors) => {
+                errors.iter().map(|e| e.to_diagnostic()).collect()
+            }
         }
     }
 }
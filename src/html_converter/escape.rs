@@ -0,0 +1,91 @@
+// HTMLに出力する際のエスケープ
+//
+// テキストは要素の内容として、属性値はダブルクォートで囲まれた値として安全な形にする
+
+pub fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub fn escape_attribute(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// 内容が文字参照として解釈されない要素
+pub fn is_raw_text_element(tag_name: &str) -> bool {
+    matches!(tag_name.to_ascii_lowercase().as_str(), "script" | "style")
+}
+
+// script, styleの内容はエスケープできないため、要素を閉じてしまう"</"の後だけを無害化する
+// ("<\/script>"はJavaScriptでもCSSでも同じ意味になる)
+pub fn escape_raw_text(tag_name: &str, text: &str) -> String {
+    let closing = format!("</{}", tag_name.to_ascii_lowercase());
+    let lower = text.to_ascii_lowercase();
+    let mut escaped = String::with_capacity(text.len());
+    let mut last = 0;
+    for (i, _) in lower.match_indices(&closing) {
+        escaped.push_str(&text[last..i + 1]);
+        escaped.push('\\');
+        last = i + 1;
+    }
+    escaped.push_str(&text[last..]);
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::{escape_attribute, escape_raw_text, escape_text, is_raw_text_element};
+
+    #[test]
+    fn text() {
+        assert_eq!(escape_text("a < b & c > d"), "a &lt; b &amp; c &gt; d");
+        assert_eq!(escape_text("\"quoted\" 'single'"), "\"quoted\" 'single'");
+        assert_eq!(escape_text("&amp;"), "&amp;amp;");
+    }
+
+    #[test]
+    fn attribute() {
+        assert_eq!(
+            escape_attribute(r#"" onclick="alert(1)"#),
+            "&quot; onclick=&quot;alert(1)"
+        );
+        assert_eq!(escape_attribute("a&b<c>"), "a&amp;b&lt;c&gt;");
+    }
+
+    #[test]
+    fn raw_text() {
+        assert!(is_raw_text_element("script"));
+        assert!(is_raw_text_element("STYLE"));
+        assert!(!is_raw_text_element("textarea"));
+        assert_eq!(
+            escape_raw_text("script", "if (a < b && c) {}"),
+            "if (a < b && c) {}"
+        );
+        assert_eq!(
+            escape_raw_text("script", "'</script><b>' + '</SCRIPT>'"),
+            "'<\\/script><b>' + '<\\/SCRIPT>'"
+        );
+        assert_eq!(
+            escape_raw_text("style", "a::after{content:'</style>'}"),
+            "a::after{content:'<\\/style>'}"
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use crate::jtml_parser::{Element, Node};
+
+use super::{HtmlOptions, OutputMode};
+
+// 連続する空白(スペース, タブ, 改行)を一つのスペースにまとめる
+pub(crate) fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut space = false;
+    for c in text.chars() {
+        if c.is_ascii_whitespace() {
+            space = true;
+            continue;
+        }
+        if space {
+            collapsed.push(' ');
+            space = false;
+        }
+        collapsed.push(c);
+    }
+    if space {
+        collapsed.push(' ');
+    }
+    collapsed
+}
+
+// エスケープ済みの属性値を引用符なしで書けるか
+pub(crate) fn is_unquotable(value: &str) -> bool {
+    !value.is_empty()
+        && !value
+            .chars()
+            .any(|c| c.is_ascii_whitespace() || matches!(c, '"' | '\'' | '=' | '<' | '>' | '`'))
+}
+
+// 開始タグが開いているpを閉じる要素
+pub(crate) fn closes_paragraph(tag_name: &str) -> bool {
+    matches!(
+        tag_name,
+        "address"
+            | "article"
+            | "aside"
+            | "blockquote"
+            | "details"
+            | "dialog"
+            | "div"
+            | "dl"
+            | "fieldset"
+            | "figcaption"
+            | "figure"
+            | "footer"
+            | "form"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+            | "header"
+            | "hgroup"
+            | "hr"
+            | "main"
+            | "menu"
+            | "nav"
+            | "ol"
+            | "p"
+            | "pre"
+            | "search"
+            | "section"
+            | "table"
+            | "ul"
+    )
+}
+
+// 縮めて出力する場合に、nodes[index]の後に何かを出力する最初のノード
+// コメントは出力しないため飛ばす
+pub(crate) fn next_output<'a>(nodes: &[&'a Node], index: usize) -> Option<&'a Node> {
+    nodes[index + 1..].iter().copied().find(|node| match node {
+        Node::Element(_) => true,
+        Node::Text(text, _) | Node::Raw(text, _) => !text.is_empty(),
+        Node::Comment(_, _) | Node::If(_) | Node::For(_) | Node::Define(_) => false,
+    })
+}
+
+// HTMLの仕様で終了タグを省ける場合(html5で縮める場合のみ)
+// nextは直後に出力するノードで、Noneの場合は親の中の最後
+// parent_closedは親の終了タグか文書の終わりが続くかどうかで、親の終了タグも省く場合に最後の子を閉じられなくなるのを防ぐ
+pub(crate) fn omits_end_tag(
+    options: &HtmlOptions,
+    tag_name: &str,
+    next: Option<&Node>,
+    parent: &str,
+    parent_closed: bool,
+) -> bool {
+    if options.minify.is_none() || options.mode != OutputMode::Html5 {
+        return false;
+    }
+    let next_tag = match next {
+        Some(Node::Element(element)) => Some(element.tag_name.as_str()),
+        _ => None,
+    };
+    let last = next.is_none() && parent_closed;
+    let followed_by = |tags: &[&str]| next_tag.is_some_and(|tag| tags.contains(&tag));
+    match tag_name {
+        "li" => last || followed_by(&["li"]),
+        "dt" => followed_by(&["dt", "dd"]),
+        "dd" => last || followed_by(&["dt", "dd"]),
+        // quirksモードではtableがpを閉じない
+        "p" => {
+            next_tag.is_some_and(|tag| closes_paragraph(tag) && tag != "table")
+                || (last
+                    && !matches!(
+                        parent,
+                        "a" | "audio" | "del" | "ins" | "map" | "noscript" | "video"
+                    )
+                    && !parent.contains('-'))
+        }
+        "rt" | "rp" => last || followed_by(&["rt", "rp"]),
+        "optgroup" => last || followed_by(&["optgroup"]),
+        "option" => last || followed_by(&["option", "optgroup"]),
+        "thead" => followed_by(&["tbody", "tfoot"]),
+        "tbody" => last || followed_by(&["tbody", "tfoot"]),
+        "tfoot" => last,
+        "tr" => last || followed_by(&["tr"]),
+        "td" | "th" => last || followed_by(&["td", "th"]),
+        "html" | "body" => last,
+        _ => false,
+    }
+}
+
+// style, scriptの中身を縮める。CSS, JavaScript, JSON以外はそのまま返す
+pub(crate) fn minify_raw_text(element: &Element, text: &str) -> String {
+    let content_type = element
+        .attributes
+        .iter()
+        .find(|attribute| attribute.name.eq_ignore_ascii_case("type"))
+        .and_then(|attribute| attribute.value.as_deref())
+        .map(|value| value.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+    match (
+        element.tag_name.to_ascii_lowercase().as_str(),
+        content_type.as_str(),
+    ) {
+        ("style", "" | "text/css") => minify_css(text),
+        (
+            "script",
+            ""
+            | "module"
+            | "importmap"
+            | "text/javascript"
+            | "application/javascript"
+            | "application/json"
+            | "application/ld+json",
+        ) => minify_script(text),
+        _ => text.to_string(),
+    }
+}
+
+// コメントと記号の前後の空白、ブロックの最後の`;`を取り除く
+// 文字列、url(...)、エスケープは変えない
+fn minify_css(css: &str) -> String {
+    // 前後の空白を省ける記号
+    let is_delimiter = |c: char| matches!(c, '{' | '}' | ';' | ',' | '>');
+    let mut output = String::with_capacity(css.len());
+    // outputのこの位置までは文字列などで、記号として扱わない
+    let mut protected = 0;
+    let mut space = false;
+    let mut comment = false;
+    let mut chars = css.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_whitespace() {
+            space = true;
+            continue;
+        }
+        if c == '/' && chars.peek() == Some(&'*') {
+            chars.next();
+            let mut previous = ' ';
+            for c in chars.by_ref() {
+                if previous == '*' && c == '/' {
+                    break;
+                }
+                previous = c;
+            }
+            comment = true;
+            continue;
+        }
+
+        let last = output
+            .chars()
+            .next_back()
+            .filter(|_| output.len() > protected);
+        let after_delimiter = last.is_some_and(|last| is_delimiter(last) || last == ':');
+        if (space || comment) && !output.is_empty() && !after_delimiter && !is_delimiter(c) {
+            // コメントだけで区切られていた場合は、つなげると別の字句になるためコメントを残す
+            output.push_str(if space { " " } else { "/**/" });
+        }
+        space = false;
+        comment = false;
+        if c == '}' && last == Some(';') {
+            output.pop();
+        }
+        output.push(c);
+
+        match c {
+            '"' | '\'' => {
+                while let Some(s) = chars.next() {
+                    output.push(s);
+                    if s == '\\' {
+                        output.extend(chars.next());
+                    } else if s == c || s == '\n' {
+                        break;
+                    }
+                }
+                protected = output.len();
+            }
+            '\\' => {
+                // 16進数のエスケープは直後の空白一つまでを含む
+                match chars.next() {
+                    Some(escaped) if escaped.is_ascii_hexdigit() => {
+                        output.push(escaped);
+                        for _ in 0..5 {
+                            match chars.next_if(char::is_ascii_hexdigit) {
+                                Some(digit) => output.push(digit),
+                                None => break,
+                            }
+                        }
+                        output.extend(chars.next_if(char::is_ascii_whitespace));
+                    }
+                    escaped => output.extend(escaped),
+                }
+                protected = output.len();
+            }
+            '(' if output.len() >= 4
+                && output[..output.len() - 1]
+                    .to_ascii_lowercase()
+                    .ends_with("url") =>
+            {
+                for s in chars.by_ref() {
+                    output.push(s);
+                    if s == ')' {
+                        break;
+                    }
+                }
+                protected = output.len();
+            }
+            _ => (),
+        }
+    }
+    output
+}
+
+// 行頭と行末の空白、空行を取り除く
+// テンプレート文字列や`\`で次の行に続く文字列は中の空白が値になるため、それらがある場合はそのまま返す
+fn minify_script(script: &str) -> String {
+    if script.contains('`') || script.lines().any(|line| line.trim_end().ends_with('\\')) {
+        return script.to_string();
+    }
+    script
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{collapse_whitespace, is_unquotable, minify_css, minify_script};
+
+    #[test]
+    fn whitespace() {
+        assert_eq!(collapse_whitespace("  a \n\t b  "), " a b ");
+        assert_eq!(collapse_whitespace("a\u{a0}\u{a0}b"), "a\u{a0}\u{a0}b");
+        assert_eq!(collapse_whitespace(""), "");
+    }
+
+    #[test]
+    fn unquotable() {
+        assert!(is_unquotable("main"));
+        assert!(is_unquotable("/a/b.png#top&amp;x"));
+        assert!(!is_unquotable(""));
+        assert!(!is_unquotable("a b"));
+        assert!(!is_unquotable("a=b"));
+        assert!(!is_unquotable("it's"));
+        assert!(!is_unquotable("`a`"));
+    }
+
+    #[test]
+    fn css() {
+        assert_eq!(
+            minify_css(
+                "a > b , c {\n  color : red ;\n  margin: 0 auto;\n}\n/* note */\np :hover { }"
+            ),
+            "a>b,c{color :red;margin:0 auto}p :hover{}"
+        );
+        assert_eq!(
+            minify_css(r#"a::after { content: " ; }  /* x */ "; font: 12px  "A  B" }"#),
+            r#"a::after{content:" ; }  /* x */ ";font:12px "A  B"}"#
+        );
+        assert_eq!(
+            minify_css("a { background: url(a/*b*/c.png) ; } .\\31  a{} b/**/c{}"),
+            "a{background:url(a/*b*/c.png)}.\\31  a{}b/**/c{}"
+        );
+    }
+
+    #[test]
+    fn script() {
+        assert_eq!(
+            minify_script("\n  if (a) {\n    b()\n  }\n\n  c()\n"),
+            "if (a) {\nb()\n}\nc()"
+        );
+        let template = "const a = `\n  x\n`";
+        assert_eq!(minify_script(template), template);
+        let continued = "const a = 'x\\\n  y'";
+        assert_eq!(minify_script(continued), continued);
+    }
+}
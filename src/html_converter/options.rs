@@ -0,0 +1,32 @@
+use crate::formatter::Tab;
+
+use super::OutputMode;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HtmlOptions {
+    // コメントを出力しない
+    pub ignore_comment: bool,
+    pub mode: OutputMode,
+    // Someの場合はブロック要素を一行ずつに分け、この幅で字下げする。Noneの場合は全てを一行に書く
+    pub pretty: Option<Tab>,
+    // Someの場合は表示を変えずに出力を小さくする。prettyより優先する
+    pub minify: Option<Minify>,
+}
+
+// 縮める場合はコメントを出力せず、文字列の連続する空白を一つにまとめる
+// html5では省ける属性値の引用符と終了タグも書かない
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Minify {
+    // style, script要素の中身も縮める
+    pub inline_code: bool,
+}
+
+impl HtmlOptions {
+    // 整形する場合の字下げ
+    pub(crate) fn indent(&self) -> Option<&Tab> {
+        match self.minify {
+            Some(_) => None,
+            None => self.pretty.as_ref(),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::{fmt::Display, str::FromStr};
+
+// HTMLの書き出し方。空要素や子を持たない外部要素(svg, math)のタグ、値のない属性の書き方が変わる
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputMode {
+    // <br>, disabled。外部要素にも終了タグを付ける
+    #[default]
+    Html5,
+    // XMLとして読める形。子を持たない要素は全て<p />のように閉じ、disabled="disabled"と書く
+    Xhtml,
+    // HTMLとしてもXMLとしても同じ意味になる形。空要素と子を持たない外部要素だけを<br />のように閉じ、disabled=""と書く
+    Polyglot,
+}
+
+impl OutputMode {
+    pub const NAMES: [&'static str; 3] = ["html5", "xhtml", "polyglot"];
+
+    // 子を持たない要素の開始タグの閉じ方。Noneの場合は終了タグを書く
+    pub(crate) fn empty_tag_end(self, void: bool, foreign: bool) -> Option<&'static str> {
+        match self {
+            OutputMode::Html5 if void => Some(">"),
+            OutputMode::Html5 => None,
+            OutputMode::Xhtml => Some(" />"),
+            OutputMode::Polyglot if void || foreign => Some(" />"),
+            OutputMode::Polyglot => None,
+        }
+    }
+
+    // 値のない属性
+    pub(crate) fn boolean_attribute(self, name: &str) -> String {
+        match self {
+            OutputMode::Html5 => name.to_string(),
+            OutputMode::Xhtml => format!("{}=\"{}\"", name, name),
+            OutputMode::Polyglot => format!("{}=\"\"", name),
+        }
+    }
+}
+
+impl FromStr for OutputMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "html5" => Ok(OutputMode::Html5),
+            "xhtml" => Ok(OutputMode::Xhtml),
+            "polyglot" => Ok(OutputMode::Polyglot),
+            _ => Err(format!(
+                "unknown output mode `{}`, expected one of {}",
+                s,
+                OutputMode::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl Display for OutputMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OutputMode::Html5 => write!(f, "html5"),
+            OutputMode::Xhtml => write!(f, "xhtml"),
+            OutputMode::Polyglot => write!(f, "polyglot"),
+        }
+    }
+}
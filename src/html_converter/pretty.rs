@@ -0,0 +1,58 @@
+use crate::{formatter::Tab, html_spec, jtml_parser::Node};
+
+// 中の空白や改行がそのまま表示に現れるため、整形しても中身を変えない要素
+pub(crate) fn is_whitespace_sensitive(tag_name: &str) -> bool {
+    matches!(tag_name, "pre" | "textarea" | "script" | "style")
+}
+
+// 整形しても中を一行のまま書く要素
+// 知らない要素(カスタム要素やsvg, mathとその中の要素)は空白を足さないよう行内として扱う
+pub(crate) fn is_inline(tag_name: &str) -> bool {
+    html_spec::element(tag_name).is_none_or(|spec| spec.inline)
+}
+
+// 整形して出力する場合に一行を占めるノード
+fn is_block(node: &Node) -> bool {
+    match node {
+        Node::Element(element) => !is_inline(&element.tag_name),
+        Node::Comment(_, _) => true,
+        _ => false,
+    }
+}
+
+// ブロック要素を持つ子は、一つずつ行に分ける
+pub(crate) fn has_block(nodes: &[&Node]) -> bool {
+    nodes.iter().any(|node| is_block(node))
+}
+
+// nodesを一行ずつ、depthの字下げを付けて並べる。連続する行内のノードは一行にまとめる
+// htmlはnodesの位置とノードから一つを出力する関数で、空文字列になったノードは行を作らない
+pub(crate) fn lines(
+    nodes: &[&Node],
+    indent: &Tab,
+    depth: usize,
+    mut html: impl FnMut(usize, &Node) -> String,
+) -> String {
+    let prefix = indent.text(depth);
+    let mut lines: Vec<String> = Vec::new();
+    let mut inline = String::new();
+    for (index, node) in nodes.iter().enumerate() {
+        let text = html(index, node);
+        if text.is_empty() {
+            continue;
+        }
+        if !is_block(node) {
+            inline.push_str(&text);
+            continue;
+        }
+        if !inline.is_empty() {
+            lines.push(format!("{}{}", prefix, inline));
+            inline.clear();
+        }
+        lines.push(format!("{}{}", prefix, text));
+    }
+    if !inline.is_empty() {
+        lines.push(format!("{}{}", prefix, inline));
+    }
+    lines.join("\n")
+}
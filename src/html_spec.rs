@@ -0,0 +1,646 @@
+// HTMLの要素と属性の知識
+// 構文解析(子要素を持たない要素の判定)やエディタの補完・ホバーで使う
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ElementSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    // 子要素を持たない(終了タグのない)要素
+    pub void: bool,
+    // headの中に書ける要素
+    pub metadata: bool,
+    // 前後で改行しない(整形して出力する場合に字下げしない)要素。phrasing contentのうち表示が行内のもの
+    pub inline: bool,
+    // グローバル属性以外に書ける属性
+    pub attributes: &'static [AttributeSpec],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AttributeSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+impl ElementSpec {
+    const fn new(name: &'static str, description: &'static str) -> Self {
+        ElementSpec {
+            name,
+            description,
+            void: false,
+            metadata: false,
+            inline: false,
+            attributes: &[],
+        }
+    }
+
+    const fn void(mut self) -> Self {
+        self.void = true;
+        self
+    }
+
+    const fn inline(mut self) -> Self {
+        self.inline = true;
+        self
+    }
+
+    const fn metadata(mut self) -> Self {
+        self.metadata = true;
+        self
+    }
+
+    const fn attributes(mut self, attributes: &'static [AttributeSpec]) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    // グローバル属性を含めて、この要素に書ける属性
+    pub fn all_attributes(&self) -> impl Iterator<Item = &'static AttributeSpec> {
+        self.attributes.iter().chain(GLOBAL_ATTRIBUTES)
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&'static AttributeSpec> {
+        self.all_attributes()
+            .find(|attribute| attribute.name == name)
+    }
+}
+
+const fn attribute(name: &'static str, description: &'static str) -> AttributeSpec {
+    AttributeSpec { name, description }
+}
+
+pub fn element(name: &str) -> Option<&'static ElementSpec> {
+    ELEMENTS
+        .binary_search_by(|element| element.name.cmp(name))
+        .ok()
+        .map(|i| &ELEMENTS[i])
+}
+
+// 名前で二分探索するため、名前順に並べる
+pub static ELEMENTS: &[ElementSpec] = &[
+    ElementSpec::new("a", "A hyperlink to another page, file, location in the same page or any other URL.")
+        .inline()
+        .attributes(&[
+            attribute("download", "Downloads the linked URL instead of navigating to it. The value is the suggested file name."),
+            attribute("href", "The URL the hyperlink points to."),
+            attribute("hreflang", "The language of the linked document."),
+            attribute("ping", "Space separated URLs that are notified when the link is followed."),
+            attribute("referrerpolicy", "How much of the referrer to send when following the link."),
+            attribute("rel", "The relationship of the linked URL, as space separated link types."),
+            attribute("target", "Where to display the linked URL: `_self`, `_blank`, `_parent` or `_top`."),
+            attribute("type", "A hint for the MIME type of the linked URL."),
+        ]),
+    ElementSpec::new("abbr", "An abbreviation or acronym.")
+        .inline(),
+    ElementSpec::new("address", "Contact information for the nearest article or the whole page."),
+    ElementSpec::new("area", "A clickable area inside an image map.")
+        .inline()
+        .void()
+        .attributes(&[
+            attribute("alt", "Alternative text for the area, shown when the image is not available."),
+            attribute("coords", "The coordinates of the area."),
+            attribute("download", "Downloads the linked URL instead of navigating to it."),
+            attribute("href", "The URL the area points to."),
+            attribute("ping", "Space separated URLs that are notified when the link is followed."),
+            attribute("referrerpolicy", "How much of the referrer to send when following the link."),
+            attribute("rel", "The relationship of the linked URL."),
+            attribute("shape", "The shape of the area: `rect`, `circle`, `poly` or `default`."),
+            attribute("target", "Where to display the linked URL."),
+        ]),
+    ElementSpec::new("article", "A self-contained composition such as a post, an article or a comment."),
+    ElementSpec::new("aside", "Content only indirectly related to the main content, such as a sidebar."),
+    ElementSpec::new("audio", "Embeds sound content.")
+        .inline()
+        .attributes(&[
+        attribute("autoplay", "Starts playing as soon as possible."),
+        attribute("controls", "Shows the browser's playback controls."),
+        attribute("crossorigin", "How to fetch the media: `anonymous` or `use-credentials`."),
+        attribute("loop", "Starts over again when the end is reached."),
+        attribute("muted", "Mutes the audio initially."),
+        attribute("preload", "What to load before playing: `none`, `metadata` or `auto`."),
+        attribute("src", "The URL of the audio."),
+    ]),
+    ElementSpec::new("b", "Draws attention to text without giving it extra importance.")
+        .inline(),
+    ElementSpec::new("base", "The base URL for all relative URLs in the document.")
+        .void()
+        .metadata()
+        .attributes(&[
+            attribute("href", "The base URL for relative URLs."),
+            attribute("target", "The default browsing context for links and forms."),
+        ]),
+    ElementSpec::new("bdi", "Isolates text that may be displayed in a different direction.")
+        .inline(),
+    ElementSpec::new("bdo", "Overrides the direction of the text.")
+        .inline()
+        .attributes(&[attribute("dir", "The direction of the text: `ltr` or `rtl`.")]),
+    ElementSpec::new("blockquote", "A long quotation.")
+        .attributes(&[attribute("cite", "The URL of the source of the quotation.")]),
+    ElementSpec::new("body", "The content of the document. There can be only one."),
+    ElementSpec::new("br", "A line break.")
+        .inline()
+        .void(),
+    ElementSpec::new("button", "An interactive button that performs an action.")
+        .inline()
+        .attributes(&[
+        attribute("disabled", "Prevents the user from interacting with the button."),
+        attribute("form", "The id of the form the button belongs to."),
+        attribute("formaction", "The URL that processes the submitted form, overriding the form's `action`."),
+        attribute("formenctype", "How the submitted form is encoded, overriding the form's `enctype`."),
+        attribute("formmethod", "The HTTP method used to submit the form, overriding the form's `method`."),
+        attribute("formnovalidate", "Submits the form without validating it."),
+        attribute("formtarget", "Where to display the response, overriding the form's `target`."),
+        attribute("name", "The name submitted with the button's value."),
+        attribute("popovertarget", "The id of the popover element the button controls."),
+        attribute("popovertargetaction", "The action on the popover: `show`, `hide` or `toggle`."),
+        attribute("type", "The behavior of the button: `submit`, `reset` or `button`."),
+        attribute("value", "The value submitted with the form."),
+    ]),
+    ElementSpec::new("canvas", "A drawing surface for scripts.")
+        .inline()
+        .attributes(&[
+        attribute("height", "The height of the coordinate space in CSS pixels."),
+        attribute("width", "The width of the coordinate space in CSS pixels."),
+    ]),
+    ElementSpec::new("caption", "The title of a table."),
+    ElementSpec::new("cite", "The title of a creative work.")
+        .inline(),
+    ElementSpec::new("code", "A fragment of computer code.")
+        .inline(),
+    ElementSpec::new("col", "A column within a column group.")
+        .void()
+        .attributes(&[attribute("span", "The number of columns the element spans.")]),
+    ElementSpec::new("colgroup", "A group of columns within a table.")
+        .attributes(&[attribute("span", "The number of columns the group spans.")]),
+    ElementSpec::new("data", "Links content with a machine-readable value.")
+        .inline()
+        .attributes(&[attribute("value", "The machine-readable value.")]),
+    ElementSpec::new("datalist", "A set of options to choose from in other controls.")
+        .inline(),
+    ElementSpec::new("dd", "The description of the preceding term in a description list."),
+    ElementSpec::new("del", "Text that has been deleted from the document.")
+        .inline()
+        .attributes(&[
+        attribute("cite", "The URL of a document explaining the change."),
+        attribute("datetime", "The date and time of the change."),
+    ]),
+    ElementSpec::new("details", "A disclosure widget that shows its content when opened.").attributes(&[
+        attribute("name", "Groups details elements so that only one of them is open at a time."),
+        attribute("open", "Shows the content."),
+    ]),
+    ElementSpec::new("dfn", "The term being defined.")
+        .inline(),
+    ElementSpec::new("dialog", "A dialog box or other interactive component.")
+        .attributes(&[attribute("open", "Shows the dialog.")]),
+    ElementSpec::new("div", "A generic container for flow content."),
+    ElementSpec::new("dl", "A description list."),
+    ElementSpec::new("dt", "A term in a description list."),
+    ElementSpec::new("em", "Emphasized text.")
+        .inline(),
+    ElementSpec::new("embed", "Embeds external content such as a plugin.")
+        .inline()
+        .void()
+        .attributes(&[
+            attribute("height", "The displayed height in CSS pixels."),
+            attribute("src", "The URL of the embedded resource."),
+            attribute("type", "The MIME type of the embedded resource."),
+            attribute("width", "The displayed width in CSS pixels."),
+        ]),
+    ElementSpec::new("fieldset", "Groups several controls and labels within a form.").attributes(&[
+        attribute("disabled", "Disables all the controls in the group."),
+        attribute("form", "The id of the form the group belongs to."),
+        attribute("name", "The name of the group."),
+    ]),
+    ElementSpec::new("figcaption", "A caption for the content of its parent figure."),
+    ElementSpec::new("figure", "Self-contained content such as an illustration, optionally with a caption."),
+    ElementSpec::new("footer", "The footer of the nearest section or the whole page."),
+    ElementSpec::new("form", "A section containing controls for submitting information.").attributes(&[
+        attribute("accept-charset", "The character encodings the server accepts."),
+        attribute("action", "The URL that processes the submitted form."),
+        attribute("autocomplete", "Whether the browser may fill in the controls: `on` or `off`."),
+        attribute("enctype", "How the submitted form is encoded."),
+        attribute("method", "The HTTP method used to submit the form: `get`, `post` or `dialog`."),
+        attribute("name", "The name of the form."),
+        attribute("novalidate", "Submits the form without validating it."),
+        attribute("rel", "The relationship of the action URL."),
+        attribute("target", "Where to display the response."),
+    ]),
+    ElementSpec::new("h1", "A level 1 section heading, the most important one."),
+    ElementSpec::new("h2", "A level 2 section heading."),
+    ElementSpec::new("h3", "A level 3 section heading."),
+    ElementSpec::new("h4", "A level 4 section heading."),
+    ElementSpec::new("h5", "A level 5 section heading."),
+    ElementSpec::new("h6", "A level 6 section heading, the least important one."),
+    ElementSpec::new("head", "Machine-readable information about the document, such as its title and style sheets."),
+    ElementSpec::new("header", "Introductory content such as a heading, a logo or navigation."),
+    ElementSpec::new("hgroup", "A heading grouped with secondary content such as subheadings."),
+    ElementSpec::new("hr", "A thematic break between paragraphs.").void(),
+    ElementSpec::new("html", "The root of the document. All other elements are its descendants."),
+    ElementSpec::new("i", "Text in an alternate voice, such as a technical term or a thought.")
+        .inline(),
+    ElementSpec::new("iframe", "Embeds another HTML page.")
+        .inline()
+        .attributes(&[
+        attribute("allow", "The permissions policy of the embedded page."),
+        attribute("allowfullscreen", "Allows the embedded page to use fullscreen mode."),
+        attribute("height", "The height in CSS pixels."),
+        attribute("loading", "When to load the page: `eager` or `lazy`."),
+        attribute("name", "The name of the embedded browsing context."),
+        attribute("referrerpolicy", "How much of the referrer to send when fetching the page."),
+        attribute("sandbox", "Restrictions applied to the embedded page."),
+        attribute("src", "The URL of the embedded page."),
+        attribute("srcdoc", "Inline HTML to embed, overriding `src`."),
+        attribute("width", "The width in CSS pixels."),
+    ]),
+    ElementSpec::new("img", "Embeds an image.")
+        .inline()
+        .void().attributes(&[
+        attribute("alt", "Alternative text describing the image. Use an empty value for decorative images."),
+        attribute("crossorigin", "How to fetch the image: `anonymous` or `use-credentials`."),
+        attribute("decoding", "How to decode the image: `sync`, `async` or `auto`."),
+        attribute("fetchpriority", "The priority of fetching the image: `high`, `low` or `auto`."),
+        attribute("height", "The intrinsic height in pixels."),
+        attribute("ismap", "Makes the image part of a server-side image map."),
+        attribute("loading", "When to load the image: `eager` or `lazy`."),
+        attribute("referrerpolicy", "How much of the referrer to send when fetching the image."),
+        attribute("sizes", "The image sizes for different page layouts."),
+        attribute("src", "The URL of the image."),
+        attribute("srcset", "Candidate images for different resolutions or widths."),
+        attribute("usemap", "The image map to use, as `#name`."),
+        attribute("width", "The intrinsic width in pixels."),
+    ]),
+    ElementSpec::new("input", "A control for entering data.")
+        .inline()
+        .void().attributes(&[
+        attribute("accept", "The file types accepted by a file input."),
+        attribute("alt", "Alternative text for an image input."),
+        attribute("autocomplete", "A hint for the browser's autofill feature."),
+        attribute("checked", "Selects a checkbox or radio button."),
+        attribute("dirname", "The name submitted with the direction of the text."),
+        attribute("disabled", "Prevents the user from interacting with the control."),
+        attribute("form", "The id of the form the control belongs to."),
+        attribute("list", "The id of a datalist with suggested values."),
+        attribute("max", "The greatest allowed value."),
+        attribute("maxlength", "The maximum length of the value."),
+        attribute("min", "The least allowed value."),
+        attribute("minlength", "The minimum length of the value."),
+        attribute("multiple", "Allows more than one value."),
+        attribute("name", "The name submitted with the value."),
+        attribute("pattern", "A regular expression the value must match."),
+        attribute("placeholder", "Text shown when the control is empty."),
+        attribute("readonly", "Prevents the user from editing the value."),
+        attribute("required", "Requires a value before the form can be submitted."),
+        attribute("size", "The width of the control in characters."),
+        attribute("src", "The URL of the image for an image input."),
+        attribute("step", "The granularity of the value."),
+        attribute("type", "The kind of control, such as `text`, `email`, `checkbox` or `submit`."),
+        attribute("value", "The initial value."),
+    ]),
+    ElementSpec::new("ins", "Text that has been added to the document.")
+        .inline()
+        .attributes(&[
+        attribute("cite", "The URL of a document explaining the change."),
+        attribute("datetime", "The date and time of the change."),
+    ]),
+    ElementSpec::new("kbd", "Text representing user input from a keyboard or other device.")
+        .inline(),
+    ElementSpec::new("keygen", "Obsolete. A key pair generator control.")
+        .inline()
+        .void(),
+    ElementSpec::new("label", "A caption for a control.")
+        .inline()
+        .attributes(&[attribute("for", "The id of the control the label describes.")]),
+    ElementSpec::new("legend", "A caption for the content of its parent fieldset."),
+    ElementSpec::new("li", "An item in a list.")
+        .attributes(&[attribute("value", "The ordinal value of the item in an ordered list.")]),
+    ElementSpec::new("link", "A relationship to an external resource, most commonly a style sheet.")
+        .void()
+        .metadata()
+        .attributes(&[
+            attribute("as", "The kind of content being preloaded."),
+            attribute("crossorigin", "How to fetch the resource: `anonymous` or `use-credentials`."),
+            attribute("href", "The URL of the linked resource."),
+            attribute("hreflang", "The language of the linked resource."),
+            attribute("integrity", "A hash used to verify the fetched resource."),
+            attribute("media", "The media the resource applies to."),
+            attribute("referrerpolicy", "How much of the referrer to send when fetching the resource."),
+            attribute("rel", "The relationship of the linked resource, such as `stylesheet` or `icon`."),
+            attribute("sizes", "The sizes of the icons."),
+            attribute("type", "The MIME type of the linked resource."),
+        ]),
+    ElementSpec::new("main", "The dominant content of the body."),
+    ElementSpec::new("map", "An image map with clickable areas.")
+        .inline()
+        .attributes(&[attribute("name", "The name referenced by `usemap`.")]),
+    ElementSpec::new("mark", "Text highlighted for reference.")
+        .inline(),
+    ElementSpec::new("menu", "A list of commands, semantically the same as ul."),
+    ElementSpec::new("meta", "Metadata that cannot be represented by the other metadata elements.")
+        .void()
+        .metadata()
+        .attributes(&[
+            attribute("charset", "The character encoding of the document. Should be `UTF-8`."),
+            attribute("content", "The value of the metadata named by `name` or `http-equiv`."),
+            attribute("http-equiv", "A pragma directive, such as `X-UA-Compatible` or `content-security-policy`."),
+            attribute("media", "The media the metadata applies to."),
+            attribute("name", "The name of the metadata, such as `viewport`, `description` or `author`."),
+        ]),
+    ElementSpec::new("meter", "A scalar value within a known range.")
+        .inline()
+        .attributes(&[
+        attribute("high", "The lower bound of the high end of the range."),
+        attribute("low", "The upper bound of the low end of the range."),
+        attribute("max", "The upper bound of the range."),
+        attribute("min", "The lower bound of the range."),
+        attribute("optimum", "The optimal value."),
+        attribute("value", "The current value."),
+    ]),
+    ElementSpec::new("nav", "A section with navigation links."),
+    ElementSpec::new("noscript", "Content shown when scripts are disabled.").metadata(),
+    ElementSpec::new("object", "Embeds an external resource.")
+        .inline()
+        .attributes(&[
+        attribute("data", "The URL of the resource."),
+        attribute("form", "The id of the form the object belongs to."),
+        attribute("height", "The displayed height in CSS pixels."),
+        attribute("name", "The name of the browsing context."),
+        attribute("type", "The MIME type of the resource."),
+        attribute("width", "The displayed width in CSS pixels."),
+    ]),
+    ElementSpec::new("ol", "An ordered list.").attributes(&[
+        attribute("reversed", "Numbers the items in descending order."),
+        attribute("start", "The number of the first item."),
+        attribute("type", "The kind of numbering: `1`, `a`, `A`, `i` or `I`."),
+    ]),
+    ElementSpec::new("optgroup", "A group of options within a select.").attributes(&[
+        attribute("disabled", "Disables all the options in the group."),
+        attribute("label", "The name of the group."),
+    ]),
+    ElementSpec::new("option", "An item in a select or datalist.").attributes(&[
+        attribute("disabled", "Prevents the option from being selected."),
+        attribute("label", "The text shown for the option."),
+        attribute("selected", "Selects the option initially."),
+        attribute("value", "The value submitted with the form."),
+    ]),
+    ElementSpec::new("output", "The result of a calculation or user action.")
+        .inline()
+        .attributes(&[
+        attribute("for", "Space separated ids of the elements that contributed to the result."),
+        attribute("form", "The id of the form the element belongs to."),
+        attribute("name", "The name of the element."),
+    ]),
+    ElementSpec::new("p", "A paragraph."),
+    ElementSpec::new("param", "Obsolete. A parameter for an object.")
+        .void()
+        .attributes(&[
+            attribute("name", "The name of the parameter."),
+            attribute("value", "The value of the parameter."),
+        ]),
+    ElementSpec::new("picture", "Image sources for different display scenarios, with an img fallback.")
+        .inline(),
+    ElementSpec::new("pre", "Preformatted text shown exactly as written."),
+    ElementSpec::new("progress", "The completion progress of a task.")
+        .inline()
+        .attributes(&[
+        attribute("max", "The amount of work the task requires."),
+        attribute("value", "The amount of work completed."),
+    ]),
+    ElementSpec::new("q", "A short inline quotation.")
+        .inline()
+        .attributes(&[attribute("cite", "The URL of the source of the quotation.")]),
+    ElementSpec::new("rp", "Fallback parentheses for browsers without ruby support.")
+        .inline(),
+    ElementSpec::new("rt", "The pronunciation of ruby text.")
+        .inline(),
+    ElementSpec::new("ruby", "Ruby annotations for showing pronunciation.")
+        .inline(),
+    ElementSpec::new("s", "Text that is no longer accurate or relevant.")
+        .inline(),
+    ElementSpec::new("samp", "Sample output from a computer program.")
+        .inline(),
+    ElementSpec::new("script", "Embeds or references executable code.")
+        .metadata()
+        .attributes(&[
+            attribute("async", "Runs the script as soon as it is available."),
+            attribute("crossorigin", "How to fetch the script: `anonymous` or `use-credentials`."),
+            attribute("defer", "Runs the script after the document has been parsed."),
+            attribute("integrity", "A hash used to verify the fetched script."),
+            attribute("nomodule", "Skips the script in browsers that support modules."),
+            attribute("referrerpolicy", "How much of the referrer to send when fetching the script."),
+            attribute("src", "The URL of an external script."),
+            attribute("type", "The type of the script, such as `module`."),
+        ]),
+    ElementSpec::new("search", "A section containing search or filtering controls."),
+    ElementSpec::new("section", "A generic standalone section of a document."),
+    ElementSpec::new("select", "A control for choosing from a list of options.")
+        .inline()
+        .attributes(&[
+        attribute("autocomplete", "A hint for the browser's autofill feature."),
+        attribute("disabled", "Prevents the user from interacting with the control."),
+        attribute("form", "The id of the form the control belongs to."),
+        attribute("multiple", "Allows selecting more than one option."),
+        attribute("name", "The name submitted with the value."),
+        attribute("required", "Requires a selection before the form can be submitted."),
+        attribute("size", "The number of visible rows."),
+    ]),
+    ElementSpec::new("slot", "A placeholder inside a web component.")
+        .inline()
+        .attributes(&[attribute("name", "The name of the slot.")]),
+    ElementSpec::new("small", "Side comments and small print.")
+        .inline(),
+    ElementSpec::new("source", "A media resource for picture, audio or video.")
+        .void()
+        .attributes(&[
+            attribute("height", "The intrinsic height of the image."),
+            attribute("media", "The media query for the resource."),
+            attribute("sizes", "The image sizes for different page layouts."),
+            attribute("src", "The URL of the media resource."),
+            attribute("srcset", "Candidate images for different resolutions or widths."),
+            attribute("type", "The MIME type of the resource."),
+            attribute("width", "The intrinsic width of the image."),
+        ]),
+    ElementSpec::new("span", "A generic inline container for phrasing content.")
+        .inline(),
+    ElementSpec::new("strong", "Text of strong importance.")
+        .inline(),
+    ElementSpec::new("style", "Style information for the document.")
+        .metadata()
+        .attributes(&[attribute("media", "The media the styles apply to.")]),
+    ElementSpec::new("sub", "Subscript text.")
+        .inline(),
+    ElementSpec::new("summary", "The summary or label of a details element."),
+    ElementSpec::new("sup", "Superscript text.")
+        .inline(),
+    ElementSpec::new("table", "Tabular data."),
+    ElementSpec::new("tbody", "The body rows of a table."),
+    ElementSpec::new("td", "A data cell of a table.").attributes(&[
+        attribute("colspan", "The number of columns the cell spans."),
+        attribute("headers", "Space separated ids of the header cells for the cell."),
+        attribute("rowspan", "The number of rows the cell spans."),
+    ]),
+    ElementSpec::new("template", "HTML that is not rendered but can be instantiated by scripts.").metadata(),
+    ElementSpec::new("textarea", "A multi-line text editing control.")
+        .inline()
+        .attributes(&[
+        attribute("autocomplete", "A hint for the browser's autofill feature."),
+        attribute("cols", "The visible width in characters."),
+        attribute("dirname", "The name submitted with the direction of the text."),
+        attribute("disabled", "Prevents the user from interacting with the control."),
+        attribute("form", "The id of the form the control belongs to."),
+        attribute("maxlength", "The maximum length of the value."),
+        attribute("minlength", "The minimum length of the value."),
+        attribute("name", "The name submitted with the value."),
+        attribute("placeholder", "Text shown when the control is empty."),
+        attribute("readonly", "Prevents the user from editing the value."),
+        attribute("required", "Requires a value before the form can be submitted."),
+        attribute("rows", "The number of visible lines."),
+        attribute("wrap", "How the value is wrapped when submitted: `soft` or `hard`."),
+    ]),
+    ElementSpec::new("tfoot", "The summary rows at the end of a table."),
+    ElementSpec::new("th", "A header cell of a table.").attributes(&[
+        attribute("abbr", "A short description of the cell's content."),
+        attribute("colspan", "The number of columns the cell spans."),
+        attribute("headers", "Space separated ids of the header cells for the cell."),
+        attribute("rowspan", "The number of rows the cell spans."),
+        attribute("scope", "The cells the header applies to: `row`, `col`, `rowgroup` or `colgroup`."),
+    ]),
+    ElementSpec::new("thead", "The header rows of a table."),
+    ElementSpec::new("time", "A specific period in time.")
+        .inline()
+        .attributes(&[attribute("datetime", "The machine-readable date and time.")]),
+    ElementSpec::new("title", "The title of the document shown in the browser's title bar or tab.").metadata(),
+    ElementSpec::new("tr", "A row of cells in a table."),
+    ElementSpec::new("track", "Timed text tracks, such as subtitles, for audio and video.").void().attributes(&[
+        attribute("default", "Enables the track unless the user's preferences indicate another one."),
+        attribute("kind", "How the track is used: `subtitles`, `captions`, `chapters` or `metadata`."),
+        attribute("label", "The title of the track shown to the user."),
+        attribute("src", "The URL of the track."),
+        attribute("srclang", "The language of the track."),
+    ]),
+    ElementSpec::new("u", "Text with a non-textual annotation, such as a misspelling.")
+        .inline(),
+    ElementSpec::new("ul", "An unordered list."),
+    ElementSpec::new("var", "The name of a variable.")
+        .inline(),
+    ElementSpec::new("video", "Embeds a video.")
+        .inline()
+        .attributes(&[
+        attribute("autoplay", "Starts playing as soon as possible."),
+        attribute("controls", "Shows the browser's playback controls."),
+        attribute("crossorigin", "How to fetch the video: `anonymous` or `use-credentials`."),
+        attribute("height", "The displayed height in CSS pixels."),
+        attribute("loop", "Starts over again when the end is reached."),
+        attribute("muted", "Mutes the audio initially."),
+        attribute("playsinline", "Plays inline instead of in fullscreen on mobile browsers."),
+        attribute("poster", "The URL of an image shown until the video plays."),
+        attribute("preload", "What to load before playing: `none`, `metadata` or `auto`."),
+        attribute("src", "The URL of the video."),
+        attribute("width", "The displayed width in CSS pixels."),
+    ]),
+    ElementSpec::new("wbr", "A position where the browser may break a line.")
+        .inline()
+        .void(),
+];
+
+// 全ての要素に書ける属性
+pub static GLOBAL_ATTRIBUTES: &[AttributeSpec] = &[
+    attribute("accesskey", "A keyboard shortcut for the element."),
+    attribute(
+        "autocapitalize",
+        "How text input is automatically capitalized.",
+    ),
+    attribute("autofocus", "Focuses the element when the page is loaded."),
+    attribute(
+        "class",
+        "Space separated class names used by CSS and scripts.",
+    ),
+    attribute("contenteditable", "Whether the user can edit the element."),
+    attribute("dir", "The direction of the text: `ltr`, `rtl` or `auto`."),
+    attribute("draggable", "Whether the element can be dragged."),
+    attribute(
+        "enterkeyhint",
+        "The label of the enter key on virtual keyboards.",
+    ),
+    attribute("hidden", "Hides the element."),
+    attribute("id", "A unique identifier for the element in the document."),
+    attribute(
+        "inert",
+        "Makes the element and its descendants non-interactive.",
+    ),
+    attribute("inputmode", "The kind of virtual keyboard to show."),
+    attribute("is", "The name of a customized built-in element."),
+    attribute("itemid", "The global identifier of a microdata item."),
+    attribute("itemprop", "Adds a property to a microdata item."),
+    attribute(
+        "itemref",
+        "Ids of elements with additional properties of the item.",
+    ),
+    attribute("itemscope", "Creates a microdata item."),
+    attribute(
+        "itemtype",
+        "The URL of the vocabulary of the microdata item.",
+    ),
+    attribute(
+        "lang",
+        "The language of the element's content, such as `ja` or `en`.",
+    ),
+    attribute(
+        "nonce",
+        "A cryptographic nonce used by the content security policy.",
+    ),
+    attribute(
+        "part",
+        "Part names of the element exposed to the shadow host.",
+    ),
+    attribute(
+        "popover",
+        "Makes the element a popover: `auto` or `manual`.",
+    ),
+    attribute(
+        "slot",
+        "The slot of the shadow tree the element is inserted into.",
+    ),
+    attribute(
+        "spellcheck",
+        "Whether to check the spelling of the element.",
+    ),
+    attribute("style", "Inline CSS declarations for the element."),
+    attribute(
+        "tabindex",
+        "The order of the element in sequential keyboard navigation.",
+    ),
+    attribute("title", "Advisory information, usually shown as a tooltip."),
+    attribute(
+        "translate",
+        "Whether the content should be translated: `yes` or `no`.",
+    ),
+];
+
+#[cfg(test)]
+mod test {
+    use super::{element, ELEMENTS};
+
+    #[test]
+    fn sorted() {
+        for pair in ELEMENTS.windows(2) {
+            assert!(
+                pair[0].name < pair[1].name,
+                "{} {}",
+                pair[0].name,
+                pair[1].name
+            );
+        }
+    }
+
+    #[test]
+    fn lookup() {
+        let meta = element("meta").unwrap();
+        assert!(meta.void && meta.metadata);
+        assert!(meta.attribute("charset").is_some());
+        // グローバル属性
+        assert!(meta.attribute("id").is_some());
+        assert!(meta.attribute("href").is_none());
+        assert!(!element("div").unwrap().void);
+        assert!(element("wbr").unwrap().void && element("track").unwrap().void);
+        assert!(element("unknown").is_none());
+    }
+}
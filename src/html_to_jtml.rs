@@ -0,0 +1,96 @@
+mod entities;
+mod errors;
+mod parser;
+use crate::formatter::FormatConfig;
+pub use errors::HtmlToJtmlError;
+pub use parser::parse;
+
+// HTMLを整形済みのjtmlに変換する
+pub fn convert(html: &str) -> Result<String, Vec<HtmlToJtmlError>> {
+    let ast = parse(html)?;
+    Ok(ast.to_jtml(false, &FormatConfig::default()))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        html_converter::HtmlOptions,
+        jtml_lexer::test_utils::lexer,
+        jtml_parser::{self, test_utils::without_spans},
+    };
+
+    use super::{convert, parse};
+
+    #[test]
+    fn index() {
+        let result = convert(include_str!("../test_file/index.html")).unwrap();
+        assert_eq!(
+            result,
+            r#"html(lang="ja"){
+    head(){
+        meta(charset="UTF-8")
+        meta(http-equiv="X-UA-Compatible" content="IE=edge")
+        meta(name="viewport" content="width=device-width" initial-scale="1.0")
+        title(){"document"}
+    }
+    body(){
+        main(){
+            h1(){"Hello World!"}
+            img(hoge="hoge" huga="huga")
+        }
+    }
+}"#
+        );
+    }
+
+    #[test]
+    fn document() {
+        let result = convert(
+            "<!DOCTYPE html>\n<p class=\"note\">Tom &amp; &quot;Jerry&quot;<br>\n<!-- todo --></p>",
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            r#"raw(){"<!DOCTYPE html>"}
+p(class="note"){
+    "Tom & \"Jerry\""
+    br()
+    // todo
+}"#
+        );
+    }
+
+    // html -> jtml -> ast がhtmlから直接読んだastと一致する
+    #[test]
+    fn round_trip_html() {
+        for html in [
+            include_str!("../test_file/index.html"),
+            include_str!("../test_file/small.html"),
+            "<!doctype html><ul><li>a &lt; b<li><a href='x?a=1&amp;b=2'>link</a></ul>",
+            "<pre>\n  keep\n    this</pre><script>if (a < b) {}</script>",
+            "<details open><summary>a</summary><input disabled value=\"\"></details>",
+        ] {
+            let ast = parse(html).unwrap();
+            let jtml = convert(html).unwrap();
+            let reparsed = jtml_parser::parse(&lexer(&jtml)).unwrap();
+            assert_eq!(without_spans(&reparsed), ast, "{}", jtml);
+
+            // 生成したhtmlからも同じastが得られる
+            assert_eq!(parse(&ast.to_html(&HtmlOptions::default())).unwrap(), ast);
+        }
+    }
+
+    // jtml -> html -> ast が元のjtmlのastと一致する
+    #[test]
+    fn round_trip_jtml() {
+        for jtml in [
+            include_str!("../test_file/index.jtml"),
+            include_str!("../test_file/small.jtml"),
+            include_str!("../test_file/with_comment.jtml"),
+        ] {
+            let ast = jtml_parser::parse(&lexer(jtml)).unwrap();
+            let html = ast.to_html(&HtmlOptions::default());
+            assert_eq!(parse(&html).unwrap(), without_spans(&ast), "{}", html);
+        }
+    }
+}
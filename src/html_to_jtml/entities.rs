@@ -0,0 +1,99 @@
+// HTMLの文字参照を展開する
+//
+// 数値文字参照と、よく使われる名前付き文字参照のみに対応する
+// 知らない名前の場合は元の文字列をそのまま残す
+
+const NAMED_ENTITIES: &[(&str, char)] = &[
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("quot", '"'),
+    ("apos", '\''),
+    ("nbsp", '\u{a0}'),
+    ("copy", '©'),
+    ("reg", '®'),
+    ("trade", '™'),
+    ("hellip", '…'),
+    ("mdash", '—'),
+    ("ndash", '–'),
+    ("lsquo", '‘'),
+    ("rsquo", '’'),
+    ("ldquo", '“'),
+    ("rdquo", '”'),
+    ("laquo", '«'),
+    ("raquo", '»'),
+    ("middot", '·'),
+    ("bull", '•'),
+    ("times", '×'),
+    ("divide", '÷'),
+    ("yen", '¥'),
+    ("euro", '€'),
+    ("deg", '°'),
+    ("larr", '←'),
+    ("rarr", '→'),
+    ("uarr", '↑'),
+    ("darr", '↓'),
+];
+
+pub fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match decode_entity(rest) {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+// "&name;"の形の先頭を読み、展開した文字と読んだバイト数を返す
+fn decode_entity(text: &str) -> Option<(char, usize)> {
+    let end = text.find(';')?;
+    let name = &text[1..end];
+    let c = match name.strip_prefix('#') {
+        Some(number) => {
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse::<u32>().ok()?,
+            };
+            char::from_u32(code)?
+        }
+        None => NAMED_ENTITIES
+            .iter()
+            .find(|(entity, _)| *entity == name)
+            .map(|(_, c)| *c)?,
+    };
+    Some((c, end + 1))
+}
+
+#[cfg(test)]
+mod test {
+    use super::decode_entities;
+
+    #[test]
+    fn named() {
+        assert_eq!(decode_entities("a &lt; b &amp;&amp; c"), "a < b && c");
+        assert_eq!(decode_entities("&copy; 2023&nbsp;x"), "© 2023\u{a0}x");
+    }
+
+    #[test]
+    fn numeric() {
+        assert_eq!(decode_entities("&#12354;&#x3042;&#X41;"), "ああA");
+    }
+
+    #[test]
+    fn unknown() {
+        assert_eq!(decode_entities("AT&T"), "AT&T");
+        assert_eq!(decode_entities("&unknown; &#xZZ;"), "&unknown; &#xZZ;");
+    }
+}
@@ -0,0 +1,44 @@
+use std::fmt::{self, Display};
+
+use crate::{diagnostics::Diagnostic, span::Span};
+
+// jtmlの識別子として書けない名前とそのHTML上の位置
+#[derive(Debug, PartialEq, Clone)]
+pub enum HtmlToJtmlError {
+    InvalidTagName(String, Span),
+    InvalidAttributeName(String, Span),
+}
+
+impl Display for HtmlToJtmlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HtmlToJtmlError::InvalidTagName(name, _) => {
+                write!(f, "tag name `{}` cannot be written in jtml", name)
+            }
+            HtmlToJtmlError::InvalidAttributeName(name, _) => {
+                write!(f, "attribute name `{}` cannot be written in jtml", name)
+            }
+        }
+    }
+}
+
+impl HtmlToJtmlError {
+    pub fn span(&self) -> Span {
+        match self {
+            HtmlToJtmlError::InvalidTagName(_, span) => *span,
+            HtmlToJtmlError::InvalidAttributeName(_, span) => *span,
+        }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let (message, name) = match self {
+            HtmlToJtmlError::InvalidTagName(name, _) => ("invalid tag name", name),
+            HtmlToJtmlError::InvalidAttributeName(name, _) => ("invalid attribute name", name),
+        };
+        Diagnostic::error(message, self.span())
+            .with_label(format!("`{}` is not a jtml identifier", name))
+            .with_hint("jtml identifiers may only contain `A-Z`, `a-z`, `0-9` and `-`")
+    }
+}
+
+impl std::error::Error for HtmlToJtmlError {}
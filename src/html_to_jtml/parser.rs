@@ -0,0 +1,572 @@
+use std::collections::VecDeque;
+
+use crate::{
+    html_converter::{closes_paragraph, is_raw_text_element},
+    jtml_parser::{is_self_terminating_tag, AstRoot, Attribute, Element, Node},
+    span::Span,
+};
+
+use super::{entities::decode_entities, errors::HtmlToJtmlError};
+
+// HTMLを読み、jtmlのASTにする
+// ブラウザと同じく多少壊れたHTMLも受け付け、jtmlで表せない名前だけをエラーにする
+pub fn parse(html: &str) -> Result<AstRoot, Vec<HtmlToJtmlError>> {
+    let mut parser = HtmlParser {
+        html,
+        position: 0,
+        open: Vec::new(),
+        root: VecDeque::new(),
+        errors: Vec::new(),
+    };
+    parser.parse();
+    if !parser.errors.is_empty() {
+        return Err(parser.errors);
+    }
+    Ok(AstRoot {
+        elements: parser.root,
+    })
+}
+
+struct HtmlParser<'a> {
+    html: &'a str,
+    position: usize,
+    // 開いている要素。閉じたら親の子要素に移す
+    open: Vec<Element>,
+    root: VecDeque<Node>,
+    errors: Vec<HtmlToJtmlError>,
+}
+
+impl<'a> HtmlParser<'a> {
+    fn parse(&mut self) {
+        while !self.rest().is_empty() {
+            let rest = self.rest();
+            let next = rest.chars().nth(1);
+            if rest.starts_with("<!--") {
+                self.comment();
+            } else if starts_with_ignore_case(rest, "<!doctype") {
+                self.doctype();
+            } else if rest.starts_with("<!") || rest.starts_with("<?") {
+                // CDATAや処理命令は出力しない
+                self.skip_past(">");
+            } else if rest.starts_with("</") {
+                self.end_tag();
+            } else if rest.starts_with('<') && next.is_some_and(|c| c.is_ascii_alphabetic()) {
+                self.start_tag();
+            } else {
+                self.text();
+            }
+        }
+        while !self.open.is_empty() {
+            self.close_element();
+        }
+        normalize_whitespace(&mut self.root);
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.html[self.position..]
+    }
+
+    fn skip_past(&mut self, pattern: &str) {
+        self.position = match self.rest().find(pattern) {
+            Some(i) => self.position + i + pattern.len(),
+            None => self.html.len(),
+        };
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.read_while(|c| c.is_ascii_whitespace());
+    }
+
+    fn read_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let len = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+        self.position += len;
+        &rest[..len]
+    }
+
+    fn push_node(&mut self, node: Node) {
+        let children = match self.open.last_mut() {
+            Some(parent) => &mut parent.children,
+            None => &mut self.root,
+        };
+        // 隣り合うテキストは一つにまとめる
+        if let (Some(Node::Text(last, _)), Node::Text(text, _)) = (children.back_mut(), &node) {
+            last.push_str(text);
+            return;
+        }
+        children.push_back(node);
+    }
+
+    fn close_element(&mut self) {
+        let Some(mut element) = self.open.pop() else {
+            return;
+        };
+        let preformatted = is_preformatted(&element.tag_name)
+            || self
+                .open
+                .iter()
+                .any(|parent| is_preformatted(&parent.tag_name));
+        if !preformatted {
+            normalize_whitespace(&mut element.children);
+        }
+        self.push_node(Node::Element(element));
+    }
+
+    fn text(&mut self) {
+        // 先頭の'<'はタグにならなかったものなので、テキストとして読む
+        let len = match self.rest().char_indices().skip(1).find(|(_, c)| *c == '<') {
+            Some((i, _)) => i,
+            None => self.rest().len(),
+        };
+        let text = decode_entities(&self.rest()[..len]);
+        self.position += len;
+        self.push_node(Node::Text(text, Span::default()));
+    }
+
+    fn comment(&mut self) {
+        self.position += "<!--".len();
+        let rest = self.rest();
+        let content = match rest.find("-->") {
+            Some(end) => &rest[..end],
+            None => rest,
+        };
+        self.skip_past("-->");
+        // jtmlのコメントは一行なので、複数行のコメントは行ごとに分ける
+        for line in content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+        {
+            self.push_node(Node::Comment(line.to_string(), Span::default()));
+        }
+    }
+
+    fn doctype(&mut self) {
+        let start = self.position;
+        self.skip_past(">");
+        let doctype = self.html[start..self.position].to_string();
+        self.push_node(Node::Raw(doctype, Span::default()));
+    }
+
+    fn end_tag(&mut self) {
+        self.position += "</".len();
+        let name = self
+            .read_while(|c| !c.is_ascii_whitespace() && c != '>')
+            .to_ascii_lowercase();
+        self.skip_past(">");
+        // 対応する開始タグがなければ無視する
+        if let Some(index) = self
+            .open
+            .iter()
+            .rposition(|element| element.tag_name == name)
+        {
+            while self.open.len() > index {
+                self.close_element();
+            }
+        }
+    }
+
+    fn start_tag(&mut self) {
+        self.position += "<".len();
+        let start = self.position;
+        let tag_name = self
+            .read_while(|c| !c.is_ascii_whitespace() && c != '/' && c != '>')
+            .to_ascii_lowercase();
+        if !is_identifier(&tag_name) {
+            self.errors.push(HtmlToJtmlError::InvalidTagName(
+                tag_name.clone(),
+                Span::new(start, self.position),
+            ));
+        }
+        self.close_implied(&tag_name);
+
+        let (attributes, self_closing) = self.attributes();
+        let element = Element {
+            tag_name,
+            attributes,
+            children: VecDeque::new(),
+            span: Span::default(),
+        };
+        if self_closing || is_self_terminating_tag(&element.tag_name) {
+            self.push_node(Node::Element(element));
+            return;
+        }
+
+        let tag_name = element.tag_name.clone();
+        self.open.push(element);
+        if is_raw_text_element(&tag_name) || matches!(tag_name.as_str(), "title" | "textarea") {
+            // 終了タグまでは要素として読まない
+            let closing = format!("</{}", tag_name);
+            let rest = self.rest();
+            let len = rest
+                .to_ascii_lowercase()
+                .find(&closing)
+                .unwrap_or(rest.len());
+            let text = match is_raw_text_element(&tag_name) {
+                true => rest[..len].to_string(),
+                false => decode_entities(&rest[..len]),
+            };
+            self.position += len;
+            if !text.is_empty() {
+                self.push_node(Node::Text(text, Span::default()));
+            }
+            self.close_element();
+            self.skip_past(">");
+        } else if is_preformatted(&tag_name) && self.rest().starts_with('\n') {
+            // <pre>直後の改行はブラウザでも無視される
+            self.position += 1;
+        }
+    }
+
+    // 属性を読み、"/>"で閉じられていたかを返す
+    fn attributes(&mut self) -> (VecDeque<Attribute>, bool) {
+        let mut attributes: VecDeque<Attribute> = VecDeque::new();
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.is_empty() {
+                return (attributes, false);
+            }
+            if rest.starts_with('>') {
+                self.position += 1;
+                return (attributes, false);
+            }
+            if rest.starts_with("/>") {
+                self.position += 2;
+                return (attributes, true);
+            }
+
+            let start = self.position;
+            let name = self
+                .read_while(|c| !c.is_ascii_whitespace() && !matches!(c, '=' | '>' | '/'))
+                .to_ascii_lowercase();
+            if name.is_empty() {
+                // 名前のない'='や'/'は読み飛ばす
+                self.position += 1;
+                continue;
+            }
+            let span = Span::new(start, self.position);
+
+            self.skip_whitespace();
+            let value = match self.rest().starts_with('=') {
+                true => {
+                    self.position += 1;
+                    self.skip_whitespace();
+                    Some(self.attribute_value())
+                }
+                false => None,
+            };
+
+            if !is_identifier(&name) {
+                self.errors
+                    .push(HtmlToJtmlError::InvalidAttributeName(name, span));
+                continue;
+            }
+            // 同じ名前の属性は最初のものが使われる
+            if attributes.iter().all(|attribute| attribute.name != name) {
+                attributes.push_back(Attribute {
+                    name,
+                    value,
+                    span: Span::default(),
+                });
+            }
+        }
+    }
+
+    fn attribute_value(&mut self) -> String {
+        let rest = self.rest();
+        let value = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let len = rest[1..].find(quote).unwrap_or(rest.len() - 1);
+                self.position += (len + 2).min(rest.len());
+                &rest[1..len + 1]
+            }
+            _ => self.read_while(|c| !c.is_ascii_whitespace() && c != '>'),
+        };
+        decode_entities(value)
+    }
+
+    // 終了タグを省略できる要素を、次の開始タグで閉じる
+    fn close_implied(&mut self, tag_name: &str) {
+        while let Some(current) = self.open.last() {
+            let close = match current.tag_name.as_str() {
+                "li" => tag_name == "li",
+                "dt" | "dd" => matches!(tag_name, "dt" | "dd"),
+                "option" => matches!(tag_name, "option" | "optgroup"),
+                "optgroup" => tag_name == "optgroup",
+                "rt" | "rp" => matches!(tag_name, "rt" | "rp"),
+                "thead" | "tbody" => matches!(tag_name, "tbody" | "tfoot"),
+                "tr" => tag_name == "tr",
+                "td" | "th" => matches!(tag_name, "td" | "th" | "tr"),
+                "p" => closes_paragraph(tag_name),
+                _ => false,
+            };
+            if !close {
+                return;
+            }
+            self.close_element();
+        }
+    }
+}
+
+// jtmlの識別子として書けるか
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn is_preformatted(tag_name: &str) -> bool {
+    matches!(tag_name, "pre" | "textarea") || is_raw_text_element(tag_name)
+}
+
+fn starts_with_ignore_case(text: &str, prefix: &str) -> bool {
+    text.get(..prefix.len())
+        .is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+}
+
+// 連続する空白を一つにまとめ、要素の先頭と末尾の空白や空白だけのテキストは取り除く
+fn normalize_whitespace(children: &mut VecDeque<Node>) {
+    let last = children.len().saturating_sub(1);
+    for (i, child) in children.iter_mut().enumerate() {
+        let Node::Text(text, _) = child else {
+            continue;
+        };
+        let mut collapsed = String::with_capacity(text.len());
+        for c in text.chars() {
+            if !c.is_ascii_whitespace() {
+                collapsed.push(c);
+            } else if !collapsed.ends_with(' ') {
+                collapsed.push(' ');
+            }
+        }
+        if i == 0 {
+            collapsed = collapsed.trim_start_matches(' ').to_string();
+        }
+        if i == last {
+            collapsed = collapsed.trim_end_matches(' ').to_string();
+        }
+        *text = collapsed;
+    }
+    children
+        .retain(|child| !matches!(child, Node::Text(text, _) if text.trim_matches(' ').is_empty()));
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+
+    use crate::{
+        html_to_jtml::HtmlToJtmlError,
+        jtml_parser::{AstRoot, Attribute, Element, Node},
+        span::Span,
+    };
+
+    use super::parse;
+
+    fn element(tag_name: &str, attributes: Vec<Attribute>, children: Vec<Node>) -> Node {
+        Node::Element(Element {
+            tag_name: tag_name.to_string(),
+            attributes: VecDeque::from(attributes),
+            children: VecDeque::from(children),
+            span: Span::default(),
+        })
+    }
+
+    fn text(text: &str) -> Node {
+        Node::Text(text.to_string(), Span::default())
+    }
+
+    #[test]
+    fn void_element() {
+        let result = parse(r#"<p>a<br>b<img src="x.png"/></p>"#).unwrap();
+        assert_eq!(
+            result.elements,
+            VecDeque::from(vec![element(
+                "p",
+                vec![],
+                vec![
+                    text("a"),
+                    element("br", vec![], vec![]),
+                    text("b"),
+                    element("img", vec![Attribute::new("src", "x.png")], vec![]),
+                ]
+            )])
+        );
+    }
+
+    #[test]
+    fn attributes() {
+        let result = parse(r#"<INPUT Type=text value='a &amp; "b"' disabled title="">"#).unwrap();
+        assert_eq!(
+            result.elements,
+            VecDeque::from(vec![element(
+                "input",
+                vec![
+                    Attribute::new("type", "text"),
+                    Attribute::new("value", "a & \"b\""),
+                    Attribute::boolean("disabled"),
+                    Attribute::new("title", ""),
+                ],
+                vec![]
+            )])
+        );
+    }
+
+    #[test]
+    fn comment_and_doctype() {
+        let result = parse("<!DOCTYPE html>\n<!-- first\n  second -->\n<p></p>").unwrap();
+        assert_eq!(
+            result.elements,
+            VecDeque::from(vec![
+                Node::Raw("<!DOCTYPE html>".to_string(), Span::default()),
+                Node::Comment("first".to_string(), Span::default()),
+                Node::Comment("second".to_string(), Span::default()),
+                element("p", vec![], vec![]),
+            ])
+        );
+    }
+
+    #[test]
+    fn whitespace() {
+        let result =
+            parse("<div>\n  <p>\n    Hello\n    <b>big</b>  world\n  </p>\n</div>").unwrap();
+        assert_eq!(
+            result.elements,
+            VecDeque::from(vec![element(
+                "div",
+                vec![],
+                vec![element(
+                    "p",
+                    vec![],
+                    vec![
+                        text("Hello "),
+                        element("b", vec![], vec![text("big")]),
+                        text(" world"),
+                    ]
+                )]
+            )])
+        );
+
+        let result = parse("<pre>\n  a\n    b</pre>").unwrap();
+        assert_eq!(
+            result.elements,
+            VecDeque::from(vec![element("pre", vec![], vec![text("  a\n    b")])])
+        );
+    }
+
+    #[test]
+    fn raw_text() {
+        let result =
+            parse("<script>if (a<b && c) { '</p>' }</SCRIPT><title>a &amp; b</title>").unwrap();
+        assert_eq!(
+            result,
+            AstRoot {
+                elements: VecDeque::from(vec![
+                    element("script", vec![], vec![text("if (a<b && c) { '</p>' }")]),
+                    element("title", vec![], vec![text("a & b")]),
+                ])
+            }
+        );
+    }
+
+    #[test]
+    fn implied_end_tag() {
+        let result = parse("<ul><li>a<li>b</ul><p>x<div>y</div></span>").unwrap();
+        assert_eq!(
+            result.elements,
+            VecDeque::from(vec![
+                element(
+                    "ul",
+                    vec![],
+                    vec![
+                        element("li", vec![], vec![text("a")]),
+                        element("li", vec![], vec![text("b")]),
+                    ]
+                ),
+                element("p", vec![], vec![text("x")]),
+                element("div", vec![], vec![text("y")]),
+            ])
+        );
+    }
+
+    #[test]
+    fn implied_end_tag_in_select_ruby_table() {
+        let result = parse(
+            "<select><optgroup label=g><option>x<option>y<optgroup><option></select>\
+             <ruby>漢<rp>(<rt>kan<rp>)</ruby>\
+             <table><thead><tr><th>h</tr><tbody><tr><td>1</tr><tbody><tfoot></table>\
+             <p>a<search>b</search><p>c<hgroup></hgroup>",
+        )
+        .unwrap();
+        let option = |children| element("option", vec![], children);
+        assert_eq!(
+            result.elements,
+            VecDeque::from(vec![
+                element(
+                    "select",
+                    vec![],
+                    vec![
+                        element(
+                            "optgroup",
+                            vec![Attribute::new("label", "g")],
+                            vec![option(vec![text("x")]), option(vec![text("y")])]
+                        ),
+                        element("optgroup", vec![], vec![option(vec![])]),
+                    ]
+                ),
+                element(
+                    "ruby",
+                    vec![],
+                    vec![
+                        text("漢"),
+                        element("rp", vec![], vec![text("(")]),
+                        element("rt", vec![], vec![text("kan")]),
+                        element("rp", vec![], vec![text(")")]),
+                    ]
+                ),
+                element(
+                    "table",
+                    vec![],
+                    vec![
+                        element(
+                            "thead",
+                            vec![],
+                            vec![element(
+                                "tr",
+                                vec![],
+                                vec![element("th", vec![], vec![text("h")])]
+                            )]
+                        ),
+                        element(
+                            "tbody",
+                            vec![],
+                            vec![element(
+                                "tr",
+                                vec![],
+                                vec![element("td", vec![], vec![text("1")])]
+                            )]
+                        ),
+                        element("tbody", vec![], vec![]),
+                        element("tfoot", vec![], vec![]),
+                    ]
+                ),
+                // 縮めて出力する場合と同じ要素でpを閉じる
+                element("p", vec![], vec![text("a")]),
+                element("search", vec![], vec![text("b")]),
+                element("p", vec![], vec![text("c")]),
+                element("hgroup", vec![], vec![]),
+            ])
+        );
+    }
+
+    #[test]
+    fn invalid_name() {
+        let errors = parse(r#"<svg:rect x="1" @click="f"></svg:rect>"#).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                HtmlToJtmlError::InvalidTagName("svg:rect".to_string(), Span::new(1, 9)),
+                HtmlToJtmlError::InvalidAttributeName("@click".to_string(), Span::new(16, 22)),
+            ]
+        );
+    }
+}
@@ -1,14 +1,18 @@
 use logos::Logos;
-use std::{
-    collections::VecDeque,
-    fmt::{self, Display},
-};
+use std::fmt::{self, Display};
+
+use crate::{diagnostics::Diagnostic, span::Span};
+
+mod selector;
+
+pub use selector::Selector;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Kind {
     StringLiteral,
     Comment,
     Identifier,
+    Selector,
     LeftBracket,
     RightBracket,
     LeftParen,
@@ -17,12 +21,30 @@ pub enum Kind {
     Whitespace,
 }
 
+impl Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Kind::StringLiteral => write!(f, "string literal"),
+            Kind::Comment => write!(f, "comment"),
+            Kind::Identifier => write!(f, "identifier"),
+            Kind::Selector => write!(f, "selector"),
+            Kind::LeftBracket => write!(f, "'{{'"),
+            Kind::RightBracket => write!(f, "'}}'"),
+            Kind::LeftParen => write!(f, "'('"),
+            Kind::RightParen => write!(f, "')'"),
+            Kind::Equal => write!(f, "'='"),
+            Kind::Whitespace => write!(f, "whitespace"),
+        }
+    }
+}
+
 impl From<JtmlToken> for Kind {
     fn from(token: JtmlToken) -> Self {
         match token {
             JtmlToken::StringLiteral(_) => Kind::StringLiteral,
             JtmlToken::Comment(_) => Kind::Comment,
             JtmlToken::Identifier(_) => Kind::Identifier,
+            JtmlToken::Selector(_) => Kind::Selector,
             JtmlToken::LeftBracket => Kind::LeftBracket,
             JtmlToken::RightBracket => Kind::RightBracket,
             JtmlToken::LeftParen => Kind::LeftParen,
@@ -34,20 +56,26 @@ impl From<JtmlToken> for Kind {
 }
 
 #[derive(Logos, Debug, PartialEq, Clone)]
+#[logos(error = LexerError)]
 pub enum JtmlToken {
-    #[regex(r#""([^"\\]|\\t|\\u|\\n|\\")*""#, |lex| lex.slice().trim_matches('"').to_string())]
+    // エスケープシーケンスはここで展開し、ASTには展開後の値を持たせる
+    #[regex(r#""([^"\\]|\\[\s\S])*""#, |lex| unescape(lex.slice(), lex.span().start))]
     StringLiteral(String),
 
     // #[regex(r#"/\*[^*/]*\*/"#)]
     #[regex(r#"//.*"#, |lex| {
-        let comment = lex.slice()[2..].to_string();
+        let comment = lex.slice()[2..].trim_end_matches('\r').to_string();
         comment.trim_start_matches("//").trim_start_matches(' ').to_string()
-    })]
+    }, allow_greedy = true)]
     Comment(String),
 
     #[regex(r#"[0-9A-Za-z\-]+"#, |lex| lex.slice().to_string())]
     Identifier(String),
 
+    // 構文解析でclass, id属性に展開する
+    #[regex(r#"[0-9A-Za-z\-]*([.#][0-9A-Za-z\-]+)+"#, |lex| Selector::parse(lex.slice(), lex.span().start))]
+    Selector(Selector),
+
     #[token("{")]
     LeftBracket,
 
@@ -63,55 +91,253 @@ pub enum JtmlToken {
     #[token("=")]
     Equal,
 
-    #[regex(r"\s+", logos::skip)]
+    // 構文木には不要だが、元のソースを復元できるようにトークンとして残す
+    #[regex(r"\s+")]
     Whitespace,
 }
 
 impl Display for JtmlToken {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            JtmlToken::StringLiteral(string) => write!(f, "Text({})", string),
-            JtmlToken::Comment(string) => write!(f, "Comment({})", string),
-            JtmlToken::LeftBracket => write!(f, "LeftBracket '{{'"),
-            JtmlToken::RightBracket => write!(f, "RightBracket '}}'"),
-            JtmlToken::LeftParen => write!(f, "LeftBrace '('"),
-            JtmlToken::RightParen => write!(f, "RightBrace ')'"),
-            JtmlToken::Equal => write!(f, "Equal '='"),
-            JtmlToken::Identifier(_string) => write!(f, "Id({})", self),
-            JtmlToken::Whitespace => write!(f, ""),
+            JtmlToken::StringLiteral(string) => write!(f, "string literal \"{}\"", string),
+            JtmlToken::Comment(string) => write!(f, "comment `// {}`", string),
+            JtmlToken::LeftBracket => write!(f, "'{{'"),
+            JtmlToken::RightBracket => write!(f, "'}}'"),
+            JtmlToken::LeftParen => write!(f, "'('"),
+            JtmlToken::RightParen => write!(f, "')'"),
+            JtmlToken::Equal => write!(f, "'='"),
+            JtmlToken::Identifier(string) => write!(f, "identifier `{}`", string),
+            JtmlToken::Selector(selector) => write!(f, "selector `{}`", selector),
+            JtmlToken::Whitespace => write!(f, "whitespace"),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+// ソース上の位置を持ったトークン
+#[derive(Debug, PartialEq, Clone)]
+pub struct Token {
+    pub token: JtmlToken,
+    pub span: Span,
+}
+
+impl Token {
+    pub fn new(token: JtmlToken, span: Span) -> Self {
+        Token { token, span }
+    }
+
+    pub fn kind(&self) -> Kind {
+        self.token.clone().into()
+    }
+
+    // 空白とコメント
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.token, JtmlToken::Whitespace | JtmlToken::Comment(_))
+    }
+}
+
+impl PartialEq<JtmlToken> for Token {
+    fn eq(&self, other: &JtmlToken) -> bool {
+        &self.token == other
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.token)
+    }
+}
+
+// 文字列リテラルの中身(前後の`"`を含む)を展開する
+// 使えるのは \\ \" \n \t \u{XXXX} のみで、それ以外はそのシーケンスを指すエラーにする
+fn unescape(literal: &str, offset: usize) -> Result<String, LexerError> {
+    let content = &literal[1..literal.len() - 1];
+    let offset = offset + 1;
+    let mut value = String::with_capacity(content.len());
+    let mut chars = content.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        let invalid = |end: usize| {
+            LexerError::InvalidEscape(
+                content[start..end].to_string(),
+                Span::new(offset + start, offset + end),
+            )
+        };
+        let Some((i, escaped)) = chars.next() else {
+            return Err(invalid(content.len()));
+        };
+        let end = i + escaped.len_utf8();
+        match escaped {
+            '\\' => value.push('\\'),
+            '"' => value.push('"'),
+            'n' => value.push('\n'),
+            't' => value.push('\t'),
+            'u' => {
+                if chars.next_if(|(_, c)| *c == '{').is_none() {
+                    return Err(invalid(end));
+                }
+                let mut code = String::new();
+                let mut end = end + 1;
+                while let Some((i, c)) = chars.next_if(|(_, c)| *c != '}' && *c != '"') {
+                    code.push(c);
+                    end = i + c.len_utf8();
+                }
+                let Some((i, _)) = chars.next_if(|(_, c)| *c == '}') else {
+                    return Err(invalid(end));
+                };
+                let end = i + 1;
+                let decoded = match code.len() {
+                    1..=6 => u32::from_str_radix(&code, 16).ok().and_then(char::from_u32),
+                    _ => None,
+                };
+                match decoded {
+                    Some(c) => value.push(c),
+                    None => return Err(invalid(end)),
+                }
+            }
+            _ => return Err(invalid(end)),
+        }
+    }
+    Ok(value)
+}
+
+// 展開後の値のindexを、文字列リテラル(前後の`"`を含む)の中の位置に戻す
+// 展開後の値から見つけたものを元のソース上で指すために使う
+pub fn literal_offset(literal: &str, index: usize) -> usize {
+    let mut decoded = 0;
+    let mut position = 1;
+    while decoded < index && position + 1 < literal.len() {
+        let rest = &literal[position..literal.len() - 1];
+        let length = match rest.strip_prefix("\\u{") {
+            Some(code) => code.find('}').map_or(rest.len(), |end| end + 4),
+            None if rest.starts_with('\\') => 2,
+            None => rest.chars().next().map_or(1, char::len_utf8),
+        };
+        let sequence = format!("\"{}\"", &rest[..length]);
+        decoded += unescape(&sequence, 0).map_or(length, |value| value.len());
+        position += length;
+    }
+    position
+}
+
+// unescapeの逆。to_jtmlで文字列リテラルとして出力する際に使う
+pub fn escape_string_literal(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// logosのエラー型としても使うため、InvalidTokenの中身はlexer()で埋める
+#[derive(Debug, PartialEq, Clone)]
 pub enum LexerError {
-    InvalidToken(String),
+    InvalidToken(String, Span),
+    // 不正なエスケープシーケンスとその位置
+    InvalidEscape(String, Span),
+    // idを複数持つセレクタと、二つ目のidの位置
+    InvalidSelector(String, Span),
+}
+
+impl Default for LexerError {
+    fn default() -> Self {
+        LexerError::InvalidToken(String::new(), Span::default())
+    }
 }
 
-pub fn lexer(text: String) -> Result<VecDeque<JtmlToken>, LexerError> {
-    let mut result: VecDeque<JtmlToken> = VecDeque::new();
-    let mut lexer = JtmlToken::lexer(text.as_str());
+impl Display for LexerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LexerError::InvalidToken(text, _) => write!(f, "invalid token `{}`", text),
+            LexerError::InvalidEscape(text, _) => {
+                write!(f, "invalid escape sequence `{}`", text)
+            }
+            LexerError::InvalidSelector(text, _) => write!(f, "invalid selector `{}`", text),
+        }
+    }
+}
+
+impl LexerError {
+    pub fn span(&self) -> Span {
+        match self {
+            LexerError::InvalidToken(_, span) => *span,
+            LexerError::InvalidEscape(_, span) => *span,
+            LexerError::InvalidSelector(_, span) => *span,
+        }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            LexerError::InvalidToken(text, span) => {
+                let diagnostic = Diagnostic::error("invalid token", *span)
+                    .with_label(format!("`{}` is not a valid token", text));
+                if text.starts_with('"') {
+                    diagnostic.with_hint("string literal is not terminated; close it with `\"`")
+                } else if text.starts_with('/') {
+                    diagnostic.with_hint("comments start with `//`")
+                } else {
+                    diagnostic
+                }
+            }
+            LexerError::InvalidEscape(text, span) => {
+                Diagnostic::error("invalid escape sequence", *span)
+                    .with_label(format!("`{}` is not a valid escape", text))
+                    .with_hint("valid escapes are `\\\\`, `\\\"`, `\\n`, `\\t` and `\\u{XXXX}`")
+            }
+            LexerError::InvalidSelector(text, span) => Diagnostic::error("invalid selector", *span)
+                .with_label(format!("`{}` has more than one id", text))
+                .with_hint("an element can only have one `#id`"),
+        }
+    }
+}
+
+// 空白を除いたトークン列
+pub fn lexer(text: String) -> Result<Vec<Token>, LexerError> {
+    let mut tokens = lossless_lexer(&text)?;
+    tokens.retain(|token| token.token != JtmlToken::Whitespace);
+    Ok(tokens)
+}
+
+// 空白も含めたトークン列。全てのトークンのテキストをつなげると元のソースになる
+pub fn lossless_lexer(text: &str) -> Result<Vec<Token>, LexerError> {
+    let mut result: Vec<Token> = Vec::new();
+    let mut lexer = JtmlToken::lexer(text);
     while let Some(token) = lexer.next() {
         match token {
-            Ok(token) => result.push_back(token),
-            Err(_) => return Err(LexerError::InvalidToken(lexer.slice().to_string())),
+            Ok(token) => result.push(Token::new(token, lexer.span().into())),
+            Err(LexerError::InvalidToken(_, _)) => {
+                return Err(LexerError::InvalidToken(
+                    lexer.slice().to_string(),
+                    lexer.span().into(),
+                ))
+            }
+            Err(error) => return Err(error),
         }
     }
-    return Ok(result);
+    Ok(result)
 }
 
 #[cfg(test)]
 mod test {
     use std::collections::VecDeque;
 
-    use crate::jtml_lexer::LexerError;
+    use crate::{jtml_lexer::LexerError, span::Span};
 
-    use super::JtmlToken;
+    use super::{JtmlToken, Token};
 
     // test 関数内で使ってるのにdead_code warningが出るため
     #[allow(dead_code)]
-    fn lexer(str: &str) -> VecDeque<JtmlToken> {
-        super::lexer(str.to_string()).unwrap()
+    fn lexer(str: &str) -> VecDeque<Token> {
+        VecDeque::from(super::lexer(str.to_string()).unwrap())
     }
 
     #[test]
@@ -137,13 +363,100 @@ mod test {
     #[test]
     fn invalid_string_literal() {
         let error = super::lexer("\"string".to_string()).unwrap_err();
-        assert_eq!(error, LexerError::InvalidToken("\"string".to_string()));
+        assert_eq!(
+            error,
+            LexerError::InvalidToken("\"string".to_string(), Span::new(0, 7))
+        );
     }
 
     #[test]
     fn invalid_string_literal_not_terminal() {
         let error = super::lexer("string\"".to_string()).unwrap_err();
-        assert_eq!(error, LexerError::InvalidToken("\"".to_string()));
+        assert_eq!(
+            error,
+            LexerError::InvalidToken("\"".to_string(), Span::new(6, 7))
+        );
+    }
+
+    #[test]
+    fn escape_sequence() {
+        let mut parsed = lexer(r#""a\\b \"q\" \n\t \u{3042}\u{1F600}""#);
+        assert_eq!(
+            parsed.pop_front().unwrap(),
+            JtmlToken::StringLiteral("a\\b \"q\" \n\t \u{3042}\u{1F600}".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_escape_sequence() {
+        let cases = [
+            (r#"p(){"ab\qc"}"#, r"\q", (7, 9)),
+            (r#""\u{110000}""#, r"\u{110000}", (1, 11)),
+            (r#""\u{}""#, r"\u{}", (1, 5)),
+            (r#""\u0041""#, r"\u", (1, 3)),
+            (r#""\u{41""#, r"\u{41", (1, 6)),
+            (r#""あ\x""#, r"\x", (4, 6)),
+        ];
+        for (source, sequence, (start, end)) in cases {
+            let error = super::lexer(source.to_string()).unwrap_err();
+            assert_eq!(
+                error,
+                LexerError::InvalidEscape(sequence.to_string(), Span::new(start, end)),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn escape_string_literal() {
+        let value = "a\\b \"q\" \n\t\u{7}あ";
+        let escaped = super::escape_string_literal(value);
+        assert_eq!(escaped, r#"a\\b \"q\" \n\t\u{7}あ"#);
+        assert_eq!(
+            lexer(&format!("\"{}\"", escaped)).pop_front().unwrap(),
+            JtmlToken::StringLiteral(value.to_string())
+        );
+    }
+
+    #[test]
+    fn literal_offset() {
+        let literal = r#""a\n\u{3042}{{ b }}""#;
+        let value = "a\nあ{{ b }}";
+        let index = value.find("{{").unwrap();
+        assert_eq!(super::literal_offset(literal, index), 12);
+        assert_eq!(
+            super::literal_offset(literal, value.len()),
+            literal.len() - 1
+        );
+        assert_eq!(super::literal_offset(literal, 0), 1);
+    }
+
+    #[test]
+    fn selector() {
+        let mut parsed = lexer(r#"div.card#main() .a(){} p"#);
+        let selector = parsed.pop_front().unwrap();
+        assert_eq!(selector.kind(), super::Kind::Selector);
+        assert_eq!((selector.span.start, selector.span.end), (0, 13));
+        assert_eq!(
+            selector.token,
+            JtmlToken::Selector(super::Selector {
+                tag_name: Some("div".to_string()),
+                classes: vec!["card".to_string()],
+                id: Some("main".to_string()),
+            })
+        );
+        assert_eq!(parsed[2].kind(), super::Kind::Selector);
+        assert_eq!(
+            parsed.back().unwrap().token,
+            JtmlToken::Identifier("p".to_string())
+        );
+
+        let error = super::lexer("p#a#b(){}".to_string()).unwrap_err();
+        assert_eq!(
+            error,
+            LexerError::InvalidSelector("p#a#b".to_string(), Span::new(3, 5))
+        );
     }
 
     #[test]
@@ -155,7 +468,14 @@ mod test {
     #[test]
     fn invalid_line_comment() {
         let error = super::lexer((r#"/ comment"#).to_string()).unwrap_err();
-        assert_eq!(error, LexerError::InvalidToken("/".to_string()));
+        assert_eq!(
+            error,
+            LexerError::InvalidToken("/".to_string(), Span::new(0, 1))
+        );
+        assert_eq!(
+            error.to_diagnostic().hint,
+            Some("comments start with `//`".to_string())
+        );
     }
 
     #[test]
@@ -317,14 +637,48 @@ mod test {
 
         assert_eq!(parsed.pop_front().unwrap(), JtmlToken::RightBracket);
     }
+
+    #[test]
+    fn lossless() {
+        let source = "p(a=\"b\") {\r\n  // c\r\n\n\t\"d\"}\n";
+        let tokens = super::lossless_lexer(source).unwrap();
+        let text = tokens
+            .iter()
+            .map(|token| &source[token.span.range()])
+            .collect::<String>();
+        assert_eq!(text, source);
+        assert_eq!(tokens[9], JtmlToken::Comment("c".to_string()));
+        assert!(tokens[9].is_trivia());
+    }
+
+    #[test]
+    fn token_span() {
+        let parsed = lexer("p(a=\"b\") {\n  // c\n}");
+        let spans = parsed
+            .iter()
+            .map(|token| (token.span.start, token.span.end))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            vec![
+                (0, 1),
+                (1, 2),
+                (2, 3),
+                (3, 4),
+                (4, 7),
+                (7, 8),
+                (9, 10),
+                (13, 17),
+                (18, 19)
+            ]
+        );
+    }
 }
 
 pub mod test_utils {
-    use std::collections::VecDeque;
+    use super::Token;
 
-    use super::JtmlToken;
-
-    pub fn lexer(str: &str) -> VecDeque<JtmlToken> {
-        VecDeque::from(super::lexer(str.to_string()).unwrap())
+    pub fn lexer(str: &str) -> Vec<Token> {
+        super::lexer(str.to_string()).unwrap()
     }
 }
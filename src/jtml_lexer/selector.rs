@@ -0,0 +1,132 @@
+use std::fmt::{self, Display};
+
+use crate::span::Span;
+
+use super::LexerError;
+
+// `div.card#main`や`.card`のように要素名の後にclassとidを続けた書き方
+// 要素名を省略した場合はdivになる
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Selector {
+    pub tag_name: Option<String>,
+    pub classes: Vec<String>,
+    pub id: Option<String>,
+}
+
+fn is_name(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+impl Selector {
+    pub fn new(tag_name: &str) -> Self {
+        Selector {
+            tag_name: Some(tag_name.to_string()),
+            ..Selector::default()
+        }
+    }
+
+    // 正規表現で形は確かめてあるため、idが複数ある場合のみエラーにする
+    pub(super) fn parse(text: &str, offset: usize) -> Result<Selector, LexerError> {
+        let mut selector = Selector::default();
+        let mut start = 0;
+        for (i, c) in text.char_indices().chain([(text.len(), '.')]).skip(1) {
+            if c != '.' && c != '#' {
+                continue;
+            }
+            let part = &text[start..i];
+            match part.chars().next() {
+                Some('.') => selector.classes.push(part[1..].to_string()),
+                Some('#') if selector.id.is_some() => {
+                    return Err(LexerError::InvalidSelector(
+                        text.to_string(),
+                        Span::new(offset + start, offset + i),
+                    ))
+                }
+                Some('#') => selector.id = Some(part[1..].to_string()),
+                Some(_) => selector.tag_name = Some(part.to_string()),
+                None => (),
+            }
+            start = i;
+        }
+        Ok(selector)
+    }
+
+    pub fn tag_name(&self) -> &str {
+        self.tag_name.as_deref().unwrap_or("div")
+    }
+
+    // `name="value"`の属性をclassまたはidとして取り込めた場合はtrueを返す
+    pub fn absorb(&mut self, name: &str, value: &str) -> bool {
+        match name {
+            "id" if self.id.is_none() && is_name(value) => {
+                self.id = Some(value.to_string());
+                true
+            }
+            "class"
+                if value.split_whitespace().next().is_some()
+                    && value.split_whitespace().all(is_name) =>
+            {
+                self.classes
+                    .extend(value.split_whitespace().map(|class| class.to_string()));
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+// 省略できる場合はdivを書かない
+impl Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let tag_name = self.tag_name();
+        if tag_name != "div" || (self.classes.is_empty() && self.id.is_none()) {
+            write!(f, "{}", tag_name)?;
+        }
+        for class in &self.classes {
+            write!(f, ".{}", class)?;
+        }
+        if let Some(id) = &self.id {
+            write!(f, "#{}", id)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{jtml_lexer::LexerError, span::Span};
+
+    use super::Selector;
+
+    #[test]
+    fn parse() {
+        let selector = Selector::parse("p.a#b.c", 0).unwrap();
+        assert_eq!(selector.tag_name.as_deref(), Some("p"));
+        assert_eq!(selector.classes, vec!["a", "c"]);
+        assert_eq!(selector.id.as_deref(), Some("b"));
+        assert_eq!(selector.to_string(), "p.a.c#b");
+
+        let selector = Selector::parse(".card", 0).unwrap();
+        assert_eq!(selector.tag_name(), "div");
+        assert_eq!(selector.to_string(), ".card");
+
+        assert_eq!(
+            Selector::parse("div#a.b#c", 3),
+            Err(LexerError::InvalidSelector(
+                "div#a.b#c".to_string(),
+                Span::new(10, 12)
+            ))
+        );
+    }
+
+    #[test]
+    fn absorb() {
+        let mut selector = Selector::new("div");
+        assert!(selector.absorb("class", " a  b "));
+        assert!(selector.absorb("id", "main"));
+        assert!(!selector.absorb("id", "other"));
+        assert!(!selector.absorb("class", "a:b"));
+        assert!(!selector.absorb("title", "x"));
+        assert_eq!(selector.to_string(), ".a.b#main");
+    }
+}
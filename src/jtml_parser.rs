@@ -1,4 +1,39 @@
+pub mod cst;
 mod errors;
 mod parsers;
 pub use errors::ParserError;
-pub use parsers::parse;
+pub use parsers::{
+    ast::{
+        node::{
+            Attribute, Component, Element, For, If, BLOCK_TAG, DEFINE_TAG, ELSE_TAG, EXTENDS_TAG,
+            FOR_TAG, IF_TAG, IMPORT_TAG, INCLUDE_TAG, RAW_HTML_TAG, SLOT_TAG,
+        },
+        root::AstRoot,
+        Node,
+    },
+    is_self_terminating_tag, parse, parse_recovering,
+};
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use std::collections::VecDeque;
+
+    use crate::span::Span;
+
+    use super::{AstRoot, Node};
+
+    // 位置を全て既定値にしたAST。手書きのASTと構文解析の結果を比べるために使う
+    pub fn without_spans(ast: &AstRoot) -> AstRoot {
+        AstRoot {
+            elements: strip(&ast.elements),
+        }
+    }
+
+    pub fn strip(nodes: &VecDeque<Node>) -> VecDeque<Node> {
+        nodes.iter().map(strip_node).collect()
+    }
+
+    pub fn strip_node(node: &Node) -> Node {
+        node.map_spans(&|_| Span::default())
+    }
+}
@@ -0,0 +1,90 @@
+use crate::{
+    jtml_lexer::{Kind, Token},
+    span::Span,
+};
+
+mod parser;
+
+pub use parser::parse;
+
+// 空白やコメントも含めて全てのトークンを持つ構文木
+// ノードのトークンを順につなげると元のソースに戻る
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    Document,
+    // 名前, 属性リスト, 本体
+    Element,
+    // '(' から ')' まで
+    AttributeList,
+    // 名前 '=' 文字列リテラル
+    Attribute,
+    // '{' から '}' まで
+    Body,
+    // どの構文にも当てはまらなかったトークン
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(Token),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    pub fn new(kind: SyntaxKind) -> Self {
+        SyntaxNode {
+            kind,
+            children: Vec::new(),
+        }
+    }
+
+    // 子孫を含めた全てのトークン
+    pub fn tokens(&self) -> Vec<&Token> {
+        let mut tokens = Vec::new();
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+
+    // トークンを一つも持たない場合はNone
+    pub fn span(&self) -> Option<Span> {
+        let tokens = self.tokens();
+        Some(tokens.first()?.span.to(tokens.last()?.span))
+    }
+
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        match self.span() {
+            Some(span) => &source[span.range()],
+            None => "",
+        }
+    }
+
+    pub fn child_nodes(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    pub fn child_node(&self, kind: SyntaxKind) -> Option<&SyntaxNode> {
+        self.child_nodes().find(|node| node.kind == kind)
+    }
+
+    // 直下のトークンのうち、最初に見つかった指定の種類のもの
+    pub fn child_token(&self, kind: Kind) -> Option<&Token> {
+        self.children.iter().find_map(|child| match child {
+            SyntaxElement::Token(token) if token.kind() == kind => Some(token),
+            _ => None,
+        })
+    }
+}
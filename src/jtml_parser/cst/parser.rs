@@ -0,0 +1,275 @@
+use crate::{
+    jtml_lexer::{JtmlToken, Token},
+    jtml_parser::DEFINE_TAG,
+};
+
+use super::{SyntaxElement, SyntaxKind, SyntaxNode};
+
+// 空白を含むトークン列から構文木を作る
+// 構文エラーがあってもトークンは全てどこかのノードに入るため、ソースは常に復元できる
+// エラーの報告はASTのパーサーが行う
+pub fn parse(tokens: &[Token]) -> SyntaxNode {
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+    };
+    let mut document = SyntaxNode::new(SyntaxKind::Document);
+    while parser.peek().is_some() {
+        parser.item(&mut document);
+    }
+    document
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.position)
+    }
+
+    // 空白とコメントを飛ばした次のトークン
+    fn peek_significant(&self) -> Option<&'a Token> {
+        self.tokens[self.position..]
+            .iter()
+            .find(|token| !token.is_trivia())
+    }
+
+    fn bump(&mut self, node: &mut SyntaxNode) {
+        if let Some(token) = self.peek() {
+            node.children.push(SyntaxElement::Token(token.clone()));
+            self.position += 1;
+        }
+    }
+
+    fn bump_trivia(&mut self, node: &mut SyntaxNode) {
+        while self.peek().is_some_and(|token| token.is_trivia()) {
+            self.bump(node);
+        }
+    }
+
+    fn error(&mut self, parent: &mut SyntaxNode) {
+        let mut error = SyntaxNode::new(SyntaxKind::Error);
+        self.bump(&mut error);
+        parent.children.push(SyntaxElement::Node(error));
+    }
+
+    // ドキュメントや本体の中身を一つ読む
+    fn item(&mut self, parent: &mut SyntaxNode) {
+        match self.peek().map(|token| &token.token) {
+            Some(JtmlToken::Identifier(_) | JtmlToken::Selector(_)) => {
+                let element = self.element();
+                parent.children.push(SyntaxElement::Node(element));
+            }
+            Some(JtmlToken::StringLiteral(_) | JtmlToken::Comment(_) | JtmlToken::Whitespace) => {
+                self.bump(parent)
+            }
+            Some(_) => self.error(parent),
+            None => (),
+        }
+    }
+
+    fn element(&mut self) -> SyntaxNode {
+        let mut element = SyntaxNode::new(SyntaxKind::Element);
+        // `define name(...)`は二つの識別子を要素の名前として読む
+        let define = self
+            .peek()
+            .is_some_and(|token| token.token == JtmlToken::Identifier(DEFINE_TAG.to_string()));
+        self.bump(&mut element);
+        if define
+            && matches!(
+                self.peek_significant().map(|token| &token.token),
+                Some(JtmlToken::Identifier(_))
+            )
+        {
+            self.bump_trivia(&mut element);
+            self.bump(&mut element);
+        }
+        // 名前と'('の間や')'と'{'の間の空白は要素に含める
+        if self.at_significant(&JtmlToken::LeftParen) {
+            self.bump_trivia(&mut element);
+            let mut attributes = SyntaxNode::new(SyntaxKind::AttributeList);
+            self.bump(&mut attributes);
+            self.attribute_list(&mut attributes);
+            element.children.push(SyntaxElement::Node(attributes));
+        }
+        if self.at_significant(&JtmlToken::LeftBracket) {
+            self.bump_trivia(&mut element);
+            let mut body = SyntaxNode::new(SyntaxKind::Body);
+            self.bump(&mut body);
+            self.body(&mut body);
+            element.children.push(SyntaxElement::Node(body));
+        }
+        element
+    }
+
+    // 空白とコメントを飛ばした次のトークンがexpectであるか
+    fn at_significant(&self, expect: &JtmlToken) -> bool {
+        self.peek_significant().is_some_and(|token| token == expect)
+    }
+
+    fn attribute_list(&mut self, attributes: &mut SyntaxNode) {
+        while let Some(token) = self.peek() {
+            match token.token {
+                JtmlToken::Identifier(_) => {
+                    let attribute = self.attribute();
+                    attributes.children.push(SyntaxElement::Node(attribute));
+                }
+                JtmlToken::Whitespace | JtmlToken::Comment(_) => self.bump(attributes),
+                JtmlToken::RightParen => {
+                    self.bump(attributes);
+                    return;
+                }
+                // 閉じられていない属性リストは本体の開始で終わりにする
+                JtmlToken::LeftBracket | JtmlToken::RightBracket => return,
+                _ => self.error(attributes),
+            }
+        }
+    }
+
+    fn attribute(&mut self) -> SyntaxNode {
+        let mut attribute = SyntaxNode::new(SyntaxKind::Attribute);
+        self.bump(&mut attribute);
+        if !self.at_significant(&JtmlToken::Equal) {
+            return attribute;
+        }
+        self.bump_trivia(&mut attribute);
+        self.bump(&mut attribute);
+        if let Some(JtmlToken::StringLiteral(_)) = self.peek_significant().map(|token| &token.token)
+        {
+            self.bump_trivia(&mut attribute);
+            self.bump(&mut attribute);
+        }
+        attribute
+    }
+
+    fn body(&mut self, body: &mut SyntaxNode) {
+        while let Some(token) = self.peek() {
+            match token.token {
+                JtmlToken::RightBracket => {
+                    self.bump(body);
+                    return;
+                }
+                _ => self.item(body),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        jtml_lexer::{lossless_lexer, JtmlToken, Kind},
+        jtml_parser::cst::{SyntaxElement, SyntaxKind, SyntaxNode},
+    };
+
+    use super::parse;
+
+    fn cst(source: &str) -> SyntaxNode {
+        parse(&lossless_lexer(source).unwrap())
+    }
+
+    fn concat(node: &SyntaxNode, source: &str) -> String {
+        node.tokens()
+            .iter()
+            .map(|token| &source[token.span.range()])
+            .collect()
+    }
+
+    #[test]
+    fn lossless() {
+        for source in [
+            "",
+            "p(){}",
+            "html(lang=\"ja\"){\n    // comment\n\n\n    body(  ) { \"text\" }\n}\n",
+            "img(\n    // alt is required\n    alt=\"\" // trailing\n    src=\"a.png\"\n)",
+            // 構文エラーがあっても全てのトークンが残る
+            ") p({ a= b=\"c\" ) } \"d\" p(",
+            "}}}{{{ ((( === ",
+        ] {
+            assert_eq!(concat(&cst(source), source), source);
+        }
+    }
+
+    #[test]
+    fn comment_in_attribute_list() {
+        let source = "img(\n    // first\n    alt=\"\" // second\n)";
+        let document = cst(source);
+        let element = document.child_node(SyntaxKind::Element).unwrap();
+        let attributes = element.child_node(SyntaxKind::AttributeList).unwrap();
+        let comments = attributes
+            .children
+            .iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Token(token) if token.kind() == Kind::Comment => {
+                    Some(token.token.clone())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            comments,
+            vec![
+                JtmlToken::Comment("first".to_string()),
+                JtmlToken::Comment("second".to_string())
+            ]
+        );
+        assert_eq!(attributes.child_nodes().count(), 1);
+        assert!(element.child_node(SyntaxKind::Body).is_none());
+    }
+
+    #[test]
+    fn comments_between_tokens() {
+        // 名前と'('、'='の前後、')'と'{'の間のコメントは要素と属性の中に入る
+        let source = "p // a\n(b // b\n= // c\n\"1\") // d\n{\"x\"} // e";
+        let document = cst(source);
+        let comments = |node: &SyntaxNode| {
+            node.children
+                .iter()
+                .filter_map(|child| match child {
+                    SyntaxElement::Token(token) if token.kind() == Kind::Comment => {
+                        Some(&source[token.span.range()])
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let element = document.child_node(SyntaxKind::Element).unwrap();
+        assert_eq!(comments(element), vec!["// a", "// d"]);
+        let attribute = element
+            .child_node(SyntaxKind::AttributeList)
+            .unwrap()
+            .child_node(SyntaxKind::Attribute)
+            .unwrap();
+        assert_eq!(attribute.text(source), "b // b\n= // c\n\"1\"");
+        assert_eq!(comments(attribute), vec!["// b", "// c"]);
+        assert_eq!(
+            element.child_node(SyntaxKind::Body).unwrap().text(source),
+            "{\"x\"}"
+        );
+        // 後に'{'が続かないコメントは要素に含めない
+        assert_eq!(comments(&document), vec!["// e"]);
+    }
+
+    #[test]
+    fn structure() {
+        let source = "p (a=\"b\") {\"c\" br()}";
+        let document = cst(source);
+        let element = document.child_node(SyntaxKind::Element).unwrap();
+        assert_eq!(element.text(source), source);
+        let body = element.child_node(SyntaxKind::Body).unwrap();
+        assert_eq!(body.text(source), "{\"c\" br()}");
+        assert_eq!(
+            body.child_node(SyntaxKind::Element).unwrap().text(source),
+            "br()"
+        );
+        let attribute = element
+            .child_node(SyntaxKind::AttributeList)
+            .unwrap()
+            .child_node(SyntaxKind::Attribute)
+            .unwrap();
+        assert_eq!(attribute.text(source), "a=\"b\"");
+    }
+}
@@ -1,52 +1,200 @@
-use crate::jtml_lexer::{self, JtmlToken};
-use std::{collections::VecDeque, error::Error, fmt::Display};
+use crate::{
+    diagnostics::Diagnostic,
+    jtml_lexer::{self, Kind},
+    span::Span,
+};
+use std::{error::Error, fmt::Display};
 
-#[derive(Debug, Clone)]
+// 期待したトークンの種類, 実際のトークンの種類, 実際のトークンの位置
+#[derive(Debug, Clone, PartialEq)]
 pub enum ParserError {
-    UnexpectedToken(jtml_lexer::Kind, JtmlToken, Option<VecDeque<JtmlToken>>),
+    UnexpectedToken(jtml_lexer::Kind, jtml_lexer::Kind, Span),
     TokenIsNotEnough(Vec<jtml_lexer::Kind>),
     EmptyTokens,
+    // 空要素の名前, 子の範囲
+    VoidElementWithChildren(String, Span),
+    // 制御構文の名前, 足りない属性, 制御構文(値のない属性の場合はその属性)の位置
+    MissingAttribute(String, &'static str, Span),
+    // 制御構文の名前, 知らない属性, その属性の位置
+    UnknownAttribute(String, String, Span),
+    // ifの直後にないelse
+    ElseWithoutIf(Span),
 }
 
 impl Display for ParserError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            ParserError::UnexpectedToken(expect, actual, left_token) => {
-                write!(
-                    f,
-                    "Unexpected token: expect {:?}, actual {} state {:?}",
-                    expect, actual, left_token
-                )
+            ParserError::UnexpectedToken(expect, actual, _) => {
+                write!(f, "unexpected token: expected {}, found {}", expect, actual)
             }
             ParserError::TokenIsNotEnough(expect) => {
-                write!(f, "Token is not enough: expect {:?}", expect)
+                write!(f, "unexpected end of file: expected {}", join_kinds(expect))
             }
-            ParserError::EmptyTokens => write!(f, "Token is empty"),
+            ParserError::EmptyTokens => write!(f, "token is empty"),
+            ParserError::VoidElementWithChildren(tag_name, _) => {
+                write!(f, "void element `{}` cannot have children", tag_name)
+            }
+            ParserError::MissingAttribute(tag_name, name, _) => {
+                write!(f, "`{}` requires `{}`", tag_name, name)
+            }
+            ParserError::UnknownAttribute(tag_name, name, _) => {
+                write!(f, "`{}` does not take `{}`", tag_name, name)
+            }
+            ParserError::ElseWithoutIf(_) => write!(f, "`else` without `if`"),
         }
     }
 }
 
-impl PartialEq for ParserError {
-    fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (
-                ParserError::UnexpectedToken(expect1, actual1, left_token1),
-                ParserError::UnexpectedToken(expect2, actual2, left_token2),
-            ) => {
-                if left_token1.is_some() && left_token2.is_some() {
-                    let left_token1 = left_token1.as_ref().unwrap();
-                    let left_token2 = left_token2.as_ref().unwrap();
-                    return expect1 == expect2 && actual1 == actual2 && left_token1 == left_token2;
+impl ParserError {
+    // トークンが足りない場合は位置を持たない
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ParserError::UnexpectedToken(_, _, span) => Some(*span),
+            ParserError::VoidElementWithChildren(_, span) => Some(*span),
+            ParserError::MissingAttribute(_, _, span) => Some(*span),
+            ParserError::UnknownAttribute(_, _, span) => Some(*span),
+            ParserError::ElseWithoutIf(span) => Some(*span),
+            _ => None,
+        }
+    }
+
+    // トークンが足りない場合はソースの末尾を指す
+    pub fn to_diagnostic(&self, source: &str) -> Diagnostic {
+        let end_of_file = Span::new(source.len(), source.len());
+        match self {
+            ParserError::UnexpectedToken(expect, actual, span) => {
+                let found = match actual {
+                    Kind::Identifier | Kind::StringLiteral | Kind::Comment => {
+                        format!("{} `{}`", actual, &source[span.range()])
+                    }
+                    _ => actual.to_string(),
+                };
+                let diagnostic = Diagnostic::error("unexpected token", *span)
+                    .with_label(format!("expected {}, found {}", expect, found));
+                match hint(expect) {
+                    Some(hint) => diagnostic.with_hint(hint),
+                    None => diagnostic,
                 }
-                expect1 == expect2 && actual1 == actual2
             }
-            (ParserError::TokenIsNotEnough(expect1), ParserError::TokenIsNotEnough(expect2)) => {
-                expect1 == expect2
+            ParserError::TokenIsNotEnough(expect) => {
+                let diagnostic = Diagnostic::error("unexpected end of file", end_of_file)
+                    .with_label(format!("expected {}", join_kinds(expect)));
+                match expect.first().and_then(hint) {
+                    Some(hint) => diagnostic.with_hint(hint),
+                    None => diagnostic,
+                }
+            }
+            ParserError::EmptyTokens => Diagnostic::error("token is empty", end_of_file),
+            ParserError::VoidElementWithChildren(tag_name, span) => {
+                Diagnostic::error("void element with children", *span)
+                    .with_label(format!("`{}` cannot have children", tag_name))
+                    .with_hint("void elements such as `img` and `br` cannot contain anything; write the children after the element")
+            }
+            ParserError::MissingAttribute(tag_name, name, span) => {
+                let diagnostic = Diagnostic::error("missing attribute", *span)
+                    .with_label(format!("`{}` needs a value for `{}`", tag_name, name));
+                match control_flow_hint(tag_name) {
+                    Some(hint) => diagnostic.with_hint(hint),
+                    None => diagnostic,
+                }
             }
-            (ParserError::EmptyTokens, ParserError::EmptyTokens) => true,
-            _ => false,
+            ParserError::UnknownAttribute(tag_name, name, span) => {
+                let diagnostic = Diagnostic::error("unknown attribute", *span)
+                    .with_label(format!("`{}` does not take `{}`", tag_name, name));
+                match control_flow_hint(tag_name) {
+                    Some(hint) => diagnostic.with_hint(hint),
+                    None => diagnostic,
+                }
+            }
+            ParserError::ElseWithoutIf(span) => Diagnostic::error("`else` without `if`", *span)
+                .with_label("`else` must directly follow the `}` of an `if`")
+                .with_hint("write `if(cond=\"name\"){...} else(){...}`"),
+        }
+    }
+}
+
+fn join_kinds(kinds: &[Kind]) -> String {
+    let kinds = kinds
+        .iter()
+        .map(|kind| kind.to_string())
+        .collect::<Vec<String>>();
+    match kinds.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => "nothing".to_string(),
+    }
+}
+
+fn hint(expect: &Kind) -> Option<&'static str> {
+    match expect {
+        Kind::LeftParen => Some("elements are written as `name(attributes){children}`"),
+        Kind::RightParen => {
+            Some("attributes are written as `name=\"value\"` and the list is closed with `)`")
+        }
+        Kind::LeftBracket => Some("only void elements such as `img()` can omit the `{}` body"),
+        Kind::RightBracket => Some("close the element body with `}`"),
+        Kind::Identifier => Some("expected an element, a string literal or a comment here"),
+        _ => None,
+    }
+}
+
+fn control_flow_hint(tag_name: &str) -> Option<&'static str> {
+    match tag_name {
+        "if" => Some("write `if(cond=\"name\"){...}`; `cond` is a variable name, optionally negated with `!`"),
+        "else" => Some("`else` takes no attributes"),
+        "for" => Some("write `for(item=\"x\" in=\"items\"){...}`"),
+        "extends" => Some("write `extends(layout=\"base.jtml\")`; the path is relative to the page"),
+        "block" => Some("write `block(name=\"content\"){...}`"),
+        "include" | "import" => {
+            Some("write `include(src=\"path.jtml\")`; the path is relative to the including file")
         }
+        _ => None,
     }
 }
 
 impl Error for ParserError {}
+
+#[cfg(test)]
+mod test {
+    use crate::{jtml_lexer::test_utils::lexer, jtml_parser::parse};
+
+    #[test]
+    fn found_token_text() {
+        let source = "div(a \"b\"){}";
+        let error = parse(&lexer(source)).unwrap_err();
+        assert_eq!(
+            error.to_diagnostic(source).label,
+            Some("expected '=', found string literal `\"b\"`".to_string())
+        );
+    }
+
+    #[test]
+    fn unexpected_token_diagnostic() {
+        let source = "html(){\n    p){}\n}";
+        let error = parse(&lexer(source)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unexpected token: expected '(', found ')'"
+        );
+        assert_eq!(
+            error.to_diagnostic(source).render("index.jtml", source),
+            r#"error: unexpected token
+ --> index.jtml:2:6
+  |
+2 |     p){}
+  |      ^ expected '(', found ')'
+  |
+  = hint: elements are written as `name(attributes){children}`
+"#
+        );
+    }
+
+    #[test]
+    fn end_of_file_diagnostic() {
+        let source = "p(){";
+        let error = parse(&lexer(source)).unwrap_err();
+        let diagnostic = error.to_diagnostic(source);
+        assert_eq!((diagnostic.span.start, diagnostic.span.end), (4, 4));
+        assert_eq!(diagnostic.label, Some("expected '}'".to_string()));
+    }
+}
@@ -1,28 +1,32 @@
-use std::collections::VecDeque;
+use crate::{html_spec, jtml_lexer::Token};
 
-use crate::jtml_lexer::JtmlToken;
-
-use self::ast::root::AstRoot;
+use self::{ast::root::AstRoot, cursor::Cursor};
 
 use super::ParserError;
 
-mod ast;
+pub(crate) mod ast;
 mod attributes;
+mod cursor;
 mod document;
 mod node;
 mod nodes;
-mod one_token;
 
-pub fn parse(tokens: &mut VecDeque<JtmlToken>) -> Result<AstRoot, ParserError> {
-    document::parse(tokens)
+// 最初のエラーを返す
+pub fn parse(tokens: &[Token]) -> Result<AstRoot, ParserError> {
+    let (ast, errors) = parse_recovering(tokens);
+    match errors.into_iter().next() {
+        Some(error) => Err(error),
+        None => Ok(ast),
+    }
 }
 
-pub fn is_self_terminating_tag(tag_name: &String) -> bool {
-    let empty_elements = vec![
-        "br", "hr", "img", "input", "meta", "area", "base", "col", "embed", "keygen", "link",
-        "param", "source",
-    ];
-    empty_elements.contains(&tag_name.as_str())
+// エラーがあっても読めた部分のASTを返す(エディタやlinterで使う)
+pub fn parse_recovering(tokens: &[Token]) -> (AstRoot, Vec<ParserError>) {
+    document::parse(&mut Cursor::new(tokens))
+}
+
+pub fn is_self_terminating_tag(tag_name: &str) -> bool {
+    html_spec::element(tag_name).is_some_and(|element| element.void)
 }
 
 #[cfg(test)]
@@ -30,32 +34,36 @@ mod test {
 
     use test::ast::{node::Element, Node};
 
-    use crate::jtml_lexer::test_utils::lexer;
+    use crate::{
+        jtml_lexer::{test_utils::lexer, Kind},
+        span::Span,
+    };
 
     use super::*;
     use std::collections::VecDeque;
 
     #[test]
     fn node_with_contents() {
-        let mut tokens = lexer(r#"p(){"hello""world"}"#);
-        let result = node::parse(&mut tokens);
+        let tokens = lexer(r#"p(){"hello""world"}"#);
+        let result = node::parse(&mut Cursor::new(&tokens), &mut Vec::new());
         assert_eq!(
             result.unwrap(),
             Node::Element(Element {
                 tag_name: "p".to_string(),
                 attributes: VecDeque::from(vec![]),
                 children: VecDeque::from(vec![
-                    Node::Text("hello".to_string()),
-                    Node::Text("world".to_string())
-                ])
+                    Node::Text("hello".to_string(), Span::new(4, 11)),
+                    Node::Text("world".to_string(), Span::new(11, 18))
+                ]),
+                span: Span::new(0, 19),
             })
         );
     }
 
     #[test]
     fn node_with_child_elements() {
-        let mut tokens = lexer(r#"p(){p(){"hello"}}"#);
-        let result = node::parse(&mut tokens);
+        let tokens = lexer(r#"p(){p(){"hello"}}"#);
+        let result = node::parse(&mut Cursor::new(&tokens), &mut Vec::new());
         assert_eq!(
             result.unwrap(),
             Node::Element(Element {
@@ -64,16 +72,21 @@ mod test {
                 children: VecDeque::from(vec![Node::Element(Element {
                     tag_name: "p".to_string(),
                     attributes: VecDeque::from(vec![]),
-                    children: VecDeque::from(vec![Node::Text("hello".to_string())])
-                })])
+                    children: VecDeque::from(vec![Node::Text(
+                        "hello".to_string(),
+                        Span::new(8, 15)
+                    )]),
+                    span: Span::new(4, 16),
+                })]),
+                span: Span::new(0, 17),
             })
         );
     }
 
     #[test]
     fn document() {
-        let mut tokens = lexer(r#"h1(){}p(){}"#);
-        let result = document::parse(&mut tokens);
+        let tokens = lexer(r#"h1(){}p(){}"#);
+        let result = parse(&tokens);
 
         assert_eq!(
             result.unwrap(),
@@ -82,12 +95,14 @@ mod test {
                     Node::Element(Element {
                         tag_name: "h1".to_string(),
                         attributes: VecDeque::from(vec![]),
-                        children: VecDeque::from(vec![])
+                        children: VecDeque::from(vec![]),
+                        span: Span::new(0, 6),
                     }),
                     Node::Element(Element {
                         tag_name: "p".to_string(),
                         attributes: VecDeque::from(vec![]),
-                        children: VecDeque::from(vec![])
+                        children: VecDeque::from(vec![]),
+                        span: Span::new(6, 11),
                     })
                 ])
             }
@@ -96,8 +111,8 @@ mod test {
 
     #[test]
     fn comment() {
-        let mut tokens = lexer(r#"h1(){}p(){}"#);
-        let result = document::parse(&mut tokens);
+        let tokens = lexer(r#"h1(){}p(){}"#);
+        let result = parse(&tokens);
 
         assert_eq!(
             result.unwrap(),
@@ -106,15 +121,128 @@ mod test {
                     Node::Element(Element {
                         tag_name: "h1".to_string(),
                         attributes: VecDeque::from(vec![]),
-                        children: VecDeque::from(vec![])
+                        children: VecDeque::from(vec![]),
+                        span: Span::new(0, 6),
                     }),
                     Node::Element(Element {
                         tag_name: "p".to_string(),
                         attributes: VecDeque::from(vec![]),
-                        children: VecDeque::from(vec![])
+                        children: VecDeque::from(vec![]),
+                        span: Span::new(6, 11),
                     })
                 ])
             }
         );
     }
+
+    #[test]
+    fn node_span() {
+        let source = "p(a=\"b\"){\n    \"text\"\n    // comment\n}\nimg()";
+        let tokens = lexer(source);
+        let result = parse(&tokens).unwrap();
+        let slice = |span: Span| &source[span.range()];
+
+        let Node::Element(p) = &result.elements[0] else {
+            panic!("element expected");
+        };
+        assert_eq!(
+            slice(p.span),
+            "p(a=\"b\"){\n    \"text\"\n    // comment\n}"
+        );
+        assert_eq!(slice(p.attributes[0].span), "a=\"b\"");
+        assert_eq!(slice(p.children[0].span()), "\"text\"");
+        assert_eq!(slice(p.children[1].span()), "// comment");
+        assert_eq!(slice(result.elements[1].span()), "img()");
+    }
+
+    #[test]
+    fn recover_multiple_errors() {
+        let source = r#"
+html(){
+    head(){
+        title(){"document"
+    }
+    body(a "b"){
+        p){"hello"}
+        h1(){"world"}}
+    }
+}
+}"#;
+        let tokens = lexer(source);
+        let (ast, errors) = parse_recovering(&tokens);
+        let kinds = errors
+            .iter()
+            .map(|error| match error {
+                ParserError::UnexpectedToken(expect, _, span) => (expect.clone(), span.start),
+                _ => panic!("unexpected error {:?}", error),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (Kind::Equal, source.find("\"b\"").unwrap()),
+                (Kind::LeftParen, source.find("){\"hello").unwrap()),
+                (Kind::Identifier, source.rfind('}').unwrap()),
+            ]
+        );
+
+        // エラーの前後の構造は残っている
+        let Node::Element(html) = &ast.elements[0] else {
+            panic!("element expected");
+        };
+        assert_eq!(html.tag_name, "html");
+        let Node::Element(head) = &html.children[0] else {
+            panic!("element expected");
+        };
+        assert_eq!(head.tag_name, "head");
+        let Node::Element(body) = &head.children[1] else {
+            panic!("element expected");
+        };
+        assert_eq!(body.tag_name, "body");
+        assert_eq!(body.children.len(), 2);
+    }
+
+    #[test]
+    fn recover_stray_tokens() {
+        // ノードを開始できないトークンの並びは一つのエラーにまとめ、括弧の中の名前はノードとして読まない
+        let source = r#""a" ) (b="c") }} div(){}"#;
+        let tokens = lexer(source);
+        let (ast, errors) = parse_recovering(&tokens);
+        assert_eq!(
+            errors,
+            vec![ParserError::UnexpectedToken(
+                Kind::Identifier,
+                Kind::RightParen,
+                Span::new(4, 16)
+            )]
+        );
+        assert_eq!(&source[4..16], r#") (b="c") }}"#);
+        assert_eq!(ast.elements.len(), 2);
+        let Node::Element(div) = &ast.elements[1] else {
+            panic!("element expected");
+        };
+        assert_eq!(div.tag_name, "div");
+    }
+
+    #[test]
+    fn recover_unclosed_element() {
+        let tokens = lexer(r#"p(){"a" div(){"b"}"#);
+        let (ast, errors) = parse_recovering(&tokens);
+        assert_eq!(
+            errors,
+            vec![ParserError::TokenIsNotEnough(vec![Kind::RightBracket])]
+        );
+        let Node::Element(p) = &ast.elements[0] else {
+            panic!("element expected");
+        };
+        assert_eq!(p.children.len(), 2);
+    }
+
+    #[test]
+    fn recover_missing_body() {
+        let tokens = lexer(r#"p() "text" img()"#);
+        let (ast, errors) = parse_recovering(&tokens);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(ast.elements.len(), 3);
+    }
 }
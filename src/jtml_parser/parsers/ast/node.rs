@@ -1,40 +1,148 @@
+mod component;
+mod control_flow;
 mod element;
 
-pub use element::Element;
-
-use crate::{formatter::FormatConfig, html_converter::Convert};
+pub use component::{Component, DEFINE_TAG, SLOT_TAG};
+pub use control_flow::{For, If, ELSE_TAG, FOR_TAG, IF_TAG};
+pub use element::{Attribute, Attributes, Children, Element};
+
+use crate::{
+    formatter::{
+        doc::{self, Doc},
+        FormatConfig,
+    },
+    html_converter::{collapse_whitespace, escape_text, Convert, HtmlOptions},
+    jtml_lexer::escape_string_literal,
+    span::Span,
+};
+
+// jtml上で生のHTMLを書くための要素名
+pub const RAW_HTML_TAG: &str = "raw";
+// 別のファイルを読み込む要素名。transformerで読み込んだ内容に置き換える
+pub const INCLUDE_TAG: &str = "include";
+// 別のファイルの部品の定義だけを読み込む
+pub const IMPORT_TAG: &str = "import";
+// ページが使うレイアウトと、レイアウトの中でページが置き換える部分
+pub const EXTENDS_TAG: &str = "extends";
+pub const BLOCK_TAG: &str = "block";
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Node {
     Element(Element),
-    Text(String),
-    Comment(String),
+    Text(String, Span),
+    Comment(String, Span),
+    // エスケープせずにそのまま出力される、信頼できるHTML
+    Raw(String, Span),
+    If(If),
+    For(For),
+    Define(Component),
+}
+
+impl Node {
+    pub fn span(&self) -> Span {
+        match self {
+            Node::Element(element) => element.span,
+            Node::Text(_, span) => *span,
+            Node::Comment(_, span) => *span,
+            Node::Raw(_, span) => *span,
+            Node::If(node) => node.span,
+            Node::For(node) => node.span,
+            Node::Define(component) => component.span,
+        }
+    }
+
+    // 中の全ての位置をfで置き換えたノード
+    pub(crate) fn map_spans(&self, f: &impl Fn(Span) -> Span) -> Node {
+        let attributes = |attributes: &Attributes| {
+            attributes
+                .iter()
+                .map(|attribute| Attribute {
+                    span: f(attribute.span),
+                    ..attribute.clone()
+                })
+                .collect()
+        };
+        let children =
+            |children: &Children| children.iter().map(|child| child.map_spans(f)).collect();
+        match self {
+            Node::Element(element) => Node::Element(Element {
+                tag_name: element.tag_name.clone(),
+                attributes: attributes(&element.attributes),
+                children: children(&element.children),
+                span: f(element.span),
+            }),
+            Node::Text(text, span) => Node::Text(text.clone(), f(*span)),
+            Node::Comment(text, span) => Node::Comment(text.clone(), f(*span)),
+            Node::Raw(html, span) => Node::Raw(html.clone(), f(*span)),
+            Node::If(node) => Node::If(If {
+                condition: node.condition.clone(),
+                condition_span: f(node.condition_span),
+                then: children(&node.then),
+                between: children(&node.between),
+                otherwise: node.otherwise.as_ref().map(children),
+                span: f(node.span),
+            }),
+            Node::For(node) => Node::For(For {
+                item: node.item.clone(),
+                item_span: f(node.item_span),
+                items: node.items.clone(),
+                items_span: f(node.items_span),
+                children: children(&node.children),
+                span: f(node.span),
+            }),
+            Node::Define(component) => Node::Define(Component {
+                name: component.name.clone(),
+                name_span: f(component.name_span),
+                parameters: attributes(&component.parameters),
+                children: children(&component.children),
+                span: f(component.span),
+            }),
+        }
+    }
 }
 
 impl Convert for Node {
-    fn to_html(&self, ignore_comment: bool) -> String {
+    fn to_html(&self, options: &HtmlOptions) -> String {
         match self {
-            Node::Element(element) => element.to_html(ignore_comment),
-            Node::Text(text) => text.to_string(),
-            Node::Comment(text) => {
-                if ignore_comment {
+            Node::Element(element) => element.to_html(options),
+            Node::Text(text, _) => match options.minify {
+                Some(_) => escape_text(&collapse_whitespace(text)),
+                None => escape_text(text),
+            },
+            Node::Raw(html, _) => html.to_string(),
+            Node::Comment(text, _) => {
+                if options.ignore_comment || options.minify.is_some() {
                     return "".to_string();
                 }
                 format!("<!--{}-->", text)
             }
+            Node::If(node) => node.to_html(options),
+            Node::For(node) => node.to_html(options),
+            Node::Define(component) => component.to_html(options),
         }
     }
 
-    fn to_jtml(&self, ignore_comment: bool, indent_depth: usize, config: &FormatConfig) -> String {
+    fn to_doc(&self, ignore_comment: bool, config: &FormatConfig) -> Doc {
         match self {
-            Node::Element(element) => element.to_jtml(ignore_comment, indent_depth, config),
-            Node::Text(text) => format!("{}\"{}\"", config.get_indent_text(indent_depth), text),
-            Node::Comment(text) => {
+            Node::Element(element) => element.to_doc(ignore_comment, config),
+            Node::Text(text, _) => Doc::text(format!("\"{}\"", escape_string_literal(text))),
+            Node::Comment(text, _) => {
                 if ignore_comment {
-                    return "".to_string();
+                    return Doc::Nil;
                 }
-                format!("{}// {}", config.get_indent_text(indent_depth), text)
+                // 行末までがコメントになるため、囲んでいる要素は必ず改行させる
+                Doc::concat(vec![Doc::text(format!("// {}", text)), Doc::BreakParent])
             }
+            Node::Raw(html, _) => Doc::concat(vec![
+                Doc::text(format!("{}()", RAW_HTML_TAG)),
+                doc::body(vec![Doc::text(format!(
+                    "\"{}\"",
+                    escape_string_literal(html)
+                ))]),
+            ]),
+            Node::If(node) => node.to_doc(ignore_comment, config),
+            Node::For(node) => node.to_doc(ignore_comment, config),
+            Node::Define(component) => component.to_doc(ignore_comment, config),
         }
     }
 }
@@ -45,17 +153,21 @@ mod test {
     // Elementはより下位のモジュールでテストしているため、ここでは使用しない
     //
 
-    use crate::{formatter::FormatConfig, html_converter::Convert};
+    use crate::{
+        formatter::FormatConfig,
+        html_converter::{Convert, HtmlOptions},
+        span::Span,
+    };
 
     use super::Node;
 
     #[test]
     fn html_comment() {
-        let comment = Node::Comment("".to_string());
-        assert_eq!(comment.to_html(false), "<!---->");
+        let comment = Node::Comment("".to_string(), Span::default());
+        assert_eq!(comment.to_html(&HtmlOptions::default()), "<!---->");
 
-        let comment = Node::Comment("comment".to_string());
-        assert_eq!(comment.to_html(false), "<!--comment-->");
+        let comment = Node::Comment("comment".to_string(), Span::default());
+        assert_eq!(comment.to_html(&HtmlOptions::default()), "<!--comment-->");
     }
 
     #[test]
@@ -63,21 +175,46 @@ mod test {
         let config = FormatConfig {
             indent: crate::formatter::Tab::Spaces(4),
             ignore_comment: false,
+            ..FormatConfig::default()
         };
-        let comment = Node::Comment("".to_string());
+        let comment = Node::Comment("".to_string(), Span::default());
         assert_eq!(comment.to_jtml(false, 0, &config), "// ");
 
-        let comment = Node::Comment("comment".to_string());
+        let comment = Node::Comment("comment".to_string(), Span::default());
         assert_eq!(comment.to_jtml(false, 0, &config), "// comment");
     }
 
     #[test]
     fn html_text() {
-        let comment = Node::Text("".to_string());
-        assert_eq!(comment.to_html(false), "");
+        let comment = Node::Text("".to_string(), Span::default());
+        assert_eq!(comment.to_html(&HtmlOptions::default()), "");
+
+        let comment = Node::Text("comment".to_string(), Span::default());
+        assert_eq!(comment.to_html(&HtmlOptions::default()), "comment");
 
-        let comment = Node::Text("comment".to_string());
-        assert_eq!(comment.to_html(false), "comment");
+        let text = Node::Text("a < b & c".to_string(), Span::default());
+        assert_eq!(text.to_html(&HtmlOptions::default()), "a &lt; b &amp; c");
+    }
+
+    #[test]
+    fn raw() {
+        let raw = Node::Raw("<b class=\"x\">a & b</b>".to_string(), Span::default());
+        assert_eq!(
+            raw.to_html(&HtmlOptions::default()),
+            "<b class=\"x\">a & b</b>"
+        );
+        assert_eq!(
+            raw.to_jtml(
+                false,
+                0,
+                &FormatConfig {
+                    indent: crate::formatter::Tab::Spaces(4),
+                    ignore_comment: false,
+                    ..FormatConfig::default()
+                }
+            ),
+            "raw(){\"<b class=\\\"x\\\">a & b</b>\"}"
+        );
     }
 
     #[test]
@@ -85,11 +222,12 @@ mod test {
         let config = FormatConfig {
             indent: crate::formatter::Tab::Spaces(4),
             ignore_comment: false,
+            ..FormatConfig::default()
         };
-        let comment = Node::Text("".to_string());
+        let comment = Node::Text("".to_string(), Span::default());
         assert_eq!(comment.to_jtml(false, 0, &config), "\"\"");
 
-        let comment = Node::Text("".to_string());
+        let comment = Node::Text("".to_string(), Span::default());
         assert_eq!(comment.to_jtml(false, 0, &config), "\"\"");
     }
 }
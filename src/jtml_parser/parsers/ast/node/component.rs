@@ -0,0 +1,37 @@
+use crate::{
+    formatter::{doc::Doc, FormatConfig},
+    html_converter::{Convert, HtmlOptions},
+    span::Span,
+};
+
+use super::{Attributes, Children};
+
+pub const DEFINE_TAG: &str = "define";
+// 部品の本体の中で、呼び出した側の子に置き換わる要素
+pub const SLOT_TAG: &str = "slot";
+
+// define card(title subtitle=""){...}
+// 値のない引数は必須、値のある引数はその値が既定値になる
+#[derive(Debug, Clone, PartialEq)]
+pub struct Component {
+    pub name: String,
+    pub name_span: Span,
+    pub parameters: Attributes,
+    pub children: Children,
+    pub span: Span,
+}
+
+// 定義そのものは何も出力せず、transformerで呼び出し側に展開する
+impl Convert for Component {
+    fn to_html(&self, _: &HtmlOptions) -> String {
+        String::new()
+    }
+
+    fn to_doc(&self, ignore_comment: bool, config: &FormatConfig) -> Doc {
+        Doc::concat(vec![
+            Doc::text(format!("{} {}", DEFINE_TAG, self.name)),
+            self.parameters.to_doc(ignore_comment, config),
+            self.children.to_doc(ignore_comment, config),
+        ])
+    }
+}
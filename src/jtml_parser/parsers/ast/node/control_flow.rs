@@ -0,0 +1,140 @@
+use crate::{
+    formatter::{doc::Doc, FormatConfig},
+    html_converter::{Convert, HtmlOptions},
+    span::Span,
+};
+
+use super::{Attribute, Attributes, Children};
+
+pub const IF_TAG: &str = "if";
+pub const ELSE_TAG: &str = "else";
+pub const FOR_TAG: &str = "for";
+
+// if(cond="name"){...} else(){...}
+// 直後のelseは構文解析でifにまとめる
+#[derive(Debug, Clone, PartialEq)]
+pub struct If {
+    pub condition: String,
+    // cond属性の位置
+    pub condition_span: Span,
+    pub then: Children,
+    // thenの`}`とelseの間に書いたコメント
+    pub between: Children,
+    pub otherwise: Option<Children>,
+    pub span: Span,
+}
+
+// for(item="x" in="items"){...}
+#[derive(Debug, Clone, PartialEq)]
+pub struct For {
+    pub item: String,
+    pub item_span: Span,
+    pub items: String,
+    pub items_span: Span,
+    pub children: Children,
+    pub span: Span,
+}
+
+fn head(name: &str, attributes: Attributes, ignore_comment: bool, config: &FormatConfig) -> Doc {
+    Doc::concat(vec![
+        Doc::text(name),
+        attributes.to_doc(ignore_comment, config),
+    ])
+}
+
+// 制御構文はtemplate::renderで展開するため、展開前のままHTMLに変換した場合は何も出力しない
+impl Convert for If {
+    fn to_html(&self, _: &HtmlOptions) -> String {
+        String::new()
+    }
+
+    fn to_doc(&self, ignore_comment: bool, config: &FormatConfig) -> Doc {
+        let attributes = Attributes::from(vec![Attribute::new("cond", &self.condition)]);
+        let mut docs = vec![
+            head(IF_TAG, attributes, ignore_comment, config),
+            self.then.to_doc(ignore_comment, config),
+        ];
+        if let Some(otherwise) = &self.otherwise {
+            for comment in &self.between {
+                if !ignore_comment {
+                    docs.extend([Doc::HardLine, comment.to_doc(ignore_comment, config)]);
+                }
+            }
+            docs.extend([
+                Doc::HardLine,
+                head(ELSE_TAG, Attributes::new(), ignore_comment, config),
+                otherwise.to_doc(ignore_comment, config),
+            ]);
+        }
+        Doc::concat(docs)
+    }
+}
+
+impl Convert for For {
+    fn to_html(&self, _: &HtmlOptions) -> String {
+        String::new()
+    }
+
+    fn to_doc(&self, ignore_comment: bool, config: &FormatConfig) -> Doc {
+        let attributes = Attributes::from(vec![
+            Attribute::new("item", &self.item),
+            Attribute::new("in", &self.items),
+        ]);
+        Doc::concat(vec![
+            head(FOR_TAG, attributes, ignore_comment, config),
+            self.children.to_doc(ignore_comment, config),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        formatter::FormatConfig,
+        html_converter::Convert,
+        jtml_parser::parsers::ast::{node::Children, Node},
+        span::Span,
+    };
+
+    use super::{For, If};
+
+    #[test]
+    fn to_jtml() {
+        let text = |text: &str| Node::Text(text.to_string(), Span::default());
+        let node = If {
+            condition: "!user.admin".to_string(),
+            condition_span: Span::default(),
+            then: Children::from(vec![text("a")]),
+            between: Children::new(),
+            otherwise: Some(Children::from(vec![text("b")])),
+            span: Span::default(),
+        };
+        assert_eq!(
+            node.to_jtml(false, 0, &FormatConfig::default()),
+            "if(cond=\"!user.admin\"){\"a\"}\nelse(){\"b\"}"
+        );
+
+        // ifとelseの間のコメントは一行ずつ書く
+        let node = If {
+            between: Children::from(vec![Node::Comment("note".to_string(), Span::default())]),
+            ..node
+        };
+        assert_eq!(
+            node.to_jtml(false, 0, &FormatConfig::default()),
+            "if(cond=\"!user.admin\"){\"a\"}\n// note\nelse(){\"b\"}"
+        );
+
+        let node = For {
+            item: "x".to_string(),
+            item_span: Span::default(),
+            items: "items".to_string(),
+            items_span: Span::default(),
+            children: Children::from(vec![text("{{ x }}")]),
+            span: Span::default(),
+        };
+        assert_eq!(
+            node.to_jtml(false, 0, &FormatConfig::default()),
+            "for(item=\"x\" in=\"items\"){\"{{ x }}\"}"
+        );
+    }
+}
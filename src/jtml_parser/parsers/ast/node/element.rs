@@ -1,63 +1,169 @@
 use crate::{
-    formatter::FormatConfig, html_converter::Convert, jtml_parser::parsers::is_self_terminating_tag,
+    formatter::{doc::Doc, FormatConfig, SelectorStyle},
+    html_converter::{
+        escape_raw_text, escape_text, has_block, is_inline, is_raw_text_element,
+        is_whitespace_sensitive, lines, minify_raw_text, next_output, omits_end_tag, Convert,
+        HtmlOptions, Minify,
+    },
+    jtml_lexer::Selector,
+    jtml_parser::parsers::is_self_terminating_tag,
+    span::Span,
 };
 
+use super::Node;
+
 mod attributes;
 mod children;
 
-use self::{attributes::Attributes, children::Children};
+pub use self::{
+    attributes::{Attribute, Attributes},
+    children::Children,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Element {
     pub tag_name: String,
     pub attributes: Attributes,
     pub children: Children,
+    pub span: Span,
 }
 
-impl Convert for Element {
-    fn to_html(&self, ignore_comment: bool) -> String {
-        let attributes = match self.attributes.to_html(ignore_comment).as_str() {
+// 親から受け取る、要素を出力する位置
+#[derive(Debug, Clone, Copy, Default)]
+struct Place {
+    // svg, mathの中(foreignObjectの中を除く)にあるかどうか
+    in_foreign: bool,
+    // pre, textareaの中にあるかどうか。縮める場合も空白をまとめない
+    preformatted: bool,
+    // 整形して出力する場合の字下げの深さ
+    depth: usize,
+    // 整形して出力する場合も一行のまま書くかどうか(行内の要素かpre, textareaの中)
+    one_line: bool,
+    // 縮める場合に終了タグを省くかどうか
+    omit_end_tag: bool,
+    // 後に何も出力しないかどうか。終了タグを省いても文書の終わりで閉じられる
+    at_end: bool,
+}
+
+impl Element {
+    // 文書の直下の要素。縮める場合は後のノードnextによって終了タグを省く
+    pub(crate) fn top_level_html(&self, options: &HtmlOptions, next: Option<&Node>) -> String {
+        let place = Place {
+            omit_end_tag: omits_end_tag(options, &self.tag_name, next, "", true),
+            at_end: next.is_none(),
+            ..Place::default()
+        };
+        self.html(options, place)
+    }
+
+    fn html(&self, options: &HtmlOptions, place: Place) -> String {
+        let attributes = match self.attributes.to_html(options).as_str() {
             "" => "".to_string(),
             s => format!(" {}", s),
         };
+        let foreign = place.in_foreign || matches!(self.tag_name.as_str(), "svg" | "math");
+        // 外部要素はHTMLの空要素と同じ名前でも子を持てる
+        let void = !foreign && is_self_terminating_tag(&self.tag_name);
+        if void || self.children.is_empty() {
+            if let Some(end) = options.mode.empty_tag_end(void, foreign) {
+                return format!("<{}{}{}", self.tag_name, attributes, end);
+            }
+        }
 
-        if is_self_terminating_tag(&self.tag_name) {
-            format!("<{}{}/>", self.tag_name, attributes)
-        } else {
-            format!(
+        let in_foreign = foreign && self.tag_name != "foreignObject";
+        let preformatted = place.preformatted || is_whitespace_sensitive(&self.tag_name);
+        let one_line = place.one_line || preformatted || is_inline(&self.tag_name);
+        // 最後の子の終了タグは、この要素の終了タグか文書の終わりが続く場合だけ省ける
+        let closed = !place.omit_end_tag || place.at_end;
+        let children = self.children.iter().collect::<Vec<_>>();
+        let child_html = |index: usize, depth: usize| match children[index] {
+            // script, styleの中のテキストは文字参照が使えないためそのまま出力する
+            Node::Text(text, _) if is_raw_text_element(&self.tag_name) => match options.minify {
+                Some(Minify { inline_code: true }) => {
+                    escape_raw_text(&self.tag_name, &minify_raw_text(self, text))
+                }
+                _ => escape_raw_text(&self.tag_name, text),
+            },
+            Node::Text(text, _) if preformatted => escape_text(text),
+            Node::Element(element) => {
+                let next = next_output(&children, index);
+                let omit_end_tag = !in_foreign
+                    && omits_end_tag(options, &element.tag_name, next, &self.tag_name, closed);
+                element.html(
+                    options,
+                    Place {
+                        in_foreign,
+                        preformatted,
+                        depth,
+                        one_line,
+                        omit_end_tag,
+                        at_end: place.at_end && place.omit_end_tag && next.is_none(),
+                    },
+                )
+            }
+            child => child.to_html(options),
+        };
+        let inner = match options.indent() {
+            // 行内の要素と、空白がそのまま表示される要素の中は子孫も含めて一行のまま書く
+            Some(indent) if !one_line && has_block(&children) => {
+                format!(
+                    "\n{}\n{}",
+                    lines(&children, indent, place.depth + 1, |index, _| child_html(
+                        index,
+                        place.depth + 1
+                    )),
+                    indent.text(place.depth)
+                )
+            }
+            _ => (0..children.len())
+                .map(|index| child_html(index, place.depth))
+                .collect::<String>(),
+        };
+        match place.omit_end_tag {
+            true => format!("<{}{}>{}", self.tag_name, attributes, inner),
+            false => format!(
                 "<{}{}>{}</{}>",
-                self.tag_name,
-                attributes,
-                self.children
-                    .iter()
-                    .map(|child| (child.to_html(ignore_comment).clone()))
-                    .collect::<Vec<String>>()
-                    .concat(),
-                self.tag_name,
-            )
+                self.tag_name, attributes, inner, self.tag_name
+            ),
         }
     }
+}
 
-    fn to_jtml(&self, ignore_comment: bool, indent_depth: usize, config: &FormatConfig) -> String {
+impl Convert for Element {
+    fn to_html(&self, options: &HtmlOptions) -> String {
+        self.html(options, Place::default())
+    }
+
+    fn to_doc(&self, ignore_comment: bool, config: &FormatConfig) -> Doc {
+        let head = match config.selector_style {
+            // class, id属性を`div.card#main`の形にまとめる
+            SelectorStyle::Shorthand => {
+                let mut selector = Selector::new(&self.tag_name);
+                let mut attributes = Attributes::new();
+                for attribute in &self.attributes {
+                    let absorbed = attribute
+                        .value
+                        .as_deref()
+                        .is_some_and(|value| selector.absorb(&attribute.name, value));
+                    if !absorbed {
+                        attributes.push_back(attribute.clone());
+                    }
+                }
+                Doc::concat(vec![
+                    Doc::text(selector.to_string()),
+                    attributes.to_doc(ignore_comment, config),
+                ])
+            }
+            _ => Doc::concat(vec![
+                Doc::text(&self.tag_name),
+                self.attributes.to_doc(ignore_comment, config),
+            ]),
+        };
         // 子要素を持たない要素の場合
         if is_self_terminating_tag(&self.tag_name) {
-            format!(
-                "{}{}({})",
-                config.get_indent_text(indent_depth),
-                self.tag_name,
-                self.attributes
-                    .to_jtml(ignore_comment, indent_depth, config)
-            )
+            head
         } else {
-            format!(
-                "{}{}({}){{{}\n{}}}",
-                config.get_indent_text(indent_depth),
-                self.tag_name,
-                self.attributes
-                    .to_jtml(ignore_comment, indent_depth, config),
-                self.children.to_jtml(ignore_comment, indent_depth, config),
-                config.get_indent_text(indent_depth),
-            )
+            Doc::concat(vec![head, self.children.to_doc(ignore_comment, config)])
         }
     }
 }
@@ -67,12 +173,13 @@ impl Convert for Element {
 mod test {
 
     use crate::{
-        formatter::FormatConfig,
-        html_converter::Convert,
+        formatter::{AttributeLayout, FormatConfig},
+        html_converter::{Convert, HtmlOptions},
         jtml_parser::parsers::ast::{node::Element, Node},
+        span::Span,
     };
 
-    use super::{Attributes, Children};
+    use super::{Attribute, Attributes, Children};
 
     #[test]
     fn element() {
@@ -80,36 +187,40 @@ mod test {
             tag_name: "p".to_string(),
             attributes: Attributes::new(),
             children: Children::new(),
+            span: Span::default(),
         };
-        assert_eq!(element.to_html(false), "<p></p>");
+        assert_eq!(element.to_html(&HtmlOptions::default()), "<p></p>");
         assert_eq!(
             element.to_jtml(
                 false,
                 0,
                 &FormatConfig {
                     indent: crate::formatter::Tab::Spaces(4),
-                    ignore_comment: false
+                    ignore_comment: false,
+                    ..FormatConfig::default()
                 }
             ),
-            "p(){\n}"
+            "p(){}"
         );
 
         let element = Element {
             tag_name: "p".to_string(),
             attributes: Attributes::new(),
-            children: Children::from(vec![Node::Text("test".to_string())]),
+            children: Children::from(vec![Node::Text("test".to_string(), Span::default())]),
+            span: Span::default(),
         };
-        assert_eq!(element.to_html(false), "<p>test</p>");
+        assert_eq!(element.to_html(&HtmlOptions::default()), "<p>test</p>");
         assert_eq!(
             element.to_jtml(
                 false,
                 0,
                 &FormatConfig {
                     indent: crate::formatter::Tab::Spaces(4),
-                    ignore_comment: false
+                    ignore_comment: false,
+                    ..FormatConfig::default()
                 }
             ),
-            "p(){\n    \"test\"\n}"
+            "p(){\"test\"}"
         );
         assert_eq!(
             element.to_jtml(
@@ -117,10 +228,11 @@ mod test {
                 0,
                 &FormatConfig {
                     indent: crate::formatter::Tab::Spaces(2),
-                    ignore_comment: false
+                    ignore_comment: false,
+                    ..FormatConfig::default()
                 }
             ),
-            "p(){\n  \"test\"\n}"
+            "p(){\"test\"}"
         );
         assert_eq!(
             element.to_jtml(
@@ -128,10 +240,11 @@ mod test {
                 0,
                 &FormatConfig {
                     indent: crate::formatter::Tab::Tabs,
-                    ignore_comment: false
+                    ignore_comment: false,
+                    ..FormatConfig::default()
                 }
             ),
-            "p(){\n\t\"test\"\n}"
+            "p(){\"test\"}"
         )
     }
 
@@ -139,38 +252,45 @@ mod test {
     fn element_with_attribute() {
         let element = Element {
             tag_name: "p".to_string(),
-            attributes: Attributes::from(vec![("class".to_string(), "btn".to_string())]),
+            attributes: Attributes::from(vec![Attribute::new("class", "btn")]),
             children: Children::new(),
+            span: Span::default(),
         };
-        assert_eq!(element.to_html(false), "<p class=\"btn\"></p>");
+        assert_eq!(
+            element.to_html(&HtmlOptions::default()),
+            "<p class=\"btn\"></p>"
+        );
         assert_eq!(
             element.to_jtml(
                 false,
                 0,
                 &FormatConfig {
                     indent: crate::formatter::Tab::Spaces(4),
-                    ignore_comment: false
+                    ignore_comment: false,
+                    ..FormatConfig::default()
                 }
             ),
-            "p(class=\"btn\"){\n}"
+            "p(class=\"btn\"){}"
         );
 
         let element = Element {
             tag_name: "img".to_string(),
-            attributes: Attributes::from(vec![(
-                "href".to_string(),
-                "./images/img.png".to_string(),
-            )]),
+            attributes: Attributes::from(vec![Attribute::new("href", "./images/img.png")]),
             children: Children::new(),
+            span: Span::default(),
         };
-        assert_eq!(element.to_html(false), "<img href=\"./images/img.png\"/>");
+        assert_eq!(
+            element.to_html(&HtmlOptions::default()),
+            "<img href=\"./images/img.png\">"
+        );
         assert_eq!(
             element.to_jtml(
                 false,
                 0,
                 &FormatConfig {
                     indent: crate::formatter::Tab::Spaces(4),
-                    ignore_comment: false
+                    ignore_comment: false,
+                    ..FormatConfig::default()
                 }
             ),
             "img(href=\"./images/img.png\")"
@@ -182,21 +302,21 @@ mod test {
         let element = Element {
             tag_name: "p".to_string(),
             attributes: Attributes::new(),
-            children: Children::from(vec![Node::Text("test".to_string())]),
+            children: Children::from(vec![Node::Text("test".to_string(), Span::default())]),
+            span: Span::default(),
         };
-        assert_eq!(element.to_html(false), "<p>test</p>");
+        assert_eq!(element.to_html(&HtmlOptions::default()), "<p>test</p>");
         assert_eq!(
             element.to_jtml(
                 false,
                 0,
                 &FormatConfig {
                     indent: crate::formatter::Tab::Spaces(4),
-                    ignore_comment: false
+                    ignore_comment: false,
+                    ..FormatConfig::default()
                 }
             ),
-            r#"p(){
-    "test"
-}"#
+            r#"p(){"test"}"#
         );
         assert_eq!(
             element.to_jtml(
@@ -204,12 +324,11 @@ mod test {
                 0,
                 &FormatConfig {
                     indent: crate::formatter::Tab::Spaces(2),
-                    ignore_comment: false
+                    ignore_comment: false,
+                    ..FormatConfig::default()
                 }
             ),
-            r#"p(){
-  "test"
-}"#
+            r#"p(){"test"}"#
         );
         assert_eq!(
             element.to_jtml(
@@ -217,10 +336,11 @@ mod test {
                 0,
                 &FormatConfig {
                     indent: crate::formatter::Tab::Tabs,
-                    ignore_comment: false
+                    ignore_comment: false,
+                    ..FormatConfig::default()
                 }
             ),
-            "p(){\n\t\"test\"\n}"
+            "p(){\"test\"}"
         );
 
         let element = Element {
@@ -230,22 +350,22 @@ mod test {
                 tag_name: "p".to_string(),
                 attributes: Attributes::new(),
                 children: Children::new(),
+                span: Span::default(),
             })]),
+            span: Span::default(),
         };
-        assert_eq!(element.to_html(false), "<p><p></p></p>");
+        assert_eq!(element.to_html(&HtmlOptions::default()), "<p><p></p></p>");
         assert_eq!(
             element.to_jtml(
                 false,
                 0,
                 &FormatConfig {
                     indent: crate::formatter::Tab::Spaces(4),
-                    ignore_comment: false
+                    ignore_comment: false,
+                    ..FormatConfig::default()
                 }
             ),
-            r#"p(){
-    p(){
-    }
-}"#
+            r#"p(){p(){}}"#
         );
         assert_eq!(
             element.to_jtml(
@@ -253,13 +373,11 @@ mod test {
                 0,
                 &FormatConfig {
                     indent: crate::formatter::Tab::Spaces(2),
-                    ignore_comment: false
+                    ignore_comment: false,
+                    ..FormatConfig::default()
                 }
             ),
-            r#"p(){
-  p(){
-  }
-}"#
+            r#"p(){p(){}}"#
         );
         assert_eq!(
             element.to_jtml(
@@ -267,28 +385,31 @@ mod test {
                 0,
                 &FormatConfig {
                     indent: crate::formatter::Tab::Tabs,
-                    ignore_comment: false
+                    ignore_comment: false,
+                    ..FormatConfig::default()
                 }
             ),
-            "p(){\n\tp(){\n\t}\n}"
+            "p(){p(){}}"
         );
 
         let element = Element {
             tag_name: "p".to_string(),
             attributes: Attributes::new(),
             children: Children::from(vec![
-                Node::Text("te".to_string()),
-                Node::Text("st".to_string()),
+                Node::Text("te".to_string(), Span::default()),
+                Node::Text("st".to_string(), Span::default()),
             ]),
+            span: Span::default(),
         };
-        assert_eq!(element.to_html(false), "<p>test</p>");
+        assert_eq!(element.to_html(&HtmlOptions::default()), "<p>test</p>");
         assert_eq!(
             element.to_jtml(
                 false,
                 0,
                 &FormatConfig {
                     indent: crate::formatter::Tab::Spaces(4),
-                    ignore_comment: false
+                    ignore_comment: false,
+                    ..FormatConfig::default()
                 }
             ),
             r#"p(){
@@ -301,28 +422,33 @@ mod test {
             tag_name: "p".to_string(),
             attributes: Attributes::new(),
             children: Children::from(vec![
-                Node::Text("test".to_string()),
+                Node::Text("test".to_string(), Span::default()),
                 Node::Element(Element {
                     tag_name: "p".to_string(),
                     attributes: Attributes::new(),
                     children: Children::new(),
+                    span: Span::default(),
                 }),
             ]),
+            span: Span::default(),
         };
-        assert_eq!(element.to_html(false), "<p>test<p></p></p>");
+        assert_eq!(
+            element.to_html(&HtmlOptions::default()),
+            "<p>test<p></p></p>"
+        );
         assert_eq!(
             element.to_jtml(
                 false,
                 0,
                 &FormatConfig {
                     indent: crate::formatter::Tab::Spaces(4),
-                    ignore_comment: false
+                    ignore_comment: false,
+                    ..FormatConfig::default()
                 }
             ),
             r#"p(){
     "test"
-    p(){
-    }
+    p(){}
 }"#
         );
         assert_eq!(
@@ -331,13 +457,13 @@ mod test {
                 0,
                 &FormatConfig {
                     indent: crate::formatter::Tab::Spaces(2),
-                    ignore_comment: false
+                    ignore_comment: false,
+                    ..FormatConfig::default()
                 }
             ),
             r#"p(){
   "test"
-  p(){
-  }
+  p(){}
 }"#
         );
 
@@ -347,14 +473,50 @@ mod test {
                 0,
                 &FormatConfig {
                     indent: crate::formatter::Tab::Tabs,
-                    ignore_comment: false
+                    ignore_comment: false,
+                    ..FormatConfig::default()
                 }
             ),
             r#"p(){
 	"test"
-	p(){
-	}
+	p(){}
 }"#
         );
     }
+
+    #[test]
+    fn attribute_layout() {
+        let element = Element {
+            tag_name: "meta".to_string(),
+            attributes: Attributes::from(vec![
+                Attribute::new("name", "viewport"),
+                Attribute::new("content", "width=device-width"),
+            ]),
+            children: Children::new(),
+            span: Span::default(),
+        };
+        let config = |attribute_layout, max_width| FormatConfig {
+            attribute_layout,
+            max_width,
+            ..FormatConfig::default()
+        };
+        let single_line = r#"meta(name="viewport" content="width=device-width")"#;
+        let multi_line = "meta(\n    name=\"viewport\"\n    content=\"width=device-width\"\n)";
+        assert_eq!(
+            element.to_jtml(false, 0, &config(AttributeLayout::Auto, 100)),
+            single_line
+        );
+        assert_eq!(
+            element.to_jtml(false, 0, &config(AttributeLayout::Auto, 40)),
+            multi_line
+        );
+        assert_eq!(
+            element.to_jtml(false, 0, &config(AttributeLayout::SingleLine, 40)),
+            single_line
+        );
+        assert_eq!(
+            element.to_jtml(false, 0, &config(AttributeLayout::MultiLine, 100)),
+            multi_line
+        );
+    }
 }
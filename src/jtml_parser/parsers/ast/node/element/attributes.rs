@@ -1,35 +1,123 @@
 use std::collections::VecDeque;
 
-use crate::{formatter::FormatConfig, html_converter::Convert};
+use crate::{
+    formatter::{
+        doc::{self, Doc},
+        FormatConfig,
+    },
+    html_converter::{escape_attribute, is_unquotable, Convert, HtmlOptions, OutputMode},
+    jtml_lexer::escape_string_literal,
+    span::Span,
+};
 
-pub type Attribute = (String, String);
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+    pub name: String,
+    // `disabled`のように値を書かない属性はNone
+    pub value: Option<String>,
+    pub span: Span,
+}
+
+impl Attribute {
+    pub fn new(name: &str, value: &str) -> Self {
+        Attribute {
+            name: name.to_string(),
+            value: Some(value.to_string()),
+            span: Span::default(),
+        }
+    }
+
+    pub fn boolean(name: &str) -> Self {
+        Attribute {
+            name: name.to_string(),
+            value: None,
+            span: Span::default(),
+        }
+    }
+}
 
 impl Convert for Attribute {
-    fn to_html(&self, _: bool) -> String {
-        return format!("{}=\"{}\"", self.0, self.1);
+    fn to_html(&self, options: &HtmlOptions) -> String {
+        match &self.value {
+            Some(value) => {
+                let value = escape_attribute(value);
+                // html5で縮める場合は、引用符なしで同じ値に読める属性値の引用符を省く
+                match options.minify.is_some()
+                    && options.mode == OutputMode::Html5
+                    && is_unquotable(&value)
+                {
+                    true => format!("{}={}", self.name, value),
+                    false => format!("{}=\"{}\"", self.name, value),
+                }
+            }
+            None => options.mode.boolean_attribute(&self.name),
+        }
     }
 
-    fn to_jtml(&self, _: bool, _indent_depth: usize, _config: &FormatConfig) -> String {
-        return format!("{}=\"{}\"", self.0, self.1);
+    fn to_doc(&self, _: bool, _config: &FormatConfig) -> Doc {
+        match &self.value {
+            Some(value) => Doc::text(format!(
+                "{}=\"{}\"",
+                self.name,
+                escape_string_literal(value)
+            )),
+            None => Doc::text(&self.name),
+        }
     }
 }
 
 pub type Attributes = VecDeque<Attribute>;
 
 impl Convert for Attributes {
-    fn to_html(&self, ignore_comment: bool) -> String {
+    fn to_html(&self, options: &HtmlOptions) -> String {
         let mut html: Vec<String> = Vec::new();
         for attribute in self {
-            html.push(attribute.to_html(ignore_comment));
+            html.push(attribute.to_html(options));
         }
-        return html.join(" ");
+        html.join(" ")
     }
 
-    fn to_jtml(&self, ignore_comment: bool, indent_depth: usize, config: &FormatConfig) -> String {
-        let mut jtml: Vec<String> = Vec::new();
-        for attribute in self {
-            jtml.push(attribute.to_jtml(ignore_comment, indent_depth, config));
-        }
-        return jtml.join(" ");
+    fn to_doc(&self, ignore_comment: bool, config: &FormatConfig) -> Doc {
+        let entries = self
+            .iter()
+            .map(|attribute| attribute.to_doc(ignore_comment, config))
+            .collect();
+        doc::attribute_list(entries, self.len(), false, config)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        formatter::{FormatConfig, Tab},
+        html_converter::{Convert, HtmlOptions},
+    };
+
+    use super::Attribute;
+
+    #[test]
+    fn html_escape() {
+        let attribute = Attribute::new("title", r#"say "hi" & <bye>"#);
+        assert_eq!(
+            attribute.to_html(&HtmlOptions::default()),
+            r#"title="say &quot;hi&quot; &amp; &lt;bye&gt;""#
+        );
+    }
+
+    #[test]
+    fn jtml_escape() {
+        let attribute = Attribute::new("title", "say \"hi\"\n");
+        assert_eq!(
+            attribute.to_jtml(
+                false,
+                0,
+                &FormatConfig {
+                    indent: Tab::Spaces(4),
+                    ignore_comment: false,
+                    ..FormatConfig::default()
+                }
+            ),
+            r#"title="say \"hi\"\n""#
+        );
     }
 }
@@ -1,27 +1,30 @@
 use std::collections::VecDeque;
 
-use crate::{formatter::FormatConfig, html_converter::Convert, jtml_parser::parsers::ast::Node};
+use crate::{
+    formatter::{
+        doc::{self, Doc},
+        FormatConfig,
+    },
+    html_converter::{Convert, HtmlOptions},
+    jtml_parser::parsers::ast::Node,
+};
 
 pub type Children = VecDeque<Node>;
 
 impl Convert for Children {
-    fn to_html(&self, ignore_comment: bool) -> String {
+    fn to_html(&self, options: &HtmlOptions) -> String {
         self.iter()
-            .map(|element| element.to_html(ignore_comment))
+            .map(|element| element.to_html(options))
             .collect::<String>()
     }
 
-    fn to_jtml(&self, ignore_comment: bool, indent_depth: usize, config: &FormatConfig) -> String {
-        if self.len() == 0 {
-            "".to_string()
-        } else {
-            format!(
-                "\n{}",
-                self.iter()
-                    .map(|element| element.to_jtml(ignore_comment, indent_depth + 1, config))
-                    .collect::<Vec<String>>()
-                    .join("\n")
-            )
-        }
+    fn to_doc(&self, ignore_comment: bool, config: &FormatConfig) -> Doc {
+        // コメントを出力しない場合はNilになるため取り除く
+        let children = self
+            .iter()
+            .map(|element| element.to_doc(ignore_comment, config))
+            .filter(|doc| !doc.is_nil())
+            .collect();
+        doc::body(children)
     }
 }
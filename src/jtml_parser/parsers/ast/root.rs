@@ -1,6 +1,12 @@
 use std::collections::VecDeque;
 
-use crate::{formatter::FormatConfig, html_converter::Convert};
+use crate::{
+    formatter::{
+        doc::{self, Doc},
+        FormatConfig,
+    },
+    html_converter::{lines, next_output, Convert, HtmlOptions},
+};
 
 use super::Node;
 
@@ -10,18 +16,29 @@ pub struct AstRoot {
 }
 
 impl AstRoot {
-    pub fn to_html(&self, ignore_comment: bool) -> String {
-        self.elements
-            .iter()
-            .map(|element| element.to_html(ignore_comment))
-            .collect::<String>()
+    pub fn to_html(&self, options: &HtmlOptions) -> String {
+        let elements = self.elements.iter().collect::<Vec<_>>();
+        match options.indent() {
+            // 直下のノードは行内のものも含めて字下げしない
+            Some(indent) => lines(&elements, indent, 0, |_, element| element.to_html(options)),
+            None => (0..elements.len())
+                .map(|index| match elements[index] {
+                    Node::Element(element) => {
+                        element.top_level_html(options, next_output(&elements, index))
+                    }
+                    node => node.to_html(options),
+                })
+                .collect::<String>(),
+        }
     }
 
     pub fn to_jtml(&self, ignore_comment: bool, config: &FormatConfig) -> String {
-        self.elements
+        let elements = self
+            .elements
             .iter()
-            .map(|element| element.to_jtml(ignore_comment, 0, config))
-            .collect::<Vec<String>>()
-            .join("\n")
+            .map(|element| element.to_doc(ignore_comment, config))
+            .filter(|doc| !doc.is_nil())
+            .collect::<Vec<Doc>>();
+        doc::lines(elements).render(0, config)
     }
 }
@@ -5,62 +5,84 @@ use crate::{
 
 use std::collections::VecDeque;
 
-pub(crate) fn parse(
-    tokens: &mut VecDeque<JtmlToken>,
-) -> Result<VecDeque<(String, String)>, ParserError> {
-    let mut attributes: VecDeque<(String, String)> = VecDeque::new();
+use super::{
+    ast::node::Attribute,
+    cursor::{unexpected, Cursor},
+};
+
+// 識別子で始まる限り属性として読む
+// 不完全な属性はerrorsに追加して読み飛ばす
+// 属性の間のコメントはHTMLに出力できないためASTには残さない
+pub(crate) fn parse(cursor: &mut Cursor, errors: &mut Vec<ParserError>) -> VecDeque<Attribute> {
+    let mut attributes: VecDeque<Attribute> = VecDeque::new();
     loop {
-        match parse_attribute(tokens) {
-            Ok((key, value)) => attributes.push_back((key, value)),
-            Err(_e) => return Ok(attributes),
+        match cursor.peek().map(|token| &token.token) {
+            Some(JtmlToken::Identifier(_)) => (),
+            Some(JtmlToken::Comment(_)) => {
+                cursor.bump();
+                continue;
+            }
+            _ => break,
+        }
+        match parse_attribute(cursor) {
+            Ok(attribute) => attributes.push_back(attribute),
+            Err(e) => {
+                errors.push(e);
+                cursor.bump();
+                if cursor.at(&JtmlToken::Equal) {
+                    cursor.bump();
+                } else if let Some(JtmlToken::StringLiteral(_)) = cursor.peek().map(|t| &t.token) {
+                    // '='を書き忘れた属性の値も読み飛ばす
+                    cursor.bump();
+                }
+            }
         }
     }
+    attributes
 }
 
-fn parse_attribute(tokens: &mut VecDeque<JtmlToken>) -> Result<(String, String), ParserError> {
-    let key = match tokens.get(0) {
-        Some(token) => match token {
-            JtmlToken::Identifier(key) => key.clone(),
-            _ => {
-                return Err(ParserError::UnexpectedToken(
-                    Kind::Identifier,
-                    tokens[0].clone(),
-                    None,
-                ))
-            }
+// `name="value"`または値のない`name`を読む。名前, '=', 値の間のコメントは読み飛ばす
+// 失敗した場合は読み進めない
+fn parse_attribute(cursor: &mut Cursor) -> Result<Attribute, ParserError> {
+    let (key, start) = match cursor.peek() {
+        Some(token) => match &token.token {
+            JtmlToken::Identifier(key) => (key, token.span),
+            _ => return Err(unexpected(Kind::Identifier, token)),
         },
         None => return Err(ParserError::TokenIsNotEnough(vec![Kind::Identifier])),
     };
-    match tokens.get(1) {
-        Some(token) => match token {
-            JtmlToken::Equal => (),
-            _ => {
-                return Err(ParserError::UnexpectedToken(
-                    Kind::Equal,
-                    tokens[1].clone(),
-                    None,
-                ))
-            }
-        },
-        None => return Err(ParserError::TokenIsNotEnough(vec![Kind::Equal])),
+    let mut ahead = *cursor;
+    ahead.bump();
+    ahead.skip_comments();
+    match ahead.peek().map(|token| (token, &token.token)) {
+        Some((_, JtmlToken::Equal)) => (),
+        // `name "value"`は'='の書き忘れとみなす
+        Some((token, JtmlToken::StringLiteral(_))) => return Err(unexpected(Kind::Equal, token)),
+        _ => {
+            cursor.bump();
+            return Ok(Attribute {
+                name: key.clone(),
+                value: None,
+                span: start,
+            });
+        }
     };
-    let value = match tokens.get(2) {
-        Some(token) => match token {
-            JtmlToken::StringLiteral(_value) => _value.clone(),
-            _ => {
-                return Err(ParserError::UnexpectedToken(
-                    Kind::StringLiteral,
-                    tokens[2].clone(),
-                    None,
-                ))
-            }
+    ahead.bump();
+    ahead.skip_comments();
+    let (value, end) = match ahead.peek() {
+        Some(token) => match &token.token {
+            JtmlToken::StringLiteral(value) => (value, token.span),
+            _ => return Err(unexpected(Kind::StringLiteral, token)),
         },
         None => return Err(ParserError::TokenIsNotEnough(vec![Kind::StringLiteral])),
     };
-    tokens.pop_front();
-    tokens.pop_front();
-    tokens.pop_front();
-    Ok((key, value))
+    ahead.bump();
+    *cursor = ahead;
+    Ok(Attribute {
+        name: key.clone(),
+        value: Some(value.clone()),
+        span: start.to(end),
+    })
 }
 
 #[cfg(test)]
@@ -68,34 +90,95 @@ mod test {
     use std::collections::VecDeque;
 
     use crate::{
-        jtml_lexer::test_utils::lexer,
-        jtml_parser::parsers::attributes::{self, parse_attribute},
+        jtml_lexer::{test_utils::lexer, JtmlToken},
+        jtml_parser::parsers::{
+            ast::node::Attribute,
+            attributes::{self, parse_attribute},
+            cursor::Cursor,
+        },
+        span::Span,
     };
 
+    // 位置を付けた属性
+    fn at(attribute: Attribute, start: usize, end: usize) -> Attribute {
+        Attribute {
+            span: Span::new(start, end),
+            ..attribute
+        }
+    }
+
     #[test]
     fn test_attribute() {
-        let mut tokens = lexer(r#"id="text""#);
-        let result = parse_attribute(&mut tokens);
-        assert_eq!(result.unwrap(), ("id".to_string(), "text".to_string()));
+        let tokens = lexer(r#"id="text""#);
+        let result = parse_attribute(&mut Cursor::new(&tokens)).unwrap();
+        assert_eq!(result, at(Attribute::new("id", "text"), 0, 9));
     }
 
     #[test]
     fn test_attributes() {
-        let mut tokens = lexer(r#"id="text" id2="text2""#);
-        let result = attributes::parse(&mut tokens);
+        let tokens = lexer(r#"id="text" id2="text2""#);
+        let result = attributes::parse(&mut Cursor::new(&tokens), &mut Vec::new());
         assert_eq!(
-            result.unwrap(),
+            result,
             VecDeque::from(vec![
-                ("id".to_string(), "text".to_string()),
-                ("id2".to_string(), "text2".to_string())
+                at(Attribute::new("id", "text"), 0, 9),
+                at(Attribute::new("id2", "text2"), 10, 21)
             ])
         );
     }
 
+    #[test]
+    fn test_boolean_attributes() {
+        let tokens = lexer(r#"type="checkbox" checked disabled)"#);
+        let mut errors = Vec::new();
+        let result = attributes::parse(&mut Cursor::new(&tokens), &mut errors);
+        assert_eq!(
+            result,
+            VecDeque::from(vec![
+                at(Attribute::new("type", "checkbox"), 0, 15),
+                at(Attribute::boolean("checked"), 16, 23),
+                at(Attribute::boolean("disabled"), 24, 32)
+            ])
+        );
+        assert!(errors.is_empty());
+    }
+
     #[test]
     fn test_empty_attributes() {
-        let mut tokens = lexer(r#""#);
-        let result = attributes::parse(&mut tokens);
-        assert_eq!(result.unwrap(), VecDeque::from(vec![]));
+        let tokens = lexer(r#""#);
+        let result = attributes::parse(&mut Cursor::new(&tokens), &mut Vec::new());
+        assert_eq!(result, VecDeque::from(vec![]));
+    }
+
+    #[test]
+    fn test_invalid_attributes() {
+        let tokens = lexer(r#"a "x" b="c" d= e="f")"#);
+        let mut errors = Vec::new();
+        let mut cursor = Cursor::new(&tokens);
+        let result = attributes::parse(&mut cursor, &mut errors);
+        assert_eq!(
+            result,
+            VecDeque::from(vec![
+                at(Attribute::new("b", "c"), 6, 11),
+                at(Attribute::new("e", "f"), 15, 20)
+            ])
+        );
+        assert_eq!(errors.len(), 2);
+        assert!(cursor.at(&JtmlToken::RightParen));
+    }
+
+    #[test]
+    fn test_comment_between_attributes() {
+        let tokens = lexer("// first\nid=\"text\" // second\nid2=\"text2\")");
+        let mut errors = Vec::new();
+        let result = attributes::parse(&mut Cursor::new(&tokens), &mut errors);
+        assert_eq!(
+            result,
+            VecDeque::from(vec![
+                at(Attribute::new("id", "text"), 9, 18),
+                at(Attribute::new("id2", "text2"), 29, 40)
+            ])
+        );
+        assert!(errors.is_empty());
     }
 }
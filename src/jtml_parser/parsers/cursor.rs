@@ -0,0 +1,111 @@
+use crate::{
+    jtml_lexer::{JtmlToken, Kind, Token},
+    jtml_parser::ParserError,
+    span::Span,
+};
+
+// トークン列を借用して先頭から読み進める
+// 読み進めるのは位置を進めるだけなので、トークン列の複製は行わない
+#[derive(Clone, Copy)]
+pub(crate) struct Cursor<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub(crate) fn new(tokens: &'a [Token]) -> Self {
+        Cursor {
+            tokens,
+            position: 0,
+        }
+    }
+
+    pub(crate) fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.position)
+    }
+
+    pub(crate) fn at(&self, expect: &JtmlToken) -> bool {
+        self.peek().is_some_and(|token| token == expect)
+    }
+
+    pub(crate) fn bump(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.get(self.position)?;
+        self.position += 1;
+        Some(token)
+    }
+
+    // コメントを飛ばした次のトークンがnextを満たす場合だけ、その前のコメントを読み飛ばす
+    // 名前と'('の間などのコメントはHTMLに出力できないためASTには残さない
+    pub(crate) fn skip_comments_before(&mut self, next: impl Fn(&JtmlToken) -> bool) {
+        let mut ahead = *self;
+        ahead.skip_comments();
+        if ahead.peek().is_some_and(|token| next(&token.token)) {
+            *self = ahead;
+        }
+    }
+
+    pub(crate) fn skip_comments(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|token| matches!(token.token, JtmlToken::Comment(_)))
+        {
+            self.position += 1;
+        }
+    }
+
+    // 期待したトークンであれば読み進めてそのSpanを返す
+    // 違った場合は読み進めずにエラーを返す
+    pub(crate) fn expect(&mut self, expect: JtmlToken) -> Result<Span, ParserError> {
+        match self.peek() {
+            Some(token) if *token == expect => {
+                self.position += 1;
+                Ok(token.span)
+            }
+            Some(token) => Err(unexpected(expect.into(), token)),
+            None => Err(ParserError::TokenIsNotEnough(vec![expect.into()])),
+        }
+    }
+}
+
+pub(crate) fn unexpected(expect: Kind, actual: &Token) -> ParserError {
+    ParserError::UnexpectedToken(expect, actual.kind(), actual.span)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        jtml_lexer::{test_utils::lexer, JtmlToken, Kind},
+        jtml_parser::ParserError,
+        span::Span,
+    };
+
+    use super::Cursor;
+
+    #[test]
+    fn expect() {
+        let tokens = lexer(r#"("#);
+        let mut cursor = Cursor::new(&tokens);
+        assert_eq!(cursor.expect(JtmlToken::LeftParen), Ok(Span::new(0, 1)));
+        assert!(cursor.peek().is_none());
+        assert_eq!(
+            cursor.expect(JtmlToken::LeftParen),
+            Err(ParserError::TokenIsNotEnough(vec![Kind::LeftParen]))
+        );
+    }
+
+    #[test]
+    fn expect_error() {
+        let tokens = lexer(r#"("#);
+        let mut cursor = Cursor::new(&tokens);
+        assert_eq!(
+            cursor.expect(JtmlToken::RightParen),
+            Err(ParserError::UnexpectedToken(
+                Kind::RightParen,
+                Kind::LeftParen,
+                Span::new(0, 1)
+            ))
+        );
+        // 失敗した場合は読み進めない
+        assert!(cursor.at(&JtmlToken::LeftParen));
+    }
+}
@@ -1,19 +1,63 @@
 use super::super::errors::ParserError;
+use super::cursor::Cursor;
 use super::{nodes, AstRoot};
 
-use crate::jtml_lexer::JtmlToken;
+use crate::jtml_lexer::{JtmlToken, Kind};
+use crate::span::Span;
 
 use std::collections::VecDeque;
 
-pub fn parse(tokens: &mut VecDeque<JtmlToken>) -> Result<AstRoot, ParserError> {
-    let elements = nodes::parse(tokens);
+// エラーが起きても最後まで読み進め、読めた部分のASTと全てのエラーを返す
+pub fn parse(cursor: &mut Cursor) -> (AstRoot, Vec<ParserError>) {
+    let mut errors = Vec::new();
+    let mut elements = VecDeque::new();
+    loop {
+        elements.extend(nodes::parse(cursor, &mut errors));
 
-    // tokenを消費しきっていない場合はast_nodes_parserの最終エラーを返す(未実装)
-    if !tokens.is_empty() {
-        return Err(elements.1);
+        // ノードを開始できないトークンは次のノードの先頭まで読み飛ばし、まとめて一つのエラーにする
+        match cursor.peek() {
+            Some(token) => {
+                let end = skip(cursor);
+                errors.push(ParserError::UnexpectedToken(
+                    Kind::Identifier,
+                    token.kind(),
+                    Span::new(token.span.start, end),
+                ));
+            }
+            None => break,
+        }
     }
 
-    return Ok(AstRoot {
-        elements: elements.0,
+    // 同じトークンで連鎖的に起きたエラーは最初のものだけを報告する
+    errors.dedup_by(|next, previous| match (next.span(), previous.span()) {
+        (Some(next), Some(previous)) => next.start == previous.start,
+        _ => false,
     });
+
+    (AstRoot { elements }, errors)
+}
+
+// 括弧の外でノードを開始できるトークンの直前まで読み進め、読み飛ばした最後のトークンの終わりを返す
+// 括弧の中の名前は属性名なので、対応する')'までは読み飛ばす
+fn skip(cursor: &mut Cursor) -> usize {
+    let mut depth = 0usize;
+    let mut end = 0;
+    while let Some(token) = cursor.peek() {
+        match token.token {
+            JtmlToken::LeftParen => depth += 1,
+            JtmlToken::RightParen => depth = depth.saturating_sub(1),
+            JtmlToken::Identifier(_)
+            | JtmlToken::Selector(_)
+            | JtmlToken::StringLiteral(_)
+            | JtmlToken::Comment(_)
+                if depth == 0 =>
+            {
+                break
+            }
+            _ => (),
+        }
+        end = token.span.end;
+        cursor.bump();
+    }
+    end
 }
@@ -1,71 +1,390 @@
-use crate::jtml_lexer::Kind;
-
-use crate::jtml_lexer::JtmlToken;
-use crate::jtml_parser::parsers::ast::node::Element;
+use crate::jtml_lexer::{JtmlToken, Kind, Selector};
+use crate::jtml_parser::parsers::ast::node::{
+    Attribute, Component, Element, For, If, BLOCK_TAG, DEFINE_TAG, ELSE_TAG, EXTENDS_TAG, FOR_TAG,
+    IF_TAG, IMPORT_TAG, INCLUDE_TAG, RAW_HTML_TAG, SLOT_TAG,
+};
 use crate::jtml_parser::parsers::ast::Node;
 use crate::jtml_parser::parsers::attributes;
+use crate::jtml_parser::parsers::cursor::{unexpected, Cursor};
 use crate::jtml_parser::parsers::is_self_terminating_tag;
-use crate::jtml_parser::parsers::one_token;
 use crate::jtml_parser::ParserError;
+use crate::span::Span;
 
 use std::collections::VecDeque;
 
 use super::nodes;
 
-pub(crate) fn parse(tokens: &mut VecDeque<JtmlToken>) -> Result<Node, ParserError> {
+// 先頭のトークンがノードを開始できない場合(トークンが無い場合も含む)は何も消費せずにNoneを返す
+// 要素の途中でエラーが起きた場合はerrorsに追加し、そこまでに読めた部分だけの要素を返す
+pub(crate) fn parse(cursor: &mut Cursor, errors: &mut Vec<ParserError>) -> Option<Node> {
     // elementの場合はelement_nameを取得
     // StringLiteral, Commentの場合はそのまま返す
-    let element_name = match tokens.front() {
-        Some(token) => match token {
-            JtmlToken::StringLiteral(text) => {
-                let new_text = text.clone();
-                tokens.pop_front();
-                return Ok(Node::Text(new_text));
+    let token = cursor.peek()?;
+    let (element_name, start, selector) = match &token.token {
+        JtmlToken::StringLiteral(text) => {
+            cursor.bump();
+            return Some(Node::Text(text.clone(), token.span));
+        }
+        JtmlToken::Comment(text) => {
+            cursor.bump();
+            return Some(Node::Comment(text.clone(), token.span));
+        }
+        JtmlToken::Identifier(id) if id == DEFINE_TAG => {
+            cursor.bump();
+            return Some(define(token.span, cursor, errors));
+        }
+        JtmlToken::Identifier(id) => (id.clone(), token.span, None),
+        JtmlToken::Selector(selector) => {
+            (selector.tag_name().to_string(), token.span, Some(selector))
+        }
+        _ => return None,
+    };
+    cursor.bump();
+
+    let mut element = parse_element(element_name, start, cursor, errors);
+    if let Some(selector) = selector {
+        desugar(selector, start, &mut element.attributes);
+    }
+    match element.tag_name.as_str() {
+        RAW_HTML_TAG => Some(raw_html(element, errors)),
+        IF_TAG => Some(if_node(element, cursor, errors)),
+        FOR_TAG => Some(for_node(element, errors)),
+        // 読み込みと置き換えはtransformerで行うため、ここでは属性だけを確かめる
+        INCLUDE_TAG | IMPORT_TAG => {
+            control_flow_attributes(&element, ["src"], errors);
+            Some(Node::Element(element))
+        }
+        EXTENDS_TAG => {
+            control_flow_attributes(&element, ["layout"], errors);
+            Some(Node::Element(element))
+        }
+        BLOCK_TAG => {
+            control_flow_attributes(&element, ["name"], errors);
+            Some(Node::Element(element))
+        }
+        ELSE_TAG => {
+            errors.push(ParserError::ElseWithoutIf(name_span(&element)));
+            Some(Node::Element(element))
+        }
+        _ => Some(Node::Element(element)),
+    }
+}
+
+// `div.a#b(class="c")`を`div(id="b" class="a c")`として扱う
+// 書かれたidとは併せず、重複はlinterで警告する
+fn desugar(selector: &Selector, span: Span, attributes: &mut VecDeque<Attribute>) {
+    if !selector.classes.is_empty() {
+        let classes = selector.classes.join(" ");
+        match attributes
+            .iter_mut()
+            .find(|attribute| attribute.name == "class")
+        {
+            Some(attribute) => {
+                attribute.value = match attribute.value.as_deref().map(str::trim) {
+                    Some(value) if !value.is_empty() => Some(format!("{} {}", classes, value)),
+                    _ => Some(classes),
+                }
             }
-            JtmlToken::Comment(text) => {
-                let new_text = text.clone();
-                tokens.pop_front();
-                return Ok(Node::Comment(new_text));
+            None => attributes.push_front(Attribute {
+                name: "class".to_string(),
+                value: Some(classes),
+                span,
+            }),
+        }
+    }
+    if let Some(id) = &selector.id {
+        attributes.push_front(Attribute {
+            name: "id".to_string(),
+            value: Some(id.clone()),
+            span,
+        });
+    }
+}
+
+// raw(){"<b>html</b>"} の文字列をエスケープしないHTMLとして扱う
+fn raw_html(element: Element, errors: &mut Vec<ParserError>) -> Node {
+    let mut html = String::new();
+    for child in &element.children {
+        let name = match child {
+            Node::Text(text, _) => {
+                html.push_str(text);
+                continue;
             }
-            JtmlToken::Identifier(id) => id.clone(),
+            Node::Comment(_, _) => continue,
+            Node::Element(child) => child.tag_name.as_str(),
+            Node::Raw(_, _) => RAW_HTML_TAG,
+            Node::If(_) => IF_TAG,
+            Node::For(_) => FOR_TAG,
+            Node::Define(_) => DEFINE_TAG,
+        };
+        let start = child.span().start;
+        errors.push(ParserError::UnexpectedToken(
+            Kind::StringLiteral,
+            Kind::Identifier,
+            Span::new(start, start + name.len()),
+        ));
+    }
+    Node::Raw(html, element.span)
+}
+
+// 要素の名前の部分
+fn name_span(element: &Element) -> Span {
+    Span::new(
+        element.span.start,
+        element.span.start + element.tag_name.len(),
+    )
+}
+
+// 制御構文の属性をnamesの順に取り出す
+// 足りない属性と知らない属性はエラーにし、同じ属性が複数ある場合は最初のものを使う
+fn control_flow_attributes<const N: usize>(
+    element: &Element,
+    names: [&'static str; N],
+    errors: &mut Vec<ParserError>,
+) -> [(String, Span); N] {
+    let mut values: [Option<(String, Span)>; N] = [const { None }; N];
+    let mut written = [false; N];
+    for attribute in &element.attributes {
+        let Some(i) = names.iter().position(|name| *name == attribute.name) else {
+            errors.push(ParserError::UnknownAttribute(
+                element.tag_name.clone(),
+                attribute.name.clone(),
+                attribute.span,
+            ));
+            continue;
+        };
+        if written[i] {
+            continue;
+        }
+        written[i] = true;
+        match &attribute.value {
+            Some(value) => values[i] = Some((value.clone(), attribute.span)),
+            // 値のない属性
+            None => errors.push(ParserError::MissingAttribute(
+                element.tag_name.clone(),
+                names[i],
+                attribute.span,
+            )),
+        }
+    }
+    for (name, written) in names.iter().zip(written) {
+        if !written {
+            errors.push(ParserError::MissingAttribute(
+                element.tag_name.clone(),
+                name,
+                name_span(element),
+            ));
+        }
+    }
+    values.map(|value| value.unwrap_or_else(|| (String::new(), name_span(element))))
+}
+
+// if(cond="name"){...}と、直後に続くelse(){...}
+fn if_node(element: Element, cursor: &mut Cursor, errors: &mut Vec<ParserError>) -> Node {
+    let [(condition, condition_span)] = control_flow_attributes(&element, ["cond"], errors);
+    let mut node = If {
+        condition,
+        condition_span,
+        then: element.children,
+        between: VecDeque::new(),
+        otherwise: None,
+        span: element.span,
+    };
+    // ifとelseの間のコメントは、elseが続く場合だけ飛ばしてifに含める
+    let mut between = *cursor;
+    cursor.skip_comments_before(|token| *token == JtmlToken::Identifier(ELSE_TAG.to_string()));
+    let Some(token) = cursor.peek() else {
+        return Node::If(node);
+    };
+    if token.token == JtmlToken::Identifier(ELSE_TAG.to_string()) {
+        while let Some(token) = between.bump() {
+            let JtmlToken::Comment(text) = &token.token else {
+                break;
+            };
+            node.between
+                .push_back(Node::Comment(text.clone(), token.span));
+        }
+        cursor.bump();
+        let otherwise = parse_element(ELSE_TAG.to_string(), token.span, cursor, errors);
+        control_flow_attributes(&otherwise, [], errors);
+        node.span = node.span.to(otherwise.span);
+        node.otherwise = Some(otherwise.children);
+    }
+    Node::If(node)
+}
+
+// `define`の後に部品の名前、引数、本体が続く
+// 名前がない場合はエラーにし、`define`という名前の要素として読む
+fn define(start: Span, cursor: &mut Cursor, errors: &mut Vec<ParserError>) -> Node {
+    cursor.skip_comments_before(|token| matches!(token, JtmlToken::Identifier(_)));
+    let (name, name_span) = match cursor.peek() {
+        Some(token) => match &token.token {
+            JtmlToken::Identifier(name) => (name.clone(), token.span),
             _ => {
-                return Err(ParserError::UnexpectedToken(
-                    Kind::Identifier,
-                    token.clone(),
-                    Some(tokens.clone()),
-                ))
+                errors.push(unexpected(Kind::Identifier, token));
+                return Node::Element(parse_element(DEFINE_TAG.to_string(), start, cursor, errors));
             }
         },
         None => {
-            return Err(ParserError::TokenIsNotEnough(vec![
-                Kind::StringLiteral,
-                Kind::Comment,
-                Kind::Identifier,
-            ]))
+            errors.push(ParserError::TokenIsNotEnough(vec![Kind::Identifier]));
+            return Node::Element(parse_element(DEFINE_TAG.to_string(), start, cursor, errors));
         }
     };
-    tokens.pop_front();
-
-    one_token::parse(JtmlToken::LeftParen, tokens)?;
-    let attributes = attributes::parse(tokens)?;
-    one_token::parse(JtmlToken::RightParen, tokens)?;
-
-    if is_self_terminating_tag(&element_name) {
-        return Ok(Node::Element(Element {
-            tag_name: element_name,
-            attributes: attributes,
-            children: VecDeque::from(vec![]),
-        }));
-    }
-    one_token::parse(JtmlToken::LeftBracket, tokens)?;
-    let children = nodes::parse(tokens).0;
-    one_token::parse(JtmlToken::RightBracket, tokens)?;
-
-    Ok(Node::Element(Element {
-        tag_name: element_name,
-        attributes: attributes,
-        children: children,
-    }))
+    cursor.bump();
+    let element = parse_element(name.clone(), start, cursor, errors);
+    Node::Define(Component {
+        name,
+        name_span,
+        parameters: element.attributes,
+        children: element.children,
+        span: element.span,
+    })
+}
+
+// for(item="x" in="items"){...}
+fn for_node(element: Element, errors: &mut Vec<ParserError>) -> Node {
+    let [(item, item_span), (items, items_span)] =
+        control_flow_attributes(&element, ["item", "in"], errors);
+    Node::For(For {
+        item,
+        item_span,
+        items,
+        items_span,
+        children: element.children,
+        span: element.span,
+    })
+}
+
+fn parse_element(
+    tag_name: String,
+    start: Span,
+    cursor: &mut Cursor,
+    errors: &mut Vec<ParserError>,
+) -> Element {
+    let mut element = Element {
+        tag_name,
+        attributes: VecDeque::new(),
+        children: VecDeque::new(),
+        span: start,
+    };
+
+    cursor.skip_comments_before(|token| *token == JtmlToken::LeftParen);
+    match cursor.expect(JtmlToken::LeftParen) {
+        Ok(_) => {
+            element.attributes = attributes::parse(cursor, errors);
+            if let Some(attribute) = element.attributes.back() {
+                element.span = start.to(attribute.span);
+            }
+            match cursor.expect(JtmlToken::RightParen) {
+                Ok(end) => element.span = start.to(end),
+                Err(error) => {
+                    errors.push(error);
+                    if let Some(end) = skip_attributes(cursor) {
+                        element.span = start.to(end);
+                    }
+                }
+            }
+        }
+        // '('を書き忘れただけで後に')'か'{'が続く場合は、そのまま要素として読み進める
+        Err(error) => {
+            errors.push(error);
+            match cursor.peek().map(|token| &token.token) {
+                Some(JtmlToken::RightParen) => {
+                    let end = cursor.bump().unwrap().span;
+                    element.span = start.to(end);
+                }
+                Some(JtmlToken::LeftBracket) => (),
+                _ => return element,
+            }
+        }
+    }
+    cursor.skip_comments_before(|token| *token == JtmlToken::LeftBracket);
+
+    if is_self_terminating_tag(&element.tag_name)
+        || matches!(
+            element.tag_name.as_str(),
+            INCLUDE_TAG | IMPORT_TAG | EXTENDS_TAG
+        )
+    {
+        // 空要素とinclude, import, extendsは'{}'を省略できるが、空の'{}'を書いてもよい
+        if cursor
+            .peek()
+            .is_some_and(|token| token.token == JtmlToken::LeftBracket)
+        {
+            parse_body(&mut element, start, cursor, errors);
+            let mut children = element
+                .children
+                .iter()
+                .filter(|child| !matches!(child, Node::Comment(_, _)))
+                .map(|child| child.span());
+            if let Some(first) = children.next() {
+                let span = first.to(children.next_back().unwrap_or(first));
+                errors.push(ParserError::VoidElementWithChildren(
+                    element.tag_name.clone(),
+                    span,
+                ));
+            }
+        }
+        return element;
+    }
+    // 部品の本体のslotとレイアウトのblockは`slot()`と書けるが、既定の内容を子として持つこともできる
+    if matches!(element.tag_name.as_str(), SLOT_TAG | BLOCK_TAG)
+        && !cursor.at(&JtmlToken::LeftBracket)
+    {
+        return element;
+    }
+    parse_body(&mut element, start, cursor, errors);
+    element
+}
+
+// '{'から対応する'}'までを子として読む
+fn parse_body(
+    element: &mut Element,
+    start: Span,
+    cursor: &mut Cursor,
+    errors: &mut Vec<ParserError>,
+) {
+    match cursor.expect(JtmlToken::LeftBracket) {
+        Ok(end) => element.span = start.to(end),
+        Err(error) => {
+            errors.push(error);
+            return;
+        }
+    }
+
+    loop {
+        element.children.extend(nodes::parse(cursor, errors));
+        if let Some(child) = element.children.back() {
+            element.span = start.to(child.span());
+        }
+        match cursor.bump() {
+            Some(token) if token.token == JtmlToken::RightBracket => {
+                element.span = start.to(token.span);
+                return;
+            }
+            // 子要素になれないトークンは読み飛ばして続きを解析する
+            Some(token) => errors.push(unexpected(Kind::RightBracket, token)),
+            None => {
+                errors.push(ParserError::TokenIsNotEnough(vec![Kind::RightBracket]));
+                return;
+            }
+        }
+    }
+}
+
+// 属性リストの残りを')'まで読み飛ばす
+// '{', '}' またはトークンの末尾に達した場合はそれを消費せずに止まる
+fn skip_attributes(cursor: &mut Cursor) -> Option<Span> {
+    while let Some(token) = cursor.peek() {
+        match token.token {
+            JtmlToken::RightParen => return cursor.bump().map(|token| token.span),
+            JtmlToken::LeftBracket | JtmlToken::RightBracket => return None,
+            _ => {
+                cursor.bump();
+            }
+        }
+    }
+    None
 }
 
 #[cfg(test)]
@@ -73,21 +392,34 @@ mod test {
     use std::collections::VecDeque;
 
     use crate::jtml_lexer::test_utils::lexer;
-    use crate::jtml_lexer::Kind;
+    use crate::jtml_lexer::{Kind, Token};
     use crate::jtml_parser::errors::ParserError;
-    use crate::jtml_parser::parsers::ast::node::{Element, Node};
+    use crate::jtml_parser::parsers::ast::node::{Attribute, Component, Element, For, If, Node};
+    use crate::jtml_parser::parsers::cursor::Cursor;
     use crate::jtml_parser::parsers::node;
+    use crate::jtml_parser::test_utils::strip_node;
+    use crate::span::Span;
+
+    fn parse(tokens: &[Token]) -> Result<Node, ParserError> {
+        let mut errors = Vec::new();
+        let node = node::parse(&mut Cursor::new(tokens), &mut errors);
+        match errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(node.unwrap()),
+        }
+    }
 
     #[test]
     fn element() {
-        let mut tokens = lexer(r#"p(){}"#);
-        let result = node::parse(&mut tokens);
+        let tokens = lexer(r#"p(){}"#);
+        let result = parse(&tokens);
         assert_eq!(
             result.unwrap(),
             Node::Element(Element {
                 tag_name: "p".to_string(),
                 attributes: VecDeque::from(vec![]),
-                children: VecDeque::from(vec![])
+                children: VecDeque::from(vec![]),
+                span: Span::new(0, 5),
             })
         );
     }
@@ -95,8 +427,8 @@ mod test {
     // 未実装
     // #[test]
     // fn element_non_child() {
-    //     let mut tokens = lexer(r#"img()"#);
-    //     let result = ast_node_parser::parse(&mut tokens);
+    //     let tokens = lexer(r#"img()"#);
+    //     let result = ast_node_parser::parse(&tokens);
     //     assert_eq!(
     //         result.unwrap(),
     //         AstNode::Element(ElementNode {
@@ -109,39 +441,44 @@ mod test {
 
     #[test]
     fn element_with_attribute() {
-        let mut tokens = lexer(r#"p(width="100"){}"#);
-        let result = node::parse(&mut tokens);
+        let tokens = lexer(r#"p(width="100"){}"#);
+        let result = parse(&tokens);
         assert_eq!(
             result.unwrap(),
             Node::Element(Element {
                 tag_name: "p".to_string(),
-                attributes: VecDeque::from(vec![("width".to_string(), "100".to_string())]),
-                children: VecDeque::from(vec![])
+                attributes: VecDeque::from(vec![Attribute {
+                    span: Span::new(2, 13),
+                    ..Attribute::new("width", "100")
+                }]),
+                children: VecDeque::from(vec![]),
+                span: Span::new(0, 16),
             })
         );
     }
     #[test]
     fn element_with_string() {
-        let mut tokens = lexer(r#"p(){"hello"}"#);
-        let result = node::parse(&mut tokens);
+        let tokens = lexer(r#"p(){"hello"}"#);
+        let result = parse(&tokens);
 
         assert_eq!(
             result.unwrap(),
             Node::Element(Element {
                 tag_name: "p".to_string(),
                 attributes: VecDeque::from(vec![]),
-                children: VecDeque::from(vec![Node::Text("hello".to_string())])
+                children: VecDeque::from(vec![Node::Text("hello".to_string(), Span::new(4, 11))]),
+                span: Span::new(0, 12),
             })
         );
     }
 
     #[test]
     fn node_with_child_node() {
-        let mut tokens = lexer(r#"p(){p(){"test"}p(){"test1""test2"}}}"#);
-        let result = node::parse(&mut tokens);
+        let tokens = lexer(r#"p(){p(){"test"}p(){"test1""test2"}}}"#);
+        let result = parse(&tokens);
 
         assert_eq!(
-            result.unwrap(),
+            strip_node(&result.unwrap()),
             Node::Element(Element {
                 tag_name: "p".to_string(),
                 attributes: VecDeque::from(vec![]),
@@ -149,25 +486,31 @@ mod test {
                     Node::Element(Element {
                         tag_name: "p".to_string(),
                         attributes: VecDeque::from(vec![]),
-                        children: VecDeque::from(vec![Node::Text("test".to_string())])
+                        children: VecDeque::from(vec![Node::Text(
+                            "test".to_string(),
+                            Span::default()
+                        )]),
+                        span: Span::default(),
                     }),
                     Node::Element(Element {
                         tag_name: "p".to_string(),
                         attributes: VecDeque::from(vec![]),
                         children: VecDeque::from(vec![
-                            Node::Text("test1".to_string()),
-                            Node::Text("test2".to_string())
-                        ])
+                            Node::Text("test1".to_string(), Span::default()),
+                            Node::Text("test2".to_string(), Span::default())
+                        ]),
+                        span: Span::default(),
                     })
-                ])
+                ]),
+                span: Span::default(),
             })
         );
     }
 
     #[test]
     fn invalid_element_right_bracket() {
-        let mut tokens = lexer(r#"p(){"#);
-        let result = node::parse(&mut tokens);
+        let tokens = lexer(r#"p(){"#);
+        let result = parse(&tokens);
         assert_eq!(
             result.unwrap_err(),
             ParserError::TokenIsNotEnough(vec![Kind::RightBracket])
@@ -176,8 +519,8 @@ mod test {
 
     #[test]
     fn invalid_element_left_bracket() {
-        let mut tokens = lexer(r#"p()"#);
-        let result = node::parse(&mut tokens);
+        let tokens = lexer(r#"p()"#);
+        let result = parse(&tokens);
         assert_eq!(
             result.unwrap_err(),
             ParserError::TokenIsNotEnough(vec![Kind::LeftBracket])
@@ -186,8 +529,8 @@ mod test {
 
     #[test]
     fn invalid_element_right_paren() {
-        let mut tokens = lexer(r#"p("#);
-        let result = node::parse(&mut tokens);
+        let tokens = lexer(r#"p("#);
+        let result = parse(&tokens);
         assert_eq!(
             result.unwrap_err(),
             ParserError::TokenIsNotEnough(vec![Kind::RightParen])
@@ -196,15 +539,277 @@ mod test {
 
     #[test]
     fn invalid_element_left_paren() {
-        let mut tokens = lexer(r#"p)"#);
-        let result = node::parse(&mut tokens);
+        let tokens = lexer(r#"p)"#);
+        let result = parse(&tokens);
         assert_eq!(
             result.unwrap_err(),
-            ParserError::UnexpectedToken(
-                Kind::LeftParen,
-                crate::jtml_lexer::JtmlToken::RightParen,
-                None
-            )
+            ParserError::UnexpectedToken(Kind::LeftParen, Kind::RightParen, Span::new(1, 2))
+        );
+    }
+
+    #[test]
+    fn invalid_child_element() {
+        let tokens = lexer(r#"p(){p){}}"#);
+        let result = parse(&tokens).unwrap_err();
+        assert_eq!(
+            result,
+            ParserError::UnexpectedToken(Kind::LeftParen, Kind::RightParen, Span::new(5, 6))
+        );
+    }
+
+    #[test]
+    fn comments_between_tokens() {
+        // 名前と'('、'='の前後、')'と'{'の間のコメントは読み飛ばす
+        let source = "p // a\n(b // b\n= // c\n\"1\") // d\n{\"x\"}";
+        let tokens = lexer(source);
+        let Node::Element(element) = parse(&tokens).unwrap() else {
+            panic!("expected an element");
+        };
+        assert_eq!(
+            element,
+            Element {
+                tag_name: "p".to_string(),
+                attributes: VecDeque::from(vec![Attribute {
+                    span: Span::new(8, 25),
+                    ..Attribute::new("b", "1")
+                }]),
+                children: VecDeque::from(vec![Node::Text("x".to_string(), Span::new(33, 36))]),
+                span: Span::new(0, 37),
+            }
+        );
+
+        let Node::Define(component) = parse(&lexer("define // a\ncard(){}")).unwrap() else {
+            panic!("expected a definition");
+        };
+        assert_eq!(component.name, "card");
+
+        // 後に'('や'{'が続かないコメントは次のノードとして残す
+        let tokens = lexer("img() // a\np(){}");
+        let mut cursor = Cursor::new(&tokens);
+        let mut errors = Vec::new();
+        assert!(matches!(
+            node::parse(&mut cursor, &mut errors),
+            Some(Node::Element(element)) if element.children.is_empty()
+        ));
+        assert!(matches!(
+            node::parse(&mut cursor, &mut errors),
+            Some(Node::Comment(text, _)) if text == "a"
+        ));
+        assert!(errors.is_empty());
+
+        // コメントの後に値がない場合は、コメントではなく実際のトークンを指す
+        assert_eq!(
+            parse(&lexer("p(a= // b\n){}")).unwrap_err(),
+            ParserError::UnexpectedToken(Kind::StringLiteral, Kind::RightParen, Span::new(10, 11))
+        );
+    }
+
+    #[test]
+    fn void_element_body() {
+        let tokens = lexer(r#"img(){}"#);
+        let Node::Element(element) = parse(&tokens).unwrap() else {
+            panic!("expected an element");
+        };
+        assert_eq!((element.span.start, element.span.end), (0, 7));
+        assert!(element.children.is_empty());
+
+        let tokens = lexer(
+            r#"img(){ // c
+        "a" p(){} }"#,
+        );
+        let error = parse(&tokens).unwrap_err();
+        assert_eq!(
+            error,
+            ParserError::VoidElementWithChildren("img".to_string(), Span::new(20, 29))
+        );
+    }
+
+    #[test]
+    fn selector() {
+        let tokens = lexer(r#"p.a.b#main(title="t" class="c"){}"#);
+        let Node::Element(element) = parse(&tokens).unwrap() else {
+            panic!("expected an element");
+        };
+        assert_eq!(element.tag_name, "p");
+        assert_eq!(
+            element.attributes,
+            VecDeque::from(vec![
+                Attribute {
+                    span: Span::new(0, 10),
+                    ..Attribute::new("id", "main")
+                },
+                Attribute {
+                    span: Span::new(11, 20),
+                    ..Attribute::new("title", "t")
+                },
+                Attribute {
+                    span: Span::new(21, 30),
+                    ..Attribute::new("class", "a b c")
+                },
+            ])
+        );
+
+        // 要素名を省略した場合はdiv
+        let tokens = lexer(r#".card(){}"#);
+        let Node::Element(element) = parse(&tokens).unwrap() else {
+            panic!("expected an element");
+        };
+        assert_eq!(element.tag_name, "div");
+        assert_eq!(
+            element.attributes,
+            VecDeque::from(vec![Attribute {
+                span: Span::new(0, 5),
+                ..Attribute::new("class", "card")
+            }])
+        );
+    }
+
+    #[test]
+    fn control_flow() {
+        let text = |text: &str| Node::Text(text.to_string(), Span::default());
+        let tokens = lexer(r#"if(cond="!a"){"x"} else(){"y"}"#);
+        let node = parse(&tokens).unwrap();
+        assert_eq!(
+            strip_node(&node),
+            Node::If(If {
+                condition: "!a".to_string(),
+                condition_span: Span::default(),
+                then: VecDeque::from(vec![text("x")]),
+                between: VecDeque::new(),
+                otherwise: Some(VecDeque::from(vec![text("y")])),
+                span: Span::default(),
+            })
+        );
+        assert_eq!((node.span().start, node.span().end), (0, 30));
+
+        // ifとelseの間のコメントはifに含める
+        let tokens = lexer("if(cond=\"a\"){\"yes\"}\n// note\nelse(){\"no\"}");
+        let Node::If(node) = parse(&tokens).unwrap() else {
+            panic!("expected if");
+        };
+        assert_eq!(
+            node.between,
+            VecDeque::from(vec![Node::Comment("note".to_string(), Span::new(20, 27))])
+        );
+        assert_eq!(
+            node.otherwise,
+            Some(VecDeque::from(vec![Node::Text(
+                "no".to_string(),
+                Span::new(35, 39)
+            )]))
+        );
+
+        let tokens = lexer(r#"for(item="x" in="items"){"{{ x }}"}"#);
+        let node = parse(&tokens).unwrap();
+        assert_eq!(
+            strip_node(&node),
+            Node::For(For {
+                item: "x".to_string(),
+                item_span: Span::default(),
+                items: "items".to_string(),
+                items_span: Span::default(),
+                children: VecDeque::from(vec![text("{{ x }}")]),
+                span: Span::default(),
+            })
+        );
+        let Node::For(node) = node else {
+            panic!("expected for");
+        };
+        assert_eq!(node.items_span, Span::new(13, 23));
+    }
+
+    #[test]
+    fn control_flow_errors() {
+        let errors = |source: &str| {
+            let mut errors = Vec::new();
+            let tokens = lexer(source);
+            let mut cursor = Cursor::new(&tokens);
+            while node::parse(&mut cursor, &mut errors).is_some() {}
+            errors
+                .iter()
+                .map(|error| (error.to_string(), error.span().unwrap().start))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            errors(r#"if(){} else(x="y"){} for(item="x" of="a" in){}"#),
+            vec![
+                ("`if` requires `cond`".to_string(), 0),
+                ("`else` does not take `x`".to_string(), 12),
+                ("`for` does not take `of`".to_string(), 34),
+                ("`for` requires `in`".to_string(), 41),
+            ]
+        );
+        assert_eq!(
+            errors(r#"p(){} else(){}"#),
+            vec![("`else` without `if`".to_string(), 6)]
+        );
+        assert_eq!(
+            errors(r#"include() import(src="a.jtml"){} include(src="a.jtml"){p(){}}"#),
+            vec![
+                ("`include` requires `src`".to_string(), 0),
+                (
+                    "void element `include` cannot have children".to_string(),
+                    55
+                ),
+            ]
+        );
+        assert_eq!(
+            errors(r#"extends(src="a.jtml") block(){}"#),
+            vec![
+                ("`extends` does not take `src`".to_string(), 8),
+                ("`extends` requires `layout`".to_string(), 0),
+                ("`block` requires `name`".to_string(), 22),
+            ]
+        );
+    }
+
+    #[test]
+    fn define() {
+        let tokens = lexer(r#"define card(title size="m"){ slot() }"#);
+        let node = parse(&tokens).unwrap();
+        assert_eq!(
+            strip_node(&node),
+            Node::Define(Component {
+                name: "card".to_string(),
+                name_span: Span::default(),
+                parameters: VecDeque::from(vec![
+                    Attribute::boolean("title"),
+                    Attribute::new("size", "m")
+                ]),
+                children: VecDeque::from(vec![Node::Element(Element {
+                    tag_name: "slot".to_string(),
+                    attributes: VecDeque::new(),
+                    children: VecDeque::new(),
+                    span: Span::default(),
+                })]),
+                span: Span::default(),
+            })
+        );
+        assert_eq!((node.span().start, node.span().end), (0, 37));
+
+        let tokens = lexer(r#"define(){}"#);
+        assert_eq!(
+            parse(&tokens).unwrap_err(),
+            ParserError::UnexpectedToken(Kind::Identifier, Kind::LeftParen, Span::new(6, 7))
+        );
+    }
+
+    #[test]
+    fn raw_html() {
+        let tokens = lexer(
+            r#"raw(){"<b>" // comment
+        "bold</b>"}"#,
+        );
+        let result = parse(&tokens).unwrap();
+        assert_eq!(
+            result,
+            Node::Raw("<b>bold</b>".to_string(), Span::new(0, 42))
+        );
+
+        let tokens = lexer(r#"raw(){p(){}}"#);
+        assert_eq!(
+            parse(&tokens).unwrap_err(),
+            ParserError::UnexpectedToken(Kind::StringLiteral, Kind::Identifier, Span::new(6, 7))
         );
     }
 }
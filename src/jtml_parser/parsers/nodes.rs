@@ -1,21 +1,18 @@
-use crate::jtml_lexer::JtmlToken;
 use crate::jtml_parser::ParserError;
 
 use std::collections::VecDeque;
 
 use super::ast::Node;
+use super::cursor::Cursor;
 use super::node;
 
-pub(crate) fn parse(tokens: &mut VecDeque<JtmlToken>) -> (VecDeque<Node>, ParserError) {
+// ノードを開始できないトークン('}'など)かトークンの末尾まで読む
+pub(crate) fn parse(cursor: &mut Cursor, errors: &mut Vec<ParserError>) -> VecDeque<Node> {
     let mut elements: VecDeque<Node> = VecDeque::new();
-    loop {
-        match node::parse(tokens) {
-            Ok(e) => {
-                elements.push_back(e);
-            }
-            Err(e) => return (elements, e),
-        }
+    while let Some(node) = node::parse(cursor, errors) {
+        elements.push_back(node);
     }
+    elements
 }
 
 #[cfg(test)]
@@ -27,15 +24,21 @@ mod test {
         jtml_parser::{
             errors::ParserError,
             parsers::{
-                ast::{node::Element, Node},
+                ast::{
+                    node::{Attribute, Element},
+                    Node,
+                },
+                cursor::Cursor,
                 nodes,
             },
+            test_utils::strip,
         },
+        span::Span,
     };
 
     #[test]
     fn normal() {
-        let mut tokens = lexer(
+        let tokens = lexer(
             r#"
         p(){
 
@@ -45,19 +48,21 @@ mod test {
         }
         "#,
         );
-        let parsed = nodes::parse(&mut tokens).0;
+        let parsed = nodes::parse(&mut Cursor::new(&tokens), &mut Vec::new());
         assert_eq!(
-            parsed,
+            strip(&parsed),
             VecDeque::from(vec![
                 Node::Element(Element {
                     tag_name: "p".to_string(),
                     attributes: VecDeque::from(vec![]),
-                    children: VecDeque::from(vec![])
+                    children: VecDeque::from(vec![]),
+                    span: Span::default(),
                 }),
                 Node::Element(Element {
                     tag_name: "p".to_string(),
                     attributes: VecDeque::from(vec![]),
-                    children: VecDeque::from(vec![])
+                    children: VecDeque::from(vec![]),
+                    span: Span::default(),
                 })
             ])
         );
@@ -65,7 +70,7 @@ mod test {
 
     #[test]
     fn complicated() {
-        let mut tokens = lexer(
+        let tokens = lexer(
             r#"
         "stringliteral"
         p(a="b"){
@@ -77,21 +82,26 @@ mod test {
         }
         "#,
         );
-        let parsed = nodes::parse(&mut tokens).0;
+        let parsed = nodes::parse(&mut Cursor::new(&tokens), &mut Vec::new());
         assert_eq!(
-            parsed,
+            strip(&parsed),
             VecDeque::from(vec![
-                Node::Text("stringliteral".to_string()),
+                Node::Text("stringliteral".to_string(), Span::default()),
                 Node::Element(Element {
                     tag_name: "p".to_string(),
-                    attributes: VecDeque::from(vec![("a".to_string(), "b".to_string())]),
-                    children: VecDeque::from(vec![Node::Text("child".to_string())])
+                    attributes: VecDeque::from(vec![Attribute::new("a", "b")]),
+                    children: VecDeque::from(vec![Node::Text(
+                        "child".to_string(),
+                        Span::default()
+                    )]),
+                    span: Span::default(),
                 }),
-                Node::Comment("comment".to_string()),
+                Node::Comment("comment".to_string(), Span::default()),
                 Node::Element(Element {
                     tag_name: "p".to_string(),
                     attributes: VecDeque::from(vec![]),
-                    children: VecDeque::from(vec![])
+                    children: VecDeque::from(vec![]),
+                    span: Span::default(),
                 })
             ])
         );
@@ -99,7 +109,7 @@ mod test {
 
     #[test]
     fn error_handling_check() {
-        let mut tokens = lexer(
+        let tokens = lexer(
             r#"
         p(a="b"){
             "child"
@@ -108,13 +118,14 @@ mod test {
         p(){
         "#,
         );
-        let parsed = nodes::parse(&mut tokens);
+        let mut errors = Vec::new();
+        nodes::parse(&mut Cursor::new(&tokens), &mut errors);
         assert_eq!(
-            parsed.1,
+            errors[0],
             (ParserError::TokenIsNotEnough(vec![Kind::RightBracket]))
         );
 
-        let mut tokens = lexer(
+        let tokens = lexer(
             r#"
         p(a="b"){
             "child"
@@ -123,14 +134,11 @@ mod test {
         p(){(
         "#,
         );
-        let parsed = nodes::parse(&mut tokens);
+        let mut errors = Vec::new();
+        nodes::parse(&mut Cursor::new(&tokens), &mut errors);
         assert_eq!(
-            parsed.1,
-            (ParserError::UnexpectedToken(
-                Kind::RightBracket,
-                crate::jtml_lexer::JtmlToken::LeftParen,
-                None
-            ))
+            errors[0],
+            (ParserError::UnexpectedToken(Kind::RightBracket, Kind::LeftParen, Span::new(80, 81)))
         )
     }
 }
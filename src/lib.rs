@@ -1,4 +1,13 @@
+pub mod diagnostics;
 pub mod formatter;
+pub mod highlight;
 pub mod html_converter;
+pub mod html_spec;
+pub mod html_to_jtml;
 pub mod jtml_lexer;
 pub mod jtml_parser;
+pub mod linter;
+pub mod lsp;
+pub mod span;
+pub mod template;
+pub mod transformer;
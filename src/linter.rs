@@ -0,0 +1,88 @@
+use crate::{
+    diagnostics::Diagnostic,
+    jtml_parser::{AstRoot, Element, Node},
+};
+
+// 構文としては正しいが、意図した出力にならない可能性のある書き方を警告する
+pub fn lint(ast: &AstRoot) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for node in &ast.elements {
+        lint_node(node, &mut diagnostics);
+    }
+    diagnostics
+}
+
+fn lint_node(node: &Node, diagnostics: &mut Vec<Diagnostic>) {
+    let children = match node {
+        Node::Element(element) => {
+            duplicate_attribute(element, diagnostics);
+            missing_alt(element, diagnostics);
+            &element.children
+        }
+        Node::If(node) => {
+            for child in node.otherwise.iter().flatten() {
+                lint_node(child, diagnostics);
+            }
+            &node.then
+        }
+        Node::For(node) => &node.children,
+        Node::Define(component) => &component.children,
+        _ => return,
+    };
+    for child in children {
+        lint_node(child, diagnostics);
+    }
+}
+
+fn duplicate_attribute(element: &Element, diagnostics: &mut Vec<Diagnostic>) {
+    for (i, attribute) in element.attributes.iter().enumerate() {
+        if element
+            .attributes
+            .iter()
+            .take(i)
+            .any(|other| other.name == attribute.name)
+        {
+            diagnostics.push(
+                Diagnostic::warning("duplicate attribute", attribute.span)
+                    .with_label(format!(
+                        "`{}` is already set on this element",
+                        attribute.name
+                    ))
+                    .with_hint("browsers only use the first value"),
+            );
+        }
+    }
+}
+
+fn missing_alt(element: &Element, diagnostics: &mut Vec<Diagnostic>) {
+    if element.tag_name == "img" && element.attributes.iter().all(|a| a.name != "alt") {
+        diagnostics.push(
+            Diagnostic::warning("image without alt text", element.span)
+                .with_label("add an `alt` attribute")
+                .with_hint("use `alt=\"\"` for decorative images"),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{jtml_lexer::test_utils::lexer, jtml_parser::parse};
+
+    use super::lint;
+
+    #[test]
+    fn duplicate_attribute() {
+        let source = r#"div(){p(class="a" id="b" class="c"){}}"#;
+        let diagnostics = lint(&parse(&lexer(source)).unwrap());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(&source[diagnostics[0].span.range()], r#"class="c""#);
+    }
+
+    #[test]
+    fn missing_alt() {
+        let source = r#"img(src="a.png") img(src="b.png" alt="")"#;
+        let diagnostics = lint(&parse(&lexer(source)).unwrap());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(&source[diagnostics[0].span.range()], r#"img(src="a.png")"#);
+    }
+}
@@ -0,0 +1,504 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as LspNotification, PublishDiagnostics,
+    },
+    request::{
+        Completion, DocumentSymbolRequest, FoldingRangeRequest, Formatting, HoverRequest,
+        Request as LspRequest, SemanticTokensFullRequest,
+    },
+    CompletionOptions, CompletionResponse, DocumentFormattingParams, DocumentSymbolResponse,
+    FoldingRangeProviderCapability, FormattingOptions, HoverProviderCapability, InitializeParams,
+    OneOf, PublishDiagnosticsParams, SemanticTokens, SemanticTokensFullOptions,
+    SemanticTokensOptions, SemanticTokensResult, SemanticTokensServerCapabilities,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, Uri,
+};
+
+use crate::formatter::{FormatConfig, Tab};
+
+mod completion;
+mod features;
+#[cfg(test)]
+mod harness;
+mod semantic_tokens;
+
+// connectionの相手をクライアントとしてLSPのサーバーとして動く
+// クライアントからshutdownとexitを受け取るまで戻らない
+pub fn run(connection: &Connection) -> anyhow::Result<()> {
+    let params: InitializeParams =
+        serde_json::from_value(connection.initialize(serde_json::to_value(capabilities())?)?)?;
+    let snippets = params
+        .capabilities
+        .text_document
+        .and_then(|document| document.completion?.completion_item?.snippet_support)
+        .unwrap_or(false);
+    let mut server = Server {
+        connection,
+        documents: HashMap::new(),
+        snippets,
+    };
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                server.request(request)?;
+            }
+            Message::Notification(notification) => server.notification(notification)?,
+            Message::Response(_) => (),
+        }
+    }
+    Ok(())
+}
+
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        // 変更のたびに全文を受け取る
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["(".to_string()]),
+            ..CompletionOptions::default()
+        }),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+            SemanticTokensOptions {
+                legend: semantic_tokens::legend(),
+                full: Some(SemanticTokensFullOptions::Bool(true)),
+                ..SemanticTokensOptions::default()
+            },
+        )),
+        ..ServerCapabilities::default()
+    }
+}
+
+struct Server<'a> {
+    connection: &'a Connection,
+    // 開かれている文書の最新の内容
+    documents: HashMap<Uri, String>,
+    // クライアントがスニペットを挿入できるか
+    snippets: bool,
+}
+
+impl Server<'_> {
+    fn request(&mut self, request: Request) -> anyhow::Result<()> {
+        let response = match request.method.as_str() {
+            Formatting::METHOD => respond::<Formatting>(request, |params| self.formatting(params)),
+            DocumentSymbolRequest::METHOD => respond::<DocumentSymbolRequest>(request, |params| {
+                Ok(self.document(&params.text_document.uri).map(|source| {
+                    DocumentSymbolResponse::Nested(features::document_symbols(source))
+                }))
+            }),
+            FoldingRangeRequest::METHOD => respond::<FoldingRangeRequest>(request, |params| {
+                Ok(self
+                    .document(&params.text_document.uri)
+                    .map(features::folding_ranges))
+            }),
+            Completion::METHOD => respond::<Completion>(request, |params| {
+                let position = params.text_document_position;
+                Ok(self
+                    .document(&position.text_document.uri)
+                    .map(|source| completion::completion(source, position.position, self.snippets))
+                    .map(CompletionResponse::Array))
+            }),
+            HoverRequest::METHOD => respond::<HoverRequest>(request, |params| {
+                let position = params.text_document_position_params;
+                Ok(self
+                    .document(&position.text_document.uri)
+                    .and_then(|source| completion::hover(source, position.position)))
+            }),
+            SemanticTokensFullRequest::METHOD => {
+                respond::<SemanticTokensFullRequest>(request, |params| {
+                    Ok(self.document(&params.text_document.uri).map(|source| {
+                        SemanticTokensResult::Tokens(SemanticTokens {
+                            result_id: None,
+                            data: semantic_tokens::semantic_tokens(source),
+                        })
+                    }))
+                })
+            }
+            _ => Response::new_err(
+                request.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unknown request: {}", request.method),
+            ),
+        };
+        self.connection.sender.send(response.into())?;
+        Ok(())
+    }
+
+    fn notification(&mut self, notification: Notification) -> anyhow::Result<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params = notification
+                    .extract::<<DidOpenTextDocument as LspNotification>::Params>(
+                        DidOpenTextDocument::METHOD,
+                    )?;
+                let document = params.text_document;
+                self.documents.insert(document.uri.clone(), document.text);
+                self.publish_diagnostics(document.uri, Some(document.version))
+            }
+            DidChangeTextDocument::METHOD => {
+                let params = notification
+                    .extract::<<DidChangeTextDocument as LspNotification>::Params>(
+                        DidChangeTextDocument::METHOD,
+                    )?;
+                // 全文同期のため、最後の変更が最新の内容になる
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents
+                        .insert(params.text_document.uri.clone(), change.text);
+                }
+                self.publish_diagnostics(
+                    params.text_document.uri,
+                    Some(params.text_document.version),
+                )
+            }
+            DidCloseTextDocument::METHOD => {
+                let params = notification
+                    .extract::<<DidCloseTextDocument as LspNotification>::Params>(
+                        DidCloseTextDocument::METHOD,
+                    )?;
+                self.documents.remove(&params.text_document.uri);
+                self.publish_diagnostics(params.text_document.uri, None)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn document(&self, uri: &Uri) -> Option<&str> {
+        self.documents.get(uri).map(String::as_str)
+    }
+
+    // 閉じられた文書は診断を空にする
+    fn publish_diagnostics(&self, uri: Uri, version: Option<i32>) -> anyhow::Result<()> {
+        let diagnostics = match self.document(&uri) {
+            Some(source) => features::diagnostics(source),
+            None => Vec::new(),
+        };
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version,
+        };
+        self.connection
+            .sender
+            .send(Notification::new(PublishDiagnostics::METHOD.to_string(), params).into())?;
+        Ok(())
+    }
+
+    fn formatting(
+        &self,
+        params: DocumentFormattingParams,
+    ) -> Result<<Formatting as LspRequest>::Result, String> {
+        let uri = params.text_document.uri;
+        let Some(source) = self.document(&uri) else {
+            return Ok(None);
+        };
+        let config = format_config(&uri, &params.options)?;
+        Ok(features::formatting(source, &config))
+    }
+}
+
+// リクエストの引数を取り出してhandlerに渡し、その結果をレスポンスにする
+fn respond<R: LspRequest>(
+    request: Request,
+    handler: impl FnOnce(R::Params) -> Result<R::Result, String>,
+) -> Response {
+    let id = request.id.clone();
+    match request.extract::<R::Params>(R::METHOD) {
+        Ok((id, params)) => match handler(params) {
+            Ok(result) => Response::new_ok(id, result),
+            Err(message) => Response::new_err(id, ErrorCode::RequestFailed as i32, message),
+        },
+        Err(e) => Response::new_err(id, ErrorCode::InvalidParams as i32, e.to_string()),
+    }
+}
+
+// ファイルは`jtml fmt`と同じくjtml.tomlや.editorconfigから設定を探す
+// 保存されていない文書はエディタのインデント設定に従う
+fn format_config(uri: &Uri, options: &FormattingOptions) -> Result<FormatConfig, String> {
+    match file_path(uri) {
+        Some(path) => FormatConfig::discover(&path).map_err(|e| format!("invalid config {}", e)),
+        None => Ok(FormatConfig {
+            indent: if options.insert_spaces {
+                Tab::Spaces(options.tab_size as usize)
+            } else {
+                Tab::Tabs
+            },
+            ..FormatConfig::default()
+        }),
+    }
+}
+
+fn file_path(uri: &Uri) -> Option<PathBuf> {
+    if uri.scheme()?.as_str() != "file" {
+        return None;
+    }
+    let path = uri.path().as_estr().decode().into_string().ok()?;
+    Some(PathBuf::from(path.as_ref()))
+}
+
+#[cfg(test)]
+mod test {
+    use lsp_types::{
+        notification::{DidChangeTextDocument, DidCloseTextDocument, PublishDiagnostics},
+        request::{
+            Completion, DocumentSymbolRequest, FoldingRangeRequest, Formatting, HoverRequest,
+            SemanticTokensFullRequest,
+        },
+        CompletionParams, CompletionResponse, CompletionTextEdit, DiagnosticSeverity,
+        DidChangeTextDocumentParams, DidCloseTextDocumentParams, DocumentFormattingParams,
+        DocumentSymbolParams, DocumentSymbolResponse, FoldingRangeParams, FormattingOptions,
+        HoverContents, HoverParams, Position, Range, SemanticTokensParams, SemanticTokensResult,
+        TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentPositionParams,
+        VersionedTextDocumentIdentifier,
+    };
+
+    use super::harness::{uri, TestClient};
+
+    #[test]
+    fn diagnostics_on_change() {
+        let mut client = TestClient::new();
+        client.open("index.jtml", "p(){\"a\"}");
+        let published = client.notification::<PublishDiagnostics>();
+        assert_eq!(published.version, Some(1));
+        assert!(published.diagnostics.is_empty());
+
+        client.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri("index.jtml"), 2),
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: "p(){\n    \"あ\" ) img(src=\"a.png\")\n}".to_string(),
+            }],
+        });
+        let published = client.notification::<PublishDiagnostics>();
+        assert_eq!(published.version, Some(2));
+        let severities = published
+            .diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.severity, diagnostic.range))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            severities,
+            vec![
+                (
+                    Some(DiagnosticSeverity::ERROR),
+                    Range::new(Position::new(1, 8), Position::new(1, 9))
+                ),
+                (
+                    Some(DiagnosticSeverity::WARNING),
+                    Range::new(Position::new(1, 10), Position::new(1, 26))
+                ),
+            ]
+        );
+
+        client.notify::<DidCloseTextDocument>(DidCloseTextDocumentParams {
+            text_document: TextDocumentIdentifier::new(uri("index.jtml")),
+        });
+        assert!(client
+            .notification::<PublishDiagnostics>()
+            .diagnostics
+            .is_empty());
+        client.shutdown();
+    }
+
+    #[test]
+    fn formatting() {
+        let mut client = TestClient::new();
+        client.open("index.jtml", "div(){\"a\" p(){ \"b\" }}");
+        let params = |uri| DocumentFormattingParams {
+            text_document: TextDocumentIdentifier::new(uri),
+            options: FormattingOptions {
+                tab_size: 2,
+                insert_spaces: true,
+                ..FormattingOptions::default()
+            },
+            work_done_progress_params: Default::default(),
+        };
+        let edits = client
+            .request::<Formatting>(params(uri("index.jtml")))
+            .unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].new_text, "div(){\n  \"a\"\n  p(){\"b\"}\n}\n");
+        assert_eq!(
+            edits[0].range,
+            Range::new(Position::new(0, 0), Position::new(0, 21))
+        );
+
+        // 構文エラーがある場合は整形しない
+        client.open("broken.jtml", "div(");
+        assert_eq!(
+            client.request::<Formatting>(params(uri("broken.jtml"))),
+            None
+        );
+        client.shutdown();
+    }
+
+    #[test]
+    fn document_symbols() {
+        let mut client = TestClient::new();
+        client.open(
+            "index.jtml",
+            "html(){\n    body(id=\"top\" class=\"a b\"){\n        p(){\"text\"}\n        br()\n    }\n}",
+        );
+        let response = client.request::<DocumentSymbolRequest>(DocumentSymbolParams {
+            text_document: TextDocumentIdentifier::new(uri("index.jtml")),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        let Some(DocumentSymbolResponse::Nested(symbols)) = response else {
+            panic!("expected nested symbols: {:?}", response);
+        };
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "html");
+        assert_eq!(
+            symbols[0].range,
+            Range::new(Position::new(0, 0), Position::new(5, 1))
+        );
+        let body = &symbols[0].children.as_ref().unwrap()[0];
+        assert_eq!(body.name, "body");
+        assert_eq!(body.detail.as_deref(), Some("#top.a.b"));
+        assert_eq!(
+            body.selection_range,
+            Range::new(Position::new(1, 4), Position::new(1, 8))
+        );
+        let children = body
+            .children
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|symbol| symbol.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(children, vec!["p", "br"]);
+        client.shutdown();
+    }
+
+    #[test]
+    fn folding_ranges() {
+        let mut client = TestClient::new();
+        client.open(
+            "index.jtml",
+            "html(){\n    head(){title(){\"a\"}}\n    body(){\n        \"b\"\n    }\n}",
+        );
+        let ranges = client
+            .request::<FoldingRangeRequest>(FoldingRangeParams {
+                text_document: TextDocumentIdentifier::new(uri("index.jtml")),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            })
+            .unwrap();
+        // 閉じ括弧の行は折りたたまずに残す
+        let lines = ranges
+            .iter()
+            .map(|range| (range.start_line, range.end_line))
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![(0, 4), (2, 3)]);
+        client.shutdown();
+    }
+
+    fn position(name: &str, line: u32, character: u32) -> TextDocumentPositionParams {
+        TextDocumentPositionParams::new(
+            TextDocumentIdentifier::new(uri(name)),
+            Position::new(line, character),
+        )
+    }
+
+    fn complete(client: &mut TestClient, line: u32, character: u32) -> Vec<(String, String)> {
+        let response = client.request::<Completion>(CompletionParams {
+            text_document_position: position("index.jtml", line, character),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        });
+        let Some(CompletionResponse::Array(items)) = response else {
+            panic!("expected completion items: {:?}", response);
+        };
+        items
+            .into_iter()
+            .map(|item| match item.text_edit {
+                Some(CompletionTextEdit::Edit(edit)) => (item.label, edit.new_text),
+                _ => panic!("expected a text edit: {:?}", item.label),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn completion() {
+        let mut client = TestClient::new();
+        client.open(
+            "index.jtml",
+            "html(){\n    head(){me}\n    body(){\n        img(src=\"a.png\" )\n    }\n}",
+        );
+        // headの中ではメタデータの要素だけを補完する
+        let items = complete(&mut client, 1, 13);
+        assert!(items.contains(&("meta".to_string(), "meta(".to_string())));
+        assert!(items.iter().all(|(label, _)| label != "div"));
+
+        let items = complete(&mut client, 3, 8);
+        assert!(items.iter().any(|(label, _)| label == "div"));
+
+        // 書かれていない属性だけを補完する
+        let items = complete(&mut client, 3, 24);
+        assert!(items.contains(&("alt".to_string(), "alt=".to_string())));
+        assert!(items.iter().any(|(label, _)| label == "class"));
+        assert!(items.iter().all(|(label, _)| label != "src"));
+        client.shutdown();
+    }
+
+    #[test]
+    fn hover() {
+        let mut client = TestClient::new();
+        client.open("index.jtml", "head(){meta(charset=\"UTF-8\")}");
+        let hover = |client: &mut TestClient, character| {
+            client
+                .request::<HoverRequest>(HoverParams {
+                    text_document_position_params: position("index.jtml", 0, character),
+                    work_done_progress_params: Default::default(),
+                })
+                .map(|hover| match hover.contents {
+                    HoverContents::Markup(content) => (content.value, hover.range),
+                    contents => panic!("expected markdown: {:?}", contents),
+                })
+        };
+        let (value, range) = hover(&mut client, 9).unwrap();
+        assert!(value.starts_with("**meta** (void element)"));
+        assert_eq!(
+            range,
+            Some(Range::new(Position::new(0, 7), Position::new(0, 11)))
+        );
+        let (value, _) = hover(&mut client, 14).unwrap();
+        assert!(value.starts_with("**charset** (attribute of `meta`)"));
+        // 文字列の中では何も表示しない
+        assert_eq!(hover(&mut client, 22), None);
+        client.shutdown();
+    }
+
+    #[test]
+    fn semantic_tokens() {
+        let mut client = TestClient::new();
+        client.open("index.jtml", "// a\nimg(src=\"a.png\")");
+        let response = client.request::<SemanticTokensFullRequest>(SemanticTokensParams {
+            text_document: TextDocumentIdentifier::new(uri("index.jtml")),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        let Some(SemanticTokensResult::Tokens(tokens)) = response else {
+            panic!("expected semantic tokens: {:?}", response);
+        };
+        let types = tokens
+            .data
+            .iter()
+            .map(|token| token.token_type)
+            .collect::<Vec<_>>();
+        // コメント, 要素名, 属性名, 属性値
+        assert_eq!(types, vec![3, 0, 1, 2]);
+        client.shutdown();
+    }
+}
@@ -0,0 +1,277 @@
+use lsp_types::{
+    self as lsp, CompletionItem, CompletionItemKind, CompletionTextEdit, Documentation, Hover,
+    HoverContents, InsertTextFormat, MarkupContent, MarkupKind, TextEdit,
+};
+
+use crate::{
+    html_spec::{self, AttributeSpec, ElementSpec},
+    jtml_lexer::{lossless_lexer, JtmlToken},
+    jtml_parser::RAW_HTML_TAG,
+    span::{LineIndex, Position, Span},
+};
+
+// jtml独自の要素
+const RAW: ElementSpec = ElementSpec {
+    name: RAW_HTML_TAG,
+    description: "Outputs its string as HTML without escaping. Only use it for trusted HTML.",
+    void: false,
+    metadata: false,
+    inline: false,
+    attributes: &[],
+};
+
+// カーソル位置が何を書く場所か
+#[derive(Debug, Clone, PartialEq)]
+enum Context {
+    // 要素名。parentは囲んでいる要素の名前
+    Tag { parent: Option<String> },
+    // 属性名。existingはすでに書かれた属性の名前
+    Attribute { tag: String, existing: Vec<String> },
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-'
+}
+
+// offsetを含む、書きかけの名前の範囲
+fn word(source: &str, offset: usize) -> Span {
+    let start = source[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_name_char(*c))
+        .last()
+        .map_or(offset, |(i, _)| i);
+    let end = source[offset..]
+        .char_indices()
+        .find(|(_, c)| !is_name_char(*c))
+        .map_or(source.len(), |(i, _)| offset + i);
+    Span::new(start, end)
+}
+
+// 名前の前までのトークンを読み、要素の入れ子と属性リストの中かどうかを求める
+// 文字列やコメントの中、'='の直後など名前を書く場所でなければNone
+fn context(source: &str, start: usize) -> Option<Context> {
+    let tokens = lossless_lexer(&source[..start]).ok()?;
+    if let Some(last) = tokens.last() {
+        // コメントは行末まで続く
+        if let JtmlToken::Comment(_) = last.token {
+            return None;
+        }
+    }
+    let mut elements: Vec<String> = Vec::new();
+    let mut attributes: Option<(String, Vec<String>)> = None;
+    // 直前に読んだ要素名
+    let mut tag: Option<String> = None;
+    let mut after_equal = false;
+    for token in tokens.iter().filter(|token| !token.is_trivia()) {
+        after_equal = token.token == JtmlToken::Equal;
+        match &token.token {
+            JtmlToken::Identifier(name) => match &mut attributes {
+                Some((_, existing)) => existing.push(name.clone()),
+                None => tag = Some(name.clone()),
+            },
+            JtmlToken::Selector(selector) => tag = Some(selector.tag_name().to_string()),
+            JtmlToken::LeftParen => {
+                attributes = Some((tag.clone().unwrap_or_default(), Vec::new()));
+            }
+            JtmlToken::RightParen => attributes = None,
+            JtmlToken::LeftBracket => {
+                attributes = None;
+                elements.push(tag.take().unwrap_or_default());
+            }
+            JtmlToken::RightBracket => {
+                attributes = None;
+                tag = None;
+                elements.pop();
+            }
+            _ => (),
+        }
+    }
+    if after_equal {
+        return None;
+    }
+    Some(match attributes {
+        Some((tag, existing)) => Context::Attribute { tag, existing },
+        None => Context::Tag {
+            parent: elements.pop(),
+        },
+    })
+}
+
+pub(super) fn completion(
+    source: &str,
+    position: lsp::Position,
+    snippets: bool,
+) -> Vec<CompletionItem> {
+    let index = LineIndex::new(source);
+    let offset = index.utf16_offset(source, from_lsp(position));
+    let word = word(source, offset);
+    let range = range(word, &index, source);
+    match context(source, word.start) {
+        Some(Context::Tag { parent }) => {
+            // headの中にはメタデータの要素しか書けない
+            let in_head = parent.as_deref() == Some("head");
+            html_spec::ELEMENTS
+                .iter()
+                .chain([&RAW])
+                .filter(|element| !in_head || element.metadata)
+                .map(|element| tag_item(element, range, snippets))
+                .collect()
+        }
+        Some(Context::Attribute { tag, existing }) => {
+            let attributes: Box<dyn Iterator<Item = &AttributeSpec>> =
+                match html_spec::element(&tag) {
+                    Some(element) => Box::new(element.all_attributes()),
+                    None => Box::new(html_spec::GLOBAL_ATTRIBUTES.iter()),
+                };
+            attributes
+                .filter(|attribute| !existing.iter().any(|name| name == attribute.name))
+                .map(|attribute| attribute_item(attribute, range, snippets))
+                .collect()
+        }
+        None => Vec::new(),
+    }
+}
+
+fn tag_item(element: &ElementSpec, range: lsp::Range, snippets: bool) -> CompletionItem {
+    let new_text = match (snippets, element.void) {
+        (true, true) => format!("{}($1)", element.name),
+        (true, false) => format!("{}($1){{$0}}", element.name),
+        (false, _) => format!("{}(", element.name),
+    };
+    CompletionItem {
+        label: element.name.to_string(),
+        kind: Some(CompletionItemKind::PROPERTY),
+        documentation: Some(Documentation::MarkupContent(markdown(element.description))),
+        insert_text_format: snippets.then_some(InsertTextFormat::SNIPPET),
+        text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(range, new_text))),
+        ..CompletionItem::default()
+    }
+}
+
+fn attribute_item(attribute: &AttributeSpec, range: lsp::Range, snippets: bool) -> CompletionItem {
+    let new_text = if snippets {
+        format!("{}=\"$1\"", attribute.name)
+    } else {
+        format!("{}=", attribute.name)
+    };
+    CompletionItem {
+        label: attribute.name.to_string(),
+        kind: Some(CompletionItemKind::FIELD),
+        documentation: Some(Documentation::MarkupContent(markdown(
+            attribute.description,
+        ))),
+        insert_text_format: snippets.then_some(InsertTextFormat::SNIPPET),
+        text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(range, new_text))),
+        ..CompletionItem::default()
+    }
+}
+
+// 要素名や属性名の説明を表示する
+pub(super) fn hover(source: &str, position: lsp::Position) -> Option<Hover> {
+    let index = LineIndex::new(source);
+    let offset = index.utf16_offset(source, from_lsp(position));
+    let word = word(source, offset);
+    if word.is_empty() {
+        return None;
+    }
+    let name = &source[word.range()];
+    let value = match context(source, word.start)? {
+        Context::Tag { .. } => {
+            let element = html_spec::element(name).or((name == RAW.name).then_some(&RAW))?;
+            let kind = if element.void { " (void element)" } else { "" };
+            format!("**{}**{}\n\n{}", element.name, kind, element.description)
+        }
+        Context::Attribute { tag, .. } => {
+            let (attribute, owner) = match html_spec::element(&tag)
+                .and_then(|element| element.attributes.iter().find(|a| a.name == name))
+            {
+                Some(attribute) => (attribute, format!("attribute of `{}`", tag)),
+                None => (
+                    html_spec::GLOBAL_ATTRIBUTES
+                        .iter()
+                        .find(|attribute| attribute.name == name)?,
+                    "global attribute".to_string(),
+                ),
+            };
+            format!(
+                "**{}** ({})\n\n{}",
+                attribute.name, owner, attribute.description
+            )
+        }
+    };
+    Some(Hover {
+        contents: HoverContents::Markup(markdown(&value)),
+        range: Some(range(word, &index, source)),
+    })
+}
+
+fn markdown(value: &str) -> MarkupContent {
+    MarkupContent {
+        kind: MarkupKind::Markdown,
+        value: value.to_string(),
+    }
+}
+
+fn from_lsp(position: lsp::Position) -> Position {
+    Position {
+        line: position.line as usize,
+        column: position.character as usize,
+    }
+}
+
+fn range(span: Span, index: &LineIndex, source: &str) -> lsp::Range {
+    super::features::range(span, index, source)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{context, word, Context};
+
+    fn context_at(source: &str) -> Option<Context> {
+        let offset = source.find('|').unwrap();
+        let source = source.replace('|', "");
+        context(&source, word(&source, offset).start)
+    }
+
+    #[test]
+    fn contexts() {
+        assert_eq!(
+            context_at("html(){head(){me|}}"),
+            Some(Context::Tag {
+                parent: Some("head".to_string())
+            })
+        );
+        assert_eq!(
+            context_at("head(){title(){\"a\"} |}"),
+            Some(Context::Tag {
+                parent: Some("head".to_string())
+            })
+        );
+        assert_eq!(context_at("|"), Some(Context::Tag { parent: None }));
+        assert_eq!(
+            context_at(".card(){ul#menu(){l|}}"),
+            Some(Context::Tag {
+                parent: Some("ul".to_string())
+            })
+        );
+        assert_eq!(
+            context_at(".card(h|"),
+            Some(Context::Attribute {
+                tag: "div".to_string(),
+                existing: vec![]
+            })
+        );
+        assert_eq!(
+            context_at("head(){meta(charset=\"UTF-8\" na|"),
+            Some(Context::Attribute {
+                tag: "meta".to_string(),
+                existing: vec!["charset".to_string()]
+            })
+        );
+        // 文字列, コメント, '='の後では補完しない
+        assert_eq!(context_at("p(){\"me|\"}"), None);
+        assert_eq!(context_at("// me|"), None);
+        assert_eq!(context_at("meta(name=|"), None);
+    }
+}
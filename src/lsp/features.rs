@@ -0,0 +1,203 @@
+use lsp_types::{
+    self as lsp, DiagnosticSeverity, DocumentSymbol, FoldingRange, FoldingRangeKind, SymbolKind,
+    TextEdit,
+};
+
+use crate::{
+    diagnostics::{Diagnostic, Severity},
+    formatter::{self, FormatConfig},
+    jtml_lexer::{lexer, lossless_lexer, JtmlToken},
+    jtml_parser::{
+        cst::{self, SyntaxKind, SyntaxNode},
+        parse_recovering, Element, Node,
+    },
+    linter,
+    span::{LineIndex, Span},
+};
+
+// 構文エラーと警告を全て報告する
+// 構文エラーがあっても読めた部分は警告の対象にする
+pub(super) fn diagnostics(source: &str) -> Vec<lsp::Diagnostic> {
+    let index = LineIndex::new(source);
+    let diagnostics = match lexer(source.to_string()) {
+        Ok(tokens) => {
+            let (ast, errors) = parse_recovering(&tokens);
+            errors
+                .iter()
+                .map(|e| e.to_diagnostic(source))
+                .chain(linter::lint(&ast))
+                .collect()
+        }
+        Err(e) => vec![e.to_diagnostic()],
+    };
+    diagnostics
+        .iter()
+        .map(|diagnostic| to_lsp_diagnostic(diagnostic, &index, source))
+        .collect()
+}
+
+fn to_lsp_diagnostic(diagnostic: &Diagnostic, index: &LineIndex, source: &str) -> lsp::Diagnostic {
+    let mut message = match &diagnostic.label {
+        Some(label) => format!("{}: {}", diagnostic.message, label),
+        None => diagnostic.message.clone(),
+    };
+    if let Some(hint) = &diagnostic.hint {
+        message.push_str(&format!("\nhint: {}", hint));
+    }
+    lsp::Diagnostic {
+        range: range(diagnostic.span, index, source),
+        severity: Some(match diagnostic.severity {
+            Severity::Error => DiagnosticSeverity::ERROR,
+            Severity::Warning => DiagnosticSeverity::WARNING,
+        }),
+        source: Some("jtml".to_string()),
+        message,
+        ..lsp::Diagnostic::default()
+    }
+}
+
+// 要素の木をアウトラインとして返す
+pub(super) fn document_symbols(source: &str) -> Vec<DocumentSymbol> {
+    let Ok(tokens) = lexer(source.to_string()) else {
+        return Vec::new();
+    };
+    let (ast, _) = parse_recovering(&tokens);
+    let index = LineIndex::new(source);
+    symbols(ast.elements.iter(), &index, source)
+}
+
+fn symbols<'a>(
+    nodes: impl Iterator<Item = &'a Node>,
+    index: &LineIndex,
+    source: &str,
+) -> Vec<DocumentSymbol> {
+    // 制御構文は要素として表示せず、中の要素を親の子として並べる
+    nodes
+        .flat_map(|node| match node {
+            Node::Element(element) => vec![symbol(element, index, source)],
+            Node::If(node) => {
+                let children = node.then.iter().chain(node.otherwise.iter().flatten());
+                symbols(children.collect::<Vec<_>>().into_iter(), index, source)
+            }
+            Node::For(node) => symbols(node.children.iter(), index, source),
+            Node::Define(component) => symbols(component.children.iter(), index, source),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+#[allow(deprecated)]
+fn symbol(element: &Element, index: &LineIndex, source: &str) -> DocumentSymbol {
+    let children = symbols(element.children.iter(), index, source);
+    let name_span = Span::new(
+        element.span.start,
+        element.span.start + element.tag_name.len(),
+    );
+    DocumentSymbol {
+        name: element.tag_name.clone(),
+        detail: selector(element),
+        kind: SymbolKind::FIELD,
+        tags: None,
+        deprecated: None,
+        range: range(element.span, index, source),
+        selection_range: range(name_span, index, source),
+        children: if children.is_empty() {
+            None
+        } else {
+            Some(children)
+        },
+    }
+}
+
+// idとclassをCSSのセレクタの形で表す
+fn selector(element: &Element) -> Option<String> {
+    let mut selector = String::new();
+    for attribute in &element.attributes {
+        let Some(value) = &attribute.value else {
+            continue;
+        };
+        match attribute.name.as_str() {
+            "id" => selector.push_str(&format!("#{}", value)),
+            "class" => {
+                for class in value.split_whitespace() {
+                    selector.push_str(&format!(".{}", class));
+                }
+            }
+            _ => (),
+        }
+    }
+    if selector.is_empty() {
+        None
+    } else {
+        Some(selector)
+    }
+}
+
+// 複数行にまたがる本体('{'から'}'まで)を折りたためるようにする
+pub(super) fn folding_ranges(source: &str) -> Vec<FoldingRange> {
+    let Ok(tokens) = lossless_lexer(source) else {
+        return Vec::new();
+    };
+    let index = LineIndex::new(source);
+    let mut ranges = Vec::new();
+    collect_folding_ranges(&cst::parse(&tokens), &index, source, &mut ranges);
+    ranges
+}
+
+fn collect_folding_ranges(
+    node: &SyntaxNode,
+    index: &LineIndex,
+    source: &str,
+    ranges: &mut Vec<FoldingRange>,
+) {
+    if node.kind == SyntaxKind::Body {
+        let tokens = node.tokens();
+        if let (Some(open), Some(close)) = (tokens.first(), tokens.last()) {
+            let start_line = index.position(source, open.span.start).line;
+            let close_line = index.position(source, close.span.start).line;
+            // 閉じ括弧の行は折りたたまずに残す
+            let end_line = if close.token == JtmlToken::RightBracket {
+                close_line.saturating_sub(1)
+            } else {
+                close_line
+            };
+            if end_line > start_line {
+                ranges.push(FoldingRange {
+                    start_line: start_line as u32,
+                    end_line: end_line as u32,
+                    kind: Some(FoldingRangeKind::Region),
+                    ..FoldingRange::default()
+                });
+            }
+        }
+    }
+    for child in node.child_nodes() {
+        collect_folding_ranges(child, index, source, ranges);
+    }
+}
+
+// 文書全体を整形結果で置き換える
+// 構文エラーがある場合はNone、すでに整形済みの場合は空を返す
+pub(super) fn formatting(source: &str, config: &FormatConfig) -> Option<Vec<TextEdit>> {
+    // ファイルは改行で終わるものを正しい形とする
+    let formatted = format!(
+        "{}\n",
+        formatter::format_with_config(source.to_string(), config).ok()?
+    );
+    if formatted == source {
+        return Some(Vec::new());
+    }
+    let index = LineIndex::new(source);
+    Some(vec![TextEdit::new(
+        range(Span::new(0, source.len()), &index, source),
+        formatted,
+    )])
+}
+
+pub(super) fn range(span: Span, index: &LineIndex, source: &str) -> lsp::Range {
+    let position = |offset| {
+        let position = index.utf16_position(source, offset);
+        lsp::Position::new(position.line as u32, position.column as u32)
+    };
+    lsp::Range::new(position(span.start), position(span.end))
+}
@@ -0,0 +1,120 @@
+use std::{
+    collections::VecDeque,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use lsp_server::{Connection, Message, Notification, Request, RequestId};
+use lsp_types::{
+    notification::{DidOpenTextDocument, Exit, Initialized, Notification as LspNotification},
+    request::{Initialize, Request as LspRequest, Shutdown},
+    ClientCapabilities, DidOpenTextDocumentParams, InitializeParams, InitializedParams,
+    TextDocumentItem, Uri,
+};
+
+use super::run;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// テスト用に同じプロセス内でサーバーを動かし、クライアントとして操作する
+pub(super) struct TestClient {
+    connection: Connection,
+    server: Option<JoinHandle<anyhow::Result<()>>>,
+    next_id: i32,
+    // レスポンスを待つ間に届いた通知
+    notifications: VecDeque<Notification>,
+}
+
+pub(super) fn uri(name: &str) -> Uri {
+    format!("untitled:{}", name).parse().unwrap()
+}
+
+impl TestClient {
+    pub(super) fn new() -> Self {
+        let (server, client) = Connection::memory();
+        let handle = thread::spawn(move || run(&server));
+        let mut client = TestClient {
+            connection: client,
+            server: Some(handle),
+            next_id: 0,
+            notifications: VecDeque::new(),
+        };
+        #[allow(deprecated)]
+        client.request::<Initialize>(InitializeParams {
+            capabilities: ClientCapabilities::default(),
+            ..InitializeParams::default()
+        });
+        client.notify::<Initialized>(InitializedParams {});
+        client
+    }
+
+    pub(super) fn open(&self, name: &str, text: &str) {
+        self.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(
+                uri(name),
+                "jtml".to_string(),
+                1,
+                text.to_string(),
+            ),
+        });
+    }
+
+    pub(super) fn notify<N: LspNotification>(&self, params: N::Params) {
+        self.connection
+            .sender
+            .send(Notification::new(N::METHOD.to_string(), params).into())
+            .unwrap();
+    }
+
+    pub(super) fn request<R: LspRequest>(&mut self, params: R::Params) -> R::Result {
+        self.next_id += 1;
+        let id = RequestId::from(self.next_id);
+        self.connection
+            .sender
+            .send(Request::new(id.clone(), R::METHOD.to_string(), params).into())
+            .unwrap();
+        loop {
+            match self.receive() {
+                Message::Response(response) if response.id == id => {
+                    match response.response_result {
+                        Ok(result) => return serde_json::from_value(result).unwrap(),
+                        Err(error) => panic!("{} failed: {:?}", R::METHOD, error),
+                    }
+                }
+                Message::Notification(notification) => self.notifications.push_back(notification),
+                message => panic!("unexpected message: {:?}", message),
+            }
+        }
+    }
+
+    // 指定した種類の次の通知を待つ
+    pub(super) fn notification<N: LspNotification>(&mut self) -> N::Params {
+        loop {
+            let notification = match self.notifications.pop_front() {
+                Some(notification) => notification,
+                None => match self.receive() {
+                    Message::Notification(notification) => notification,
+                    message => panic!("unexpected message: {:?}", message),
+                },
+            };
+            if notification.method == N::METHOD {
+                return serde_json::from_value(notification.params).unwrap();
+            }
+        }
+    }
+
+    // サーバーを終了させ、エラーなく終わったことを確かめる
+    pub(super) fn shutdown(mut self) {
+        self.request::<Shutdown>(());
+        self.notify::<Exit>(());
+        let server = self.server.take().unwrap();
+        server.join().unwrap().unwrap();
+    }
+
+    fn receive(&self) -> Message {
+        self.connection
+            .receiver
+            .recv_timeout(TIMEOUT)
+            .expect("no message from the server")
+    }
+}
@@ -0,0 +1,113 @@
+use lsp_types::{SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokensLegend};
+
+use crate::{
+    highlight::{highlight, HighlightKind},
+    jtml_lexer::lossless_lexer,
+    span::LineIndex,
+};
+
+// 属性値と本文はどちらも文字列として色付けし、本文には修飾子を付けて区別する
+const TEXT_MODIFIER: &str = "text";
+
+pub(super) fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: vec![
+            SemanticTokenType::TYPE,
+            SemanticTokenType::PROPERTY,
+            SemanticTokenType::STRING,
+            SemanticTokenType::COMMENT,
+        ],
+        token_modifiers: vec![SemanticTokenModifier::new(TEXT_MODIFIER)],
+    }
+}
+
+// legendの並びに対応する(種類, 修飾子のビット)
+fn encode(kind: HighlightKind) -> (u32, u32) {
+    match kind {
+        HighlightKind::Tag => (0, 0),
+        HighlightKind::AttributeName => (1, 0),
+        HighlightKind::AttributeValue => (2, 0),
+        HighlightKind::Text => (2, 1),
+        HighlightKind::Comment => (3, 0),
+    }
+}
+
+// 複数行にまたがるトークンは行ごとに分けて送る
+pub(super) fn semantic_tokens(source: &str) -> Vec<SemanticToken> {
+    let Ok(tokens) = lossless_lexer(source) else {
+        return Vec::new();
+    };
+    let index = LineIndex::new(source);
+    let mut result = Vec::new();
+    let (mut previous_line, mut previous_start) = (0, 0);
+    for highlight in highlight(&tokens) {
+        let (token_type, token_modifiers_bitset) = encode(highlight.kind);
+        let start = index.position(source, highlight.span.start).line;
+        let end = index.position(source, highlight.span.end).line;
+        for line in start..=end {
+            let line_range = index.line_range(source, line);
+            let from = highlight.span.start.max(line_range.start);
+            let to = highlight.span.end.min(line_range.end);
+            let text = &source[from..to];
+            if text.is_empty() {
+                continue;
+            }
+            let column = index.utf16_position(source, from).column as u32;
+            let line = line as u32;
+            result.push(SemanticToken {
+                delta_line: line - previous_line,
+                delta_start: if line == previous_line {
+                    column - previous_start
+                } else {
+                    column
+                },
+                length: text.encode_utf16().count() as u32,
+                token_type,
+                token_modifiers_bitset,
+            });
+            (previous_line, previous_start) = (line, column);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use lsp_types::SemanticToken;
+
+    use super::semantic_tokens;
+
+    #[test]
+    fn relative_positions() {
+        let tokens = semantic_tokens("p(id=\"あ\"){\"a\n  b\"}")
+            .into_iter()
+            .map(
+                |SemanticToken {
+                     delta_line,
+                     delta_start,
+                     length,
+                     token_type,
+                     token_modifiers_bitset,
+                 }| {
+                    (
+                        delta_line,
+                        delta_start,
+                        length,
+                        token_type,
+                        token_modifiers_bitset,
+                    )
+                },
+            )
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                (0, 0, 1, 0, 0),
+                (0, 2, 2, 1, 0),
+                (0, 3, 3, 2, 0),
+                (0, 5, 2, 2, 1),
+                (1, 0, 4, 2, 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use std::ops::Range;
+
+// ソースコード上の範囲を表す(バイトオフセットの半開区間)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    // self の開始位置から other の終了位置までを覆うSpanを返す
+    pub fn to(&self, other: Span) -> Span {
+        Span::new(self.start, other.end)
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Span::new(range.start, range.end)
+    }
+}
+
+// 0始まりの行番号と列番号(文字単位)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+// バイトオフセットから行・列を求めるための索引
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(source: &str) -> Self {
+        let mut line_starts = vec![0];
+        for (i, c) in source.char_indices() {
+            if c == '\n' {
+                line_starts.push(i + 1);
+            }
+        }
+        LineIndex { line_starts }
+    }
+
+    pub fn position(&self, source: &str, offset: usize) -> Position {
+        let offset = offset.min(source.len());
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        let line_start = self.line_starts[line];
+        Position {
+            line,
+            column: source[line_start..offset].chars().count(),
+        }
+    }
+
+    // LSPと同じく、列をUTF-16のコード単位で数える
+    pub fn utf16_position(&self, source: &str, offset: usize) -> Position {
+        let position = self.position(source, offset);
+        let line_start = self.line_starts[position.line];
+        Position {
+            line: position.line,
+            column: source[line_start..offset.min(source.len())]
+                .encode_utf16()
+                .count(),
+        }
+    }
+
+    // utf16_positionの逆。範囲外の位置は行末や文末にする
+    pub fn utf16_offset(&self, source: &str, position: Position) -> usize {
+        if position.line >= self.line_count() {
+            return source.len();
+        }
+        let line = self.line_range(source, position.line);
+        let mut column = 0;
+        for (i, c) in source[line.clone()].char_indices() {
+            if column >= position.column {
+                return line.start + i;
+            }
+            column += c.len_utf16();
+        }
+        line.end
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    // 改行文字を含まない行の範囲
+    pub fn line_range(&self, source: &str, line: usize) -> Range<usize> {
+        let start = self.line_starts[line];
+        let end = match self.line_starts.get(line + 1) {
+            Some(next) => next - 1,
+            None => source.len(),
+        };
+        let end = if end > start && source.as_bytes()[end - 1] == b'\r' {
+            end - 1
+        } else {
+            end
+        };
+        start..end
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LineIndex, Position, Span};
+
+    #[test]
+    fn span_to() {
+        let span = Span::new(2, 4).to(Span::new(6, 9));
+        assert_eq!((span.start, span.end), (2, 9));
+        assert_eq!(span.len(), 7);
+    }
+
+    #[test]
+    fn position() {
+        let source = "p(){\n  \"あい\"\n}";
+        let index = LineIndex::new(source);
+        assert_eq!(index.line_count(), 3);
+        assert_eq!(index.position(source, 0), Position { line: 0, column: 0 });
+        assert_eq!(index.position(source, 3), Position { line: 0, column: 3 });
+        assert_eq!(index.position(source, 5), Position { line: 1, column: 0 });
+        // マルチバイト文字は1文字として数える
+        assert_eq!(
+            index.position(source, source.find('}').unwrap()),
+            Position { line: 2, column: 0 }
+        );
+        assert_eq!(
+            index.position(source, source.find('い').unwrap()),
+            Position { line: 1, column: 4 }
+        );
+    }
+
+    #[test]
+    fn utf16_position() {
+        let source = "\"🎉あ\"\np()";
+        let index = LineIndex::new(source);
+        // サロゲートペアは2つとして数える
+        assert_eq!(
+            index.utf16_position(source, source.find('あ').unwrap()),
+            Position { line: 0, column: 3 }
+        );
+        assert_eq!(
+            index.utf16_position(source, source.find('p').unwrap()),
+            Position { line: 1, column: 0 }
+        );
+        assert_eq!(
+            index.utf16_offset(source, Position { line: 0, column: 3 }),
+            source.find('あ').unwrap()
+        );
+        assert_eq!(
+            index.utf16_offset(source, Position { line: 1, column: 9 }),
+            source.len()
+        );
+    }
+
+    #[test]
+    fn line_range() {
+        let source = "a\r\nbc\n";
+        let index = LineIndex::new(source);
+        assert_eq!(&source[index.line_range(source, 0)], "a");
+        assert_eq!(&source[index.line_range(source, 1)], "bc");
+        assert_eq!(&source[index.line_range(source, 2)], "");
+    }
+}
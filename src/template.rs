@@ -0,0 +1,310 @@
+mod context;
+mod errors;
+mod expression;
+mod interpolate;
+
+use std::collections::{HashSet, VecDeque};
+
+pub use context::Context;
+pub use errors::{ContextError, Location, TemplateError};
+
+use serde_json::Value;
+
+use crate::{
+    html_converter::is_raw_text_element,
+    jtml_parser::{AstRoot, Attribute, Element, Node, FOR_TAG, IF_TAG},
+    span::Span,
+};
+
+use context::Scope;
+use expression::{condition, is_name, kind, lookup};
+use interpolate::interpolate;
+
+// 構文解析の後、HTMLに変換する前に、テンプレートの記法をcontextの値で展開する
+// 全てのエラーを集めてから返す
+pub fn render(ast: &AstRoot, context: &Context) -> Result<AstRoot, Vec<TemplateError>> {
+    run(ast, &Scope::Root(context))
+}
+
+// contextを渡さずに変換する場合、制御構文は評価できないためエラーにする
+// `{{ }}`は置き換えず、書いたとおりに出力する
+pub fn render_without_context(ast: &AstRoot) -> Result<AstRoot, Vec<TemplateError>> {
+    run(ast, &Scope::Empty)
+}
+
+fn run(ast: &AstRoot, scope: &Scope) -> Result<AstRoot, Vec<TemplateError>> {
+    let mut errors = Vec::new();
+    let mut elements = VecDeque::new();
+    render_nodes(&ast.elements, scope, false, &mut errors, &mut elements);
+    if !errors.is_empty() {
+        // forの中のエラーは繰り返しごとに起きるため、同じ位置のものは一つにまとめる
+        let mut seen = HashSet::new();
+        errors.retain(|error| {
+            let position = error
+                .location()
+                .map(|location| (location.literal.start, location.start));
+            seen.insert((position, error.to_string()))
+        });
+        return Err(errors);
+    }
+    Ok(AstRoot { elements })
+}
+
+// 属性値全体を指す位置
+fn attribute_location(value: &str, span: Span) -> Location {
+    Location {
+        literal: span,
+        start: 0,
+        end: value.len(),
+    }
+}
+
+// raw_textはscript, styleの中かどうか
+// その中の文字列はHTMLのエスケープが効かず、値でスクリプトを書き換えられるため置き換えない
+fn render_nodes(
+    nodes: &VecDeque<Node>,
+    scope: &Scope,
+    raw_text: bool,
+    errors: &mut Vec<TemplateError>,
+    output: &mut VecDeque<Node>,
+) {
+    for node in nodes {
+        match node {
+            Node::Element(element) => {
+                let attributes = element
+                    .attributes
+                    .iter()
+                    .map(|attribute| Attribute {
+                        value: attribute.value.as_ref().map(|value| match scope {
+                            Scope::Empty => value.clone(),
+                            _ => interpolate(value, attribute.span, scope, errors),
+                        }),
+                        ..attribute.clone()
+                    })
+                    .collect();
+                let mut children = VecDeque::new();
+                render_nodes(
+                    &element.children,
+                    scope,
+                    raw_text || is_raw_text_element(&element.tag_name),
+                    errors,
+                    &mut children,
+                );
+                output.push_back(Node::Element(Element {
+                    tag_name: element.tag_name.clone(),
+                    attributes,
+                    children,
+                    span: element.span,
+                }));
+            }
+            Node::Text(text, span) if !raw_text && !matches!(scope, Scope::Empty) => {
+                output.push_back(Node::Text(interpolate(text, *span, scope, errors), *span))
+            }
+            // コメントと生のHTMLは書いたとおりに出力する
+            Node::Text(_, _) | Node::Comment(_, _) | Node::Raw(_, _) => {
+                output.push_back(node.clone())
+            }
+            // 部品の定義はtransformerで展開済み
+            Node::Define(_) => (),
+            Node::If(node) if matches!(scope, Scope::Empty) => errors.push(
+                TemplateError::MissingContext(IF_TAG.to_string(), node.condition_span),
+            ),
+            Node::For(node) if matches!(scope, Scope::Empty) => errors.push(
+                TemplateError::MissingContext(FOR_TAG.to_string(), node.items_span),
+            ),
+            Node::If(node) => {
+                let location = attribute_location(&node.condition, node.condition_span);
+                // ifとelseの間のコメントは、どちらを出力しても書いた位置に残す
+                if condition(&node.condition, &location, scope, errors) {
+                    render_nodes(&node.then, scope, raw_text, errors, output);
+                    output.extend(node.between.iter().cloned());
+                } else if let Some(otherwise) = &node.otherwise {
+                    output.extend(node.between.iter().cloned());
+                    render_nodes(otherwise, scope, raw_text, errors, output);
+                }
+            }
+            Node::For(node) => {
+                // 繰り返しの変数は`.`を含まない名前に限る
+                if !is_name(&node.item) || node.item.contains('.') {
+                    errors.push(TemplateError::InvalidExpression(
+                        node.item.clone(),
+                        attribute_location(&node.item, node.item_span),
+                    ));
+                    continue;
+                }
+                let location = attribute_location(&node.items, node.items_span);
+                match lookup(node.items.trim(), &location, scope, errors) {
+                    Some(Value::Array(items)) => {
+                        for item in items {
+                            let scope = Scope::Binding {
+                                parent: scope,
+                                name: &node.item,
+                                value: item,
+                            };
+                            render_nodes(&node.children, &scope, raw_text, errors, output);
+                        }
+                    }
+                    Some(value) => errors.push(TemplateError::NotIterable(
+                        node.items.trim().to_string(),
+                        kind(value),
+                        location,
+                    )),
+                    None => (),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        html_converter::HtmlOptions,
+        jtml_lexer::test_utils::lexer,
+        jtml_parser::{parse_recovering, Node},
+    };
+
+    use super::{render, render_without_context, Context};
+
+    #[test]
+    fn render_text_and_attributes() {
+        let (ast, _) = parse_recovering(&lexer(
+            r#"a(href="/{{ path }}"){"{{ name }}"} // {{ name }}"#,
+        ));
+        let mut context = Context::new();
+        context.insert("path", "a&b");
+        context.insert("name", "<jtml>");
+        assert_eq!(
+            render(&ast, &context)
+                .unwrap()
+                .to_html(&HtmlOptions::default()),
+            r#"<a href="/a&amp;b">&lt;jtml&gt;</a><!--{{ name }}-->"#
+        );
+    }
+
+    #[test]
+    fn control_flow() {
+        let source = r#"
+ul(){
+    for(item="product" in="products"){
+        li(){
+            "{{ product.name }}"
+            if(cond="product.sale"){" (sale)"}
+            // price
+            else(){" - {{ product.price }}"}
+        }
+    }
+}
+if(cond="!products"){p(){"empty"}}"#;
+        let (ast, _) = parse_recovering(&lexer(source));
+        let context = Context::from_json(
+            r#"{"products": [{"name": "a", "sale": true}, {"name": "b", "sale": false, "price": 3}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            render(&ast, &context)
+                .unwrap()
+                .to_html(&HtmlOptions::default()),
+            "<ul><li>a (sale)<!--price--></li><li>b<!--price--> - 3</li></ul>"
+        );
+    }
+
+    #[test]
+    fn large_loop() {
+        // 繰り返しごとにcontextを複製すると要素数の二乗の時間がかかる
+        let source = r#"ul(){ for(item="n" in="items"){ for(item="m" in="pair"){ li(){"{{ n }}{{ m }}"} } } }"#;
+        let (ast, _) = parse_recovering(&lexer(source));
+        let mut context = Context::new();
+        context.insert("items", (0..20_000).collect::<Vec<_>>());
+        context.insert("pair", vec!["a", "b"]);
+        let rendered = render(&ast, &context).unwrap();
+        let Node::Element(ul) = &rendered.elements[0] else {
+            panic!("element expected");
+        };
+        assert_eq!(ul.children.len(), 40_000);
+        let html = rendered.to_html(&HtmlOptions::default());
+        assert!(html.starts_with("<ul><li>0a</li><li>0b</li><li>1a</li>"));
+        assert!(html.ends_with("<li>19999b</li></ul>"));
+    }
+
+    #[test]
+    fn control_flow_errors() {
+        let source = r#"for(item="x" in="items"){"{{ y }}"} for(item="x" in="title"){}"#;
+        let (ast, _) = parse_recovering(&lexer(source));
+        let context = Context::from_json(r#"{"items": [1, 2, 3], "title": "t"}"#).unwrap();
+        let errors = render(&ast, &context).unwrap_err();
+        // 繰り返しごとに起きる同じエラーは一つにまとめる
+        assert_eq!(
+            errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            vec![
+                "undefined variable `y`",
+                "`title` is a string and cannot be iterated"
+            ]
+        );
+        let span = errors[1].span(source);
+        assert_eq!(&source[span.range()], "title");
+    }
+
+    #[test]
+    fn without_context() {
+        // `{{ }}`は書いたとおりに出力し、制御構文だけをエラーにする
+        let source = r#"p(title="{{ a }}"){"{{{{ b }}"} if(cond="c"){} for(item="x" in="d"){}"#;
+        let (ast, _) = parse_recovering(&lexer(source));
+        let errors = render_without_context(&ast).unwrap_err();
+        assert_eq!(
+            errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            vec![
+                "`if` requires a data context",
+                "`for` requires a data context"
+            ]
+        );
+
+        let (ast, _) = parse_recovering(&lexer(r#"p(title="{{ a }}"){"{{{{ b }}"}"#));
+        assert_eq!(
+            render_without_context(&ast)
+                .unwrap()
+                .to_html(&HtmlOptions::default()),
+            r#"<p title="{{ a }}">{{{{ b }}</p>"#
+        );
+    }
+
+    #[test]
+    fn raw_text() {
+        // script, styleの中は値を埋め込まない。埋め込むとエスケープされずにスクリプトになる
+        let source = r#"script(src="/{{ path }}"){"var a = \"{{ y }}\"; if(a){{b}}"} style(){"a{{color:red}}"} p(){"{{ y }}"}"#;
+        let (ast, _) = parse_recovering(&lexer(source));
+        let context = Context::from_json(r#"{"y": "\";alert(1);//", "path": "a.js"}"#).unwrap();
+        assert_eq!(
+            render(&ast, &context)
+                .unwrap()
+                .to_html(&HtmlOptions::default()),
+            r#"<script src="/a.js">var a = "{{ y }}"; if(a){{b}}</script><style>a{{color:red}}</style><p>";alert(1);//</p>"#
+        );
+    }
+
+    #[test]
+    fn undefined_variable() {
+        let source = "p(title=\"{{ a }}\"){\n    \"\\u{3042}{{ b }}\"\n}";
+        let (ast, _) = parse_recovering(&lexer(source));
+        let errors = render(&ast, &Context::new()).unwrap_err();
+        let spans = errors
+            .iter()
+            .map(|error| {
+                let span = error.to_diagnostic(source).span;
+                &source[span.range()]
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(spans, vec!["{{ a }}", "{{ b }}"]);
+        assert_eq!(
+            errors[1].to_diagnostic(source).render("index.jtml", source),
+            r#"error: undefined variable
+ --> index.jtml:2:14
+  |
+2 |     "\u{3042}{{ b }}"
+  |              ^^^^^^^ `b` is not in the data context
+  |
+  = hint: pass the value with `--data` or `convert_with_context`
+"#
+        );
+    }
+}
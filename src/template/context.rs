@@ -0,0 +1,152 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde_json::{Map, Value};
+
+use super::ContextError;
+
+// `{{ name }}`で参照する値の集まり。JSON, TOMLのファイルかHashMapから作る
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Context {
+    values: Map<String, Value>,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Context::default()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ContextError> {
+        match serde_json::from_str(json).map_err(ContextError::Json)? {
+            Value::Object(values) => Ok(Context { values }),
+            _ => Err(ContextError::NotAnObject),
+        }
+    }
+
+    pub fn from_toml(toml: &str) -> Result<Self, ContextError> {
+        let table: toml::Table = toml::from_str(toml).map_err(ContextError::Toml)?;
+        match serde_json::to_value(table).map_err(ContextError::Json)? {
+            Value::Object(values) => Ok(Context { values }),
+            _ => Err(ContextError::NotAnObject),
+        }
+    }
+
+    // 拡張子が.tomlの場合はTOML、それ以外はJSONとして読む
+    pub fn load(path: &Path) -> Result<Self, ContextError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| ContextError::Io(path.display().to_string(), e))?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Context::from_toml(&text),
+            _ => Context::from_json(&text),
+        }
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<Value>) {
+        self.values.insert(name.into(), value.into());
+    }
+
+    // `site.title`のように`.`で区切ってオブジェクトの中をたどる
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        let mut keys = name.split('.');
+        walk(self.values.get(keys.next()?)?, keys)
+    }
+}
+
+fn walk<'a, 'k>(mut value: &'a Value, keys: impl Iterator<Item = &'k str>) -> Option<&'a Value> {
+    for key in keys {
+        value = value.as_object()?.get(key)?;
+    }
+    Some(value)
+}
+
+// forの変数をcontextに重ねたもの
+// 繰り返しごとにcontextを複製しないよう、外側の値は参照でたどる
+pub(super) enum Scope<'a> {
+    // contextなしで変換する場合。`{{ }}`は置き換えない
+    Empty,
+    Root(&'a Context),
+    Binding {
+        parent: &'a Scope<'a>,
+        name: &'a str,
+        value: &'a Value,
+    },
+}
+
+impl<'a> Scope<'a> {
+    // 内側の変数が同じ名前の外側の値を隠す
+    pub(super) fn get(&self, name: &str) -> Option<&'a Value> {
+        match self {
+            Scope::Empty => None,
+            Scope::Root(context) => context.get(name),
+            Scope::Binding {
+                parent,
+                name: bound,
+                value,
+            } => {
+                let mut keys = name.split('.');
+                match keys.next()? == *bound {
+                    true => walk(value, keys),
+                    false => parent.get(name),
+                }
+            }
+        }
+    }
+}
+
+impl<K: Into<String>, V: Into<Value>> From<HashMap<K, V>> for Context {
+    fn from(map: HashMap<K, V>) -> Self {
+        let mut context = Context::new();
+        for (name, value) in map {
+            context.insert(name, value);
+        }
+        context
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::{Context, Scope};
+
+    #[test]
+    fn get() {
+        let context = Context::from_json(r#"{"site": {"title": "jtml", "version": 2}}"#).unwrap();
+        assert_eq!(context.get("site.title"), Some(&json!("jtml")));
+        assert_eq!(context.get("site.version"), Some(&json!(2)));
+        assert_eq!(context.get("site.title.length"), None);
+        assert_eq!(context.get("page"), None);
+
+        let context = Context::from_toml("[site]\ntitle = \"jtml\"").unwrap();
+        assert_eq!(context.get("site.title"), Some(&json!("jtml")));
+
+        let context = Context::from(HashMap::from([("title", "jtml")]));
+        assert_eq!(context.get("title"), Some(&json!("jtml")));
+
+        assert!(Context::from_json("[1, 2]").is_err());
+    }
+
+    #[test]
+    fn scope() {
+        let context = Context::from_json(r#"{"item": 1, "site": {"title": "jtml"}}"#).unwrap();
+        let root = Scope::Root(&context);
+        let value = json!({"name": "a"});
+        let outer = Scope::Binding {
+            parent: &root,
+            name: "item",
+            value: &value,
+        };
+        let inner = Scope::Binding {
+            parent: &outer,
+            name: "n",
+            value: &json!(2),
+        };
+        assert_eq!(inner.get("item.name"), Some(&json!("a")));
+        assert_eq!(inner.get("item"), Some(&value));
+        assert_eq!(inner.get("n"), Some(&json!(2)));
+        assert_eq!(inner.get("site.title"), Some(&json!("jtml")));
+        assert_eq!(inner.get("items"), None);
+        assert_eq!(root.get("item"), Some(&json!(1)));
+    }
+}
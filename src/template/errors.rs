@@ -0,0 +1,200 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io,
+};
+
+use crate::{
+    diagnostics::Diagnostic, jtml_lexer::literal_offset, span::Span, transformer::Sources,
+};
+
+// 文字列リテラルを展開した値の中の位置
+// 元のソースを受け取るまで正確な位置は分からないため、リテラルの位置と組にして持つ
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    // 文字列リテラル、または`name="value"`の属性全体
+    pub literal: Span,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Location {
+    pub fn span(&self, source: &str) -> Span {
+        let Some(text) = source.get(self.literal.range()) else {
+            return self.literal;
+        };
+        let Some(quote) = text.find('"') else {
+            return self.literal;
+        };
+        let literal = &text[quote..];
+        let start = self.literal.start + quote;
+        Span::new(
+            start + literal_offset(literal, self.start),
+            start + literal_offset(literal, self.end),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateError {
+    UndefinedVariable(String, Location),
+    // `{{`に対応する`}}`がない
+    UnclosedInterpolation(Location),
+    // `{{ }}`の中が変数名として読めない
+    InvalidExpression(String, Location),
+    // 配列やオブジェクトは文字列にできない
+    NotPrintable(String, &'static str, Location),
+    // forで繰り返せるのは配列のみ
+    NotIterable(String, &'static str, Location),
+    // contextなしで変換した制御構文の名前と位置
+    MissingContext(String, Span),
+    // include, importで読み込んだファイル, `行:列: エラー`(1始まり), 変換するファイルのincludeの位置
+    InIncludedFile(String, String, Span),
+}
+
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TemplateError::UndefinedVariable(name, _) => {
+                write!(f, "undefined variable `{}`", name)
+            }
+            TemplateError::UnclosedInterpolation(_) => write!(f, "unclosed `{{{{`"),
+            TemplateError::InvalidExpression(text, _) => {
+                write!(f, "invalid expression `{}`", text)
+            }
+            TemplateError::NotPrintable(name, kind, _) => {
+                write!(f, "`{}` is {} and cannot be printed", name, kind)
+            }
+            TemplateError::NotIterable(name, kind, _) => {
+                write!(f, "`{}` is {} and cannot be iterated", name, kind)
+            }
+            TemplateError::MissingContext(keyword, _) => {
+                write!(f, "`{}` requires a data context", keyword)
+            }
+            TemplateError::InIncludedFile(path, message, _) => {
+                write!(f, "error in {}:{}", path, message)
+            }
+        }
+    }
+}
+
+impl TemplateError {
+    // 文字列リテラルの中を指すエラーの位置
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            TemplateError::UndefinedVariable(_, location) => Some(location),
+            TemplateError::UnclosedInterpolation(location) => Some(location),
+            TemplateError::InvalidExpression(_, location) => Some(location),
+            TemplateError::NotPrintable(_, _, location) => Some(location),
+            TemplateError::NotIterable(_, _, location) => Some(location),
+            TemplateError::MissingContext(_, _) | TemplateError::InIncludedFile(_, _, _) => None,
+        }
+    }
+
+    pub fn span(&self, source: &str) -> Span {
+        match self {
+            TemplateError::MissingContext(_, span) | TemplateError::InIncludedFile(_, _, span) => {
+                *span
+            }
+            _ => self
+                .location()
+                .map_or(Span::default(), |location| location.span(source)),
+        }
+    }
+
+    // 読み込んだファイルの中のエラーを、そのファイルの中の位置と共に変換するファイルのincludeを指すエラーにする
+    pub(crate) fn in_included(self, sources: &Sources) -> TemplateError {
+        let span = match (&self, self.location()) {
+            (TemplateError::MissingContext(_, span), _) => *span,
+            (_, Some(location)) => location.literal,
+            _ => return self,
+        };
+        let Some(file) = sources.locate(span) else {
+            return self;
+        };
+        let local = match self.location() {
+            Some(location) => Location {
+                literal: file.local(location.literal),
+                ..location.clone()
+            }
+            .span(&file.text),
+            None => file.local(span),
+        };
+        TemplateError::InIncludedFile(
+            file.path.display().to_string(),
+            format!("{}: {}", file.position(local.start), self),
+            file.site,
+        )
+    }
+
+    // sourceはエラーが発生したjtmlの全文
+    pub fn to_diagnostic(&self, source: &str) -> Diagnostic {
+        let span = self.span(source);
+        match self {
+            TemplateError::UndefinedVariable(name, _) => {
+                Diagnostic::error("undefined variable", span)
+                    .with_label(format!("`{}` is not in the data context", name))
+                    .with_hint("pass the value with `--data` or `convert_with_context`")
+            }
+            TemplateError::UnclosedInterpolation(_) => {
+                Diagnostic::error("unclosed interpolation", span)
+                    .with_label("expected `}}`")
+                    .with_hint(
+                        "variables are written as `{{ name }}`; write `{{{{` for a literal `{{`",
+                    )
+            }
+            TemplateError::InvalidExpression(text, _) => Diagnostic::error(
+                "invalid expression",
+                span,
+            )
+            .with_label(format!("`{}` is not a variable name", text))
+            .with_hint(
+                "names are letters, digits, `_` and `-`, joined by `.` such as `site.title`",
+            ),
+            TemplateError::NotPrintable(name, kind, _) => {
+                Diagnostic::error("value cannot be printed", span)
+                    .with_label(format!("`{}` is {}", name, kind))
+                    .with_hint("only strings, numbers, booleans and null can be written into text")
+            }
+            TemplateError::NotIterable(name, kind, _) => {
+                Diagnostic::error("value cannot be iterated", span)
+                    .with_label(format!("`{}` is {}", name, kind))
+                    .with_hint("`for` repeats its body for each element of an array")
+            }
+            TemplateError::MissingContext(keyword, _) => {
+                Diagnostic::error("missing data context", span)
+                    .with_label(format!("`{}` is evaluated against data", keyword))
+                    .with_hint("pass the data with `--data` or `convert_with_context`")
+            }
+            TemplateError::InIncludedFile(path, message, _) => {
+                Diagnostic::error("error in included file", span)
+                    .with_label(format!("{}:{}", path, message))
+            }
+        }
+    }
+}
+
+impl Error for TemplateError {}
+
+// データファイルを読めなかった場合のエラー
+#[derive(Debug)]
+pub enum ContextError {
+    Io(String, io::Error),
+    Json(serde_json::Error),
+    Toml(toml::de::Error),
+    // 最上位がオブジェクトでない
+    NotAnObject,
+}
+
+impl Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContextError::Io(path, e) => write!(f, "cannot read {}: {}", path, e),
+            ContextError::Json(e) => write!(f, "invalid json: {}", e),
+            ContextError::Toml(e) => write!(f, "invalid toml: {}", e),
+            ContextError::NotAnObject => write!(f, "data must be an object of named values"),
+        }
+    }
+}
+
+impl Error for ContextError {}
@@ -0,0 +1,131 @@
+use serde_json::Value;
+
+use super::{context::Scope, Location, TemplateError};
+
+// `site.title`のように`.`で区切った変数名
+pub(super) fn is_name(name: &str) -> bool {
+    name.split('.').all(|key| {
+        !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    })
+}
+
+// エラーメッセージに使う値の種類
+pub(super) fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "a string",
+        Value::Number(_) => "a number",
+        Value::Bool(_) => "a boolean",
+        Value::Null => "null",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+// 文字列にできない場合はNone
+pub(super) fn print(value: &Value) -> Option<String> {
+    match value {
+        Value::String(string) => Some(string.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(bool) => Some(bool.to_string()),
+        Value::Null => Some(String::new()),
+        Value::Array(_) | Value::Object(_) => None,
+    }
+}
+
+// false, null, 0, 空の文字列・配列・オブジェクトは偽
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Bool(bool) => *bool,
+        Value::Null => false,
+        Value::Number(number) => number.as_f64() != Some(0.0),
+        Value::String(string) => !string.is_empty(),
+        Value::Array(array) => !array.is_empty(),
+        Value::Object(object) => !object.is_empty(),
+    }
+}
+
+// 変数を読む。読めない場合はエラーを追加してNoneを返す
+pub(super) fn lookup<'c>(
+    name: &str,
+    location: &Location,
+    scope: &Scope<'c>,
+    errors: &mut Vec<TemplateError>,
+) -> Option<&'c Value> {
+    if !is_name(name) {
+        errors.push(TemplateError::InvalidExpression(
+            name.to_string(),
+            location.clone(),
+        ));
+        return None;
+    }
+    let value = scope.get(name);
+    if value.is_none() {
+        errors.push(TemplateError::UndefinedVariable(
+            name.to_string(),
+            location.clone(),
+        ));
+    }
+    value
+}
+
+// ifの条件。変数名か、`!`で否定した変数名
+pub(super) fn condition(
+    expression: &str,
+    location: &Location,
+    scope: &Scope,
+    errors: &mut Vec<TemplateError>,
+) -> bool {
+    let expression = expression.trim();
+    let (negated, name) = match expression.strip_prefix('!') {
+        Some(name) => (true, name.trim_start()),
+        None => (false, expression),
+    };
+    match lookup(name, location, scope, errors) {
+        Some(value) => truthy(value) != negated,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        span::Span,
+        template::{context::Scope, Context, Location},
+    };
+
+    use super::condition;
+
+    #[test]
+    fn conditions() {
+        let context = Context::from_json(
+            r#"{"yes": true, "zero": 0, "empty": "", "items": [1], "user": {"name": "a"}}"#,
+        )
+        .unwrap();
+        let location = Location {
+            literal: Span::default(),
+            start: 0,
+            end: 0,
+        };
+        let mut errors = Vec::new();
+        let mut check =
+            |expression| condition(expression, &location, &Scope::Root(&context), &mut errors);
+        assert!(check("yes"));
+        assert!(!check("zero"));
+        assert!(check("! empty"));
+        assert!(check("items"));
+        assert!(check(" user.name "));
+        assert!(!check("!user"));
+        assert!(!check("missing"));
+        assert!(!check("a == b"));
+        assert_eq!(
+            errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            vec![
+                "undefined variable `missing`",
+                "invalid expression `a == b`"
+            ]
+        );
+    }
+}
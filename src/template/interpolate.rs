@@ -0,0 +1,120 @@
+use crate::span::Span;
+
+use super::{
+    context::Scope,
+    expression::{kind, lookup, print},
+    Location, TemplateError,
+};
+
+// 文字列中の`{{ name }}`を値に、`{{{{`を`{{`に置き換える
+// エスケープはHTMLに出力する際に行うため、ここでは値をそのまま埋め込む
+// literalは値を書いた文字列リテラル(または属性)の位置
+pub(super) fn interpolate(
+    text: &str,
+    literal: Span,
+    scope: &Scope,
+    errors: &mut Vec<TemplateError>,
+) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = 0;
+    while let Some(open) = text[rest..].find("{{").map(|i| rest + i) {
+        result.push_str(&text[rest..open]);
+        if text[open..].starts_with("{{{{") {
+            result.push_str("{{");
+            rest = open + 4;
+            continue;
+        }
+        let Some(close) = text[open..].find("}}").map(|i| open + i + 2) else {
+            errors.push(TemplateError::UnclosedInterpolation(Location {
+                literal,
+                start: open,
+                end: open + 2,
+            }));
+            return result;
+        };
+        let location = Location {
+            literal,
+            start: open,
+            end: close,
+        };
+        let name = text[open + 2..close - 2].trim();
+        if let Some(value) = lookup(name, &location, scope, errors) {
+            match print(value) {
+                Some(value) => result.push_str(&value),
+                None => errors.push(TemplateError::NotPrintable(
+                    name.to_string(),
+                    kind(value),
+                    location,
+                )),
+            }
+        }
+        rest = close;
+    }
+    result.push_str(&text[rest..]);
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::{
+        span::Span,
+        template::{context::Scope, Context},
+    };
+
+    use super::{interpolate, TemplateError};
+
+    #[test]
+    fn values() {
+        let mut context = Context::new();
+        context.insert("title", "a < b");
+        context.insert("version", 2);
+        context.insert("site", json!({"draft": false, "note": null}));
+        let mut errors = Vec::new();
+        assert_eq!(
+            interpolate(
+                "{{title}} v{{ version }} {{ site.draft }}{{site.note}} }}",
+                Span::default(),
+                &Scope::Root(&context),
+                &mut errors
+            ),
+            "a < b v2 false }}"
+        );
+        assert_eq!(
+            interpolate(
+                "{{{{ title }} {{{{{{title}} {{{{{{{{",
+                Span::default(),
+                &Scope::Root(&context),
+                &mut errors
+            ),
+            "{{ title }} {{a < b {{{{"
+        );
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        let mut context = Context::new();
+        context.insert("items", json!([1, 2]));
+        let mut errors = Vec::new();
+        let result = interpolate(
+            "a{{ page }}b{{ items }}c{{ 1 + 2 }}d{{ e",
+            Span::default(),
+            &Scope::Root(&context),
+            &mut errors,
+        );
+        assert_eq!(result, "abcd");
+        assert!(matches!(
+            &errors[..],
+            [
+                TemplateError::UndefinedVariable(page, _),
+                TemplateError::NotPrintable(items, "an array", _),
+                TemplateError::InvalidExpression(expression, _),
+                TemplateError::UnclosedInterpolation(_),
+            ] if page == "page" && items == "items" && expression == "1 + 2"
+        ));
+        let location = errors[0].location().unwrap();
+        assert_eq!((location.start, location.end), (1, 11));
+    }
+}
@@ -0,0 +1,65 @@
+mod components;
+mod errors;
+mod includes;
+mod layouts;
+mod resolver;
+mod sources;
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+pub use errors::TransformError;
+pub use resolver::{normalize, FileResolver, MemoryResolver, Resolver};
+pub(crate) use sources::Sources;
+
+use crate::jtml_parser::AstRoot;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transformed {
+    pub ast: AstRoot,
+    // include, importで読み込んだファイル(変換したファイル自身は含まない)
+    pub dependencies: Vec<PathBuf>,
+    // 読み込んだファイルの中身。astの中の読み込んだノードのエラーの位置を求めるのに使う
+    pub(crate) sources: Sources,
+}
+
+// 構文解析の後、ASTをHTMLに変換できる形に書き換える
+// 部品(define)の呼び出しを展開する。ファイルを読み込むinclude, importはエラーになる
+pub fn transform(ast: &AstRoot) -> Result<AstRoot, Vec<TransformError>> {
+    run(ast, None).map(|transformed| transformed.ast)
+}
+
+// pathのファイルのASTとして、include, import, extendsをresolverで読み込んでから部品を展開する
+pub fn transform_file(
+    ast: &AstRoot,
+    path: &Path,
+    resolver: &dyn Resolver,
+) -> Result<Transformed, Vec<TransformError>> {
+    run(ast, Some((path, resolver)))
+}
+
+fn run(
+    ast: &AstRoot,
+    file: Option<(&Path, &dyn Resolver)>,
+) -> Result<Transformed, Vec<TransformError>> {
+    let mut errors = Vec::new();
+    let (ast, dependencies, sources) = includes::resolve(ast, file, &mut errors);
+    let ast = components::expand(&ast, &mut errors);
+    if !errors.is_empty() {
+        let mut errors = errors
+            .into_iter()
+            .map(|error| error.in_included(&sources))
+            .collect::<Vec<_>>();
+        // 部品の中のエラーは呼び出しごとに起きるため、同じ位置のものは一つにまとめる
+        let mut seen = HashSet::new();
+        errors.retain(|error| seen.insert((error.span().start, error.to_string())));
+        return Err(errors);
+    }
+    Ok(Transformed {
+        ast,
+        dependencies,
+        sources,
+    })
+}
@@ -0,0 +1,304 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    jtml_parser::{AstRoot, Attribute, Component, Element, For, If, Node, SLOT_TAG},
+    span::Span,
+};
+
+use super::TransformError;
+
+// ドキュメントの直下の`define`を集め、部品の呼び出しを本体に置き換える
+// 定義そのものは出力しない
+pub(super) fn expand(ast: &AstRoot, errors: &mut Vec<TransformError>) -> AstRoot {
+    let mut components: HashMap<&str, &Component> = HashMap::new();
+    for node in &ast.elements {
+        let Node::Define(component) = node else {
+            continue;
+        };
+        if let Some(defined) = components.get(component.name.as_str()) {
+            // 同じファイルを何度も読み込んだ場合の定義は位置も同じになるため、重複としない
+            if defined.name_span != component.name_span {
+                errors.push(TransformError::DuplicateComponent(
+                    component.name.clone(),
+                    component.name_span,
+                ));
+            }
+            continue;
+        }
+        components.insert(&component.name, component);
+    }
+
+    let mut expander = Expander {
+        components,
+        errors,
+        stack: Vec::new(),
+    };
+    let mut elements = VecDeque::new();
+    for node in &ast.elements {
+        match node {
+            // 呼ばれない部品の中の入れ子の定義も報告する
+            Node::Define(component) => {
+                let mut body = VecDeque::new();
+                expander.stack.push(component.name.clone());
+                expander.nodes(&component.children, Some(&VecDeque::new()), &mut body);
+                expander.stack.pop();
+            }
+            _ => expander.node(node, None, &mut elements),
+        }
+    }
+    AstRoot { elements }
+}
+
+struct Expander<'a, 'e> {
+    components: HashMap<&'a str, &'a Component>,
+    errors: &'e mut Vec<TransformError>,
+    // 展開中の部品の名前
+    stack: Vec<String>,
+}
+
+impl<'a> Expander<'a, '_> {
+    fn collect(&mut self, nodes: &VecDeque<Node>, slot: Option<&VecDeque<Node>>) -> VecDeque<Node> {
+        let mut output = VecDeque::new();
+        self.nodes(nodes, slot, &mut output);
+        output
+    }
+
+    // slotは展開中の部品を呼び出した側の子(展開済み)。部品の外ではNone
+    fn nodes(
+        &mut self,
+        nodes: &VecDeque<Node>,
+        slot: Option<&VecDeque<Node>>,
+        output: &mut VecDeque<Node>,
+    ) {
+        for node in nodes {
+            self.node(node, slot, output);
+        }
+    }
+
+    fn node(&mut self, node: &Node, slot: Option<&VecDeque<Node>>, output: &mut VecDeque<Node>) {
+        match (node, slot) {
+            // 子を渡されなかった場合はslotの中身を既定の内容として使う
+            (Node::Element(element), Some(children)) if element.tag_name == SLOT_TAG => {
+                if children.is_empty() {
+                    self.nodes(&element.children, None, output);
+                } else {
+                    output.extend(children.iter().cloned());
+                }
+            }
+            (Node::Element(element), _) => match self.components.get(element.tag_name.as_str()) {
+                Some(component) => self.call(component, element, slot, output),
+                None => output.push_back(Node::Element(Element {
+                    tag_name: element.tag_name.clone(),
+                    attributes: element.attributes.clone(),
+                    children: self.collect(&element.children, slot),
+                    span: element.span,
+                })),
+            },
+            (Node::If(node), _) => output.push_back(Node::If(If {
+                then: self.collect(&node.then, slot),
+                otherwise: node
+                    .otherwise
+                    .as_ref()
+                    .map(|otherwise| self.collect(otherwise, slot)),
+                ..node.clone()
+            })),
+            (Node::For(node), _) => output.push_back(Node::For(For {
+                children: self.collect(&node.children, slot),
+                ..node.clone()
+            })),
+            (Node::Define(component), _) => self.errors.push(TransformError::NestedDefinition(
+                component.name.clone(),
+                component.name_span,
+            )),
+            (Node::Text(_, _) | Node::Comment(_, _) | Node::Raw(_, _), _) => {
+                output.push_back(node.clone())
+            }
+        }
+    }
+
+    fn call(
+        &mut self,
+        component: &'a Component,
+        element: &Element,
+        slot: Option<&VecDeque<Node>>,
+        output: &mut VecDeque<Node>,
+    ) {
+        let name_span = Span::new(
+            element.span.start,
+            element.span.start + element.tag_name.len(),
+        );
+        if let Some(start) = self.stack.iter().position(|name| *name == component.name) {
+            let mut chain = self.stack[start..].to_vec();
+            chain.push(component.name.clone());
+            self.errors
+                .push(TransformError::RecursiveComponent(chain, name_span));
+            return;
+        }
+
+        let mut arguments: HashMap<&str, String> = HashMap::new();
+        for attribute in &element.attributes {
+            let declared = component
+                .parameters
+                .iter()
+                .any(|parameter| parameter.name == attribute.name);
+            if !declared {
+                self.errors.push(TransformError::UnknownParameter(
+                    component.name.clone(),
+                    attribute.name.clone(),
+                    attribute.span,
+                ));
+                continue;
+            }
+            arguments
+                .entry(&attribute.name)
+                .or_insert_with(|| attribute.value.clone().unwrap_or_default());
+        }
+        for parameter in &component.parameters {
+            if arguments.contains_key(parameter.name.as_str()) {
+                continue;
+            }
+            match &parameter.value {
+                Some(default) => {
+                    arguments.insert(&parameter.name, default.clone());
+                }
+                None => self.errors.push(TransformError::MissingParameter(
+                    component.name.clone(),
+                    parameter.name.clone(),
+                    name_span,
+                )),
+            }
+        }
+
+        // 呼び出し側の子は呼び出した側の部品の中として展開してから渡す
+        let children = self.collect(&element.children, slot);
+        let body = substitute(&component.children, &arguments);
+        self.stack.push(component.name.clone());
+        self.nodes(&body, Some(&children), output);
+        self.stack.pop();
+    }
+}
+
+// 本体の文字列と属性値の中の`{{ 引数 }}`を渡された値に置き換える
+// 引数でない名前はtemplate::renderでデータから読むためそのまま残す
+fn substitute(nodes: &VecDeque<Node>, arguments: &HashMap<&str, String>) -> VecDeque<Node> {
+    nodes
+        .iter()
+        .map(|node| match node {
+            Node::Element(element) => Node::Element(Element {
+                tag_name: element.tag_name.clone(),
+                attributes: element
+                    .attributes
+                    .iter()
+                    .map(|attribute| Attribute {
+                        value: attribute
+                            .value
+                            .as_ref()
+                            .map(|value| replace(value, arguments)),
+                        ..attribute.clone()
+                    })
+                    .collect(),
+                children: substitute(&element.children, arguments),
+                span: element.span,
+            }),
+            Node::Text(text, span) => Node::Text(replace(text, arguments), *span),
+            Node::If(node) => Node::If(If {
+                then: substitute(&node.then, arguments),
+                otherwise: node
+                    .otherwise
+                    .as_ref()
+                    .map(|otherwise| substitute(otherwise, arguments)),
+                ..node.clone()
+            }),
+            Node::For(node) => Node::For(For {
+                children: substitute(&node.children, arguments),
+                ..node.clone()
+            }),
+            Node::Comment(_, _) | Node::Raw(_, _) | Node::Define(_) => node.clone(),
+        })
+        .collect()
+}
+
+fn replace(text: &str, arguments: &HashMap<&str, String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = 0;
+    while let Some(open) = text[rest..].find("{{").map(|i| rest + i) {
+        // `{{{{`は文字どおりの`{{`としてtemplate::renderに渡す
+        if text[open..].starts_with("{{{{") {
+            result.push_str(&text[rest..open + 4]);
+            rest = open + 4;
+            continue;
+        }
+        let Some(close) = text[open..].find("}}").map(|i| open + i + 2) else {
+            break;
+        };
+        result.push_str(&text[rest..open]);
+        match arguments.get(text[open + 2..close - 2].trim()) {
+            Some(value) => result.push_str(value),
+            None => result.push_str(&text[open..close]),
+        }
+        rest = close;
+    }
+    result.push_str(&text[rest..]);
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        html_converter::HtmlOptions, jtml_lexer::test_utils::lexer, jtml_parser::parse,
+        transformer::transform,
+    };
+
+    fn expand(source: &str) -> Result<String, Vec<String>> {
+        let ast = parse(&lexer(source)).unwrap();
+        match transform(&ast) {
+            Ok(ast) => Ok(ast.to_html(&HtmlOptions::default())),
+            Err(errors) => Err(errors.iter().map(|e| e.to_string()).collect()),
+        }
+    }
+
+    #[test]
+    fn expand_components() {
+        let source = r#"
+define card(title subtitle="-"){
+    div(class="card {{ title }}"){ h2(){"{{ title }}: {{ subtitle }}"} slot(){"empty"} }
+}
+define list(){ ul(){ slot() } }
+list(){ li(){ card(title="a"){ p(){"{{ title }}"} } } }
+card(title="b" subtitle="c"){"{{{{ title }}"}"#;
+        // 呼び出し側の子の中の`{{ title }}`は部品の引数ではなくデータとして残す
+        assert_eq!(
+            expand(source).unwrap(),
+            r#"<ul><li><div class="card a"><h2>a: -</h2><p>{{ title }}</p></div></li></ul><div class="card b"><h2>b: c</h2>{{{{ title }}</div>"#
+        );
+        assert_eq!(
+            expand(r#"define a(x){ p(title="{{{{ x }}"){"{{{{{{ x }}"} } a(x="1"){}"#).unwrap(),
+            r#"<p title="{{{{ x }}">{{{{1</p>"#
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            expand(r#"define a(x){} a(y="1"){}"#).unwrap_err(),
+            vec![
+                "component `a` has no parameter `y`",
+                "component `a` requires `x`"
+            ]
+        );
+        assert_eq!(
+            expand(r#"define a(){ b(){} } define b(){ p(){ a(){} } } a(){}"#).unwrap_err(),
+            vec![
+                "recursive component: a -> b -> a",
+                "recursive component: b -> a -> b"
+            ]
+        );
+        assert_eq!(
+            expand(r#"define a(){} define a(){} p(){ define b(){} }"#).unwrap_err(),
+            vec![
+                "component `a` is defined more than once",
+                "component `b` is not defined at the top level"
+            ]
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+use crate::{diagnostics::Diagnostic, span::Span};
+
+use super::Sources;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransformError {
+    // 部品の名前, 定義にない引数, その引数の位置
+    UnknownParameter(String, String, Span),
+    // 部品の名前, 渡されなかった必須の引数, 呼び出しの位置
+    MissingParameter(String, String, Span),
+    // 展開中の部品の名前の連鎖(最後が再び現れた部品), 呼び出しの位置
+    RecursiveComponent(Vec<String>, Span),
+    // 同じ名前で二度目に定義された部品
+    DuplicateComponent(String, Span),
+    // ドキュメントの直下以外で定義された部品
+    NestedDefinition(String, Span),
+    // 文字列を変換した場合など、読み込む側のファイルのパスがないinclude, import
+    MissingResolver(String, Span),
+    // 読み込めなかったファイル, その理由, include, importの位置
+    IncludeNotFound(String, String, Span),
+    // 構文エラーのあるファイル, `行:列: エラー`(1始まり), include, importの位置
+    InvalidInclude(String, String, Span),
+    // 読み込み中のファイルの連鎖(最後が再び現れたファイル), include, importの位置
+    IncludeCycle(Vec<String>, Span),
+    // ファイルの直下の最初のもの以外のextends
+    MisplacedExtends(Span),
+    // extendsを持つファイルの直下の、block, define, コメント以外のノード
+    ContentOutsideBlock(Span),
+    // ページで二度目に書かれたblockの名前
+    DuplicateBlock(String, Span),
+    // レイアウト, レイアウトにないblockの名前, ページのblockの位置
+    UnknownBlock(String, String, Span),
+}
+
+impl Display for TransformError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransformError::UnknownParameter(component, name, _) => {
+                write!(f, "component `{}` has no parameter `{}`", component, name)
+            }
+            TransformError::MissingParameter(component, name, _) => {
+                write!(f, "component `{}` requires `{}`", component, name)
+            }
+            TransformError::RecursiveComponent(chain, _) => {
+                write!(f, "recursive component: {}", chain.join(" -> "))
+            }
+            TransformError::DuplicateComponent(name, _) => {
+                write!(f, "component `{}` is defined more than once", name)
+            }
+            TransformError::NestedDefinition(name, _) => {
+                write!(f, "component `{}` is not defined at the top level", name)
+            }
+            TransformError::MissingResolver(tag_name, _) => {
+                write!(
+                    f,
+                    "`{}` requires the path of the file being converted",
+                    tag_name
+                )
+            }
+            TransformError::IncludeNotFound(path, reason, _) => {
+                write!(f, "cannot read `{}`: {}", path, reason)
+            }
+            TransformError::InvalidInclude(path, message, _) => {
+                write!(f, "error in {}:{}", path, message)
+            }
+            TransformError::IncludeCycle(chain, _) => {
+                write!(f, "include cycle: {}", chain.join(" -> "))
+            }
+            TransformError::MisplacedExtends(_) => {
+                write!(f, "`extends` must be written once at the top level")
+            }
+            TransformError::ContentOutsideBlock(_) => {
+                write!(f, "content outside of a block in a page with a layout")
+            }
+            TransformError::DuplicateBlock(name, _) => {
+                write!(f, "block `{}` is filled more than once", name)
+            }
+            TransformError::UnknownBlock(layout, name, _) => {
+                write!(f, "layout `{}` has no block `{}`", layout, name)
+            }
+        }
+    }
+}
+
+impl TransformError {
+    pub fn span(&self) -> Span {
+        match self {
+            TransformError::UnknownParameter(_, _, span) => *span,
+            TransformError::MissingParameter(_, _, span) => *span,
+            TransformError::RecursiveComponent(_, span) => *span,
+            TransformError::DuplicateComponent(_, span) => *span,
+            TransformError::NestedDefinition(_, span) => *span,
+            TransformError::MissingResolver(_, span) => *span,
+            TransformError::IncludeNotFound(_, _, span) => *span,
+            TransformError::InvalidInclude(_, _, span) => *span,
+            TransformError::IncludeCycle(_, span) => *span,
+            TransformError::MisplacedExtends(span) => *span,
+            TransformError::ContentOutsideBlock(span) => *span,
+            TransformError::DuplicateBlock(_, span) => *span,
+            TransformError::UnknownBlock(_, _, span) => *span,
+        }
+    }
+
+    // 読み込んだファイルの中のエラーを、そのファイルの中の位置と共に変換するファイルのincludeを指すエラーにする
+    pub(super) fn in_included(self, sources: &Sources) -> TransformError {
+        let Some(file) = sources.locate(self.span()) else {
+            return self;
+        };
+        TransformError::InvalidInclude(
+            file.path.display().to_string(),
+            format!("{}: {}", file.position(file.local(self.span()).start), self),
+            file.site,
+        )
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            TransformError::UnknownParameter(component, name, span) => {
+                Diagnostic::error("unknown parameter", *span)
+                    .with_label(format!("`{}` does not take `{}`", component, name))
+                    .with_hint("parameters are declared in `define name(a b=\"default\")`")
+            }
+            TransformError::MissingParameter(component, name, span) => {
+                Diagnostic::error("missing parameter", *span)
+                    .with_label(format!("`{}` needs a value for `{}`", component, name))
+                    .with_hint("parameters declared without a value are required")
+            }
+            TransformError::RecursiveComponent(chain, span) => {
+                Diagnostic::error("recursive component", *span)
+                    .with_label(format!("expands to itself: {}", chain.join(" -> ")))
+                    .with_hint(
+                        "a component cannot use itself, directly or through other components",
+                    )
+            }
+            TransformError::DuplicateComponent(name, span) => {
+                Diagnostic::error("duplicate component", *span)
+                    .with_label(format!("`{}` is already defined", name))
+            }
+            TransformError::NestedDefinition(name, span) => {
+                Diagnostic::error("nested component definition", *span)
+                    .with_label(format!("`{}` is defined inside another node", name))
+                    .with_hint("write `define` at the top level of the file")
+            }
+            TransformError::MissingResolver(tag_name, span) => {
+                Diagnostic::error("cannot resolve file", *span)
+                    .with_label(format!(
+                        "`{}` is relative to the file being converted",
+                        tag_name
+                    ))
+                    .with_hint("convert a file with `jtml build` or `html_converter::convert_file`")
+            }
+            TransformError::IncludeNotFound(path, reason, span) => {
+                Diagnostic::error("cannot read included file", *span)
+                    .with_label(format!("`{}`: {}", path, reason))
+                    .with_hint("`src` is relative to the directory of the including file")
+            }
+            TransformError::InvalidInclude(path, message, span) => {
+                Diagnostic::error("error in included file", *span)
+                    .with_label(format!("{}:{}", path, message))
+            }
+            TransformError::IncludeCycle(chain, span) => Diagnostic::error("include cycle", *span)
+                .with_label(format!("includes itself: {}", chain.join(" -> ")))
+                .with_hint(
+                    "a file cannot include or import itself, directly or through other files",
+                ),
+            TransformError::MisplacedExtends(span) => {
+                Diagnostic::error("misplaced `extends`", *span)
+                    .with_label("a page extends one layout")
+                    .with_hint(
+                        "write `extends(layout=\"base.jtml\")` once at the top level of the page",
+                    )
+            }
+            TransformError::ContentOutsideBlock(span) => {
+                Diagnostic::error("content outside of a block", *span)
+                    .with_label("this is not part of any block of the layout")
+                    .with_hint("wrap it in `block(name=\"...\"){...}`")
+            }
+            TransformError::DuplicateBlock(name, span) => {
+                Diagnostic::error("duplicate block", *span)
+                    .with_label(format!("`{}` is already filled", name))
+            }
+            TransformError::UnknownBlock(layout, name, span) => Diagnostic::error(
+                "unknown block",
+                *span,
+            )
+            .with_label(format!("`{}` has no block `{}`", layout, name))
+            .with_hint(
+                "the layout declares the blocks a page can fill with `block(name=\"...\"){...}`",
+            ),
+        }
+    }
+}
+
+impl Error for TransformError {}
@@ -0,0 +1,350 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    jtml_lexer::lexer,
+    jtml_parser::{
+        parse_recovering, AstRoot, Component, Element, For, If, Node, IMPORT_TAG, INCLUDE_TAG,
+    },
+    span::{LineIndex, Span},
+};
+
+use super::{layouts, Resolver, Sources, TransformError};
+
+// include(src="...")を読み込んだファイルの中身に、import(src="...")をその中の部品の定義に置き換える
+// extends(layout="...")を持つファイルは、レイアウトのblockを埋めたものに置き換える
+// fileは変換するファイルのパスとresolverで、文字列を変換する場合はNone(読み込みはエラーになる)
+// 読み込んだファイルの一覧と中身も返す
+pub(super) fn resolve(
+    ast: &AstRoot,
+    file: Option<(&Path, &dyn Resolver)>,
+    errors: &mut Vec<TransformError>,
+) -> (AstRoot, Vec<PathBuf>, Sources) {
+    let mut includer = Includer {
+        resolver: file.map(|(_, resolver)| resolver),
+        errors,
+        stack: file
+            .map(|(path, _)| vec![path.to_path_buf()])
+            .unwrap_or_default(),
+        site: None,
+        dependencies: Vec::new(),
+        imported: HashSet::new(),
+        sources: Sources::new(ast.elements.back().map_or(0, |node| node.span().end)),
+    };
+    let elements = includer.file(&ast.elements);
+    let elements = layouts::strip(&elements, includer.errors);
+    (
+        AstRoot { elements },
+        includer.dependencies,
+        includer.sources,
+    )
+}
+
+struct Includer<'r, 'e> {
+    resolver: Option<&'r dyn Resolver>,
+    errors: &'e mut Vec<TransformError>,
+    // 読み込み中のファイル。先頭が変換するファイル
+    stack: Vec<PathBuf>,
+    // 変換するファイルに書かれた、読み込み中のincludeの位置
+    // 読み込んだファイルの中の位置は変換するファイルを指さないため、読み込みのエラーはこの位置にする
+    site: Option<Span>,
+    dependencies: Vec<PathBuf>,
+    // importしたファイル。同じファイルを何度importしても部品は一度だけ取り込む
+    imported: HashSet<PathBuf>,
+    sources: Sources,
+}
+
+impl Includer<'_, '_> {
+    // ファイル一つ分のノード
+    fn file(&mut self, nodes: &VecDeque<Node>) -> VecDeque<Node> {
+        let nodes = self.nodes(nodes);
+        let Some(extends) = layouts::extends(&nodes, self.errors) else {
+            return nodes;
+        };
+        let Some((path, layout)) = self.load(extends, "layout") else {
+            return VecDeque::new();
+        };
+        layouts::apply(&layout, &nodes, &path.display().to_string(), self.errors)
+    }
+
+    fn nodes(&mut self, nodes: &VecDeque<Node>) -> VecDeque<Node> {
+        let mut output = VecDeque::new();
+        for node in nodes {
+            self.node(node, &mut output);
+        }
+        output
+    }
+
+    fn node(&mut self, node: &Node, output: &mut VecDeque<Node>) {
+        match node {
+            Node::Element(element) if element.tag_name == INCLUDE_TAG => {
+                if let Some((_, nodes)) = self.load(element, "src") {
+                    output.extend(nodes)
+                }
+            }
+            // 部品の定義以外は読み捨てる
+            Node::Element(element) if element.tag_name == IMPORT_TAG => {
+                if let Some((_, nodes)) = self.load(element, "src") {
+                    output.extend(
+                        nodes
+                            .into_iter()
+                            .filter(|node| matches!(node, Node::Define(_))),
+                    )
+                }
+            }
+            Node::Element(element) => output.push_back(Node::Element(Element {
+                tag_name: element.tag_name.clone(),
+                attributes: element.attributes.clone(),
+                children: self.nodes(&element.children),
+                span: element.span,
+            })),
+            Node::If(node) => output.push_back(Node::If(If {
+                then: self.nodes(&node.then),
+                otherwise: node
+                    .otherwise
+                    .as_ref()
+                    .map(|otherwise| self.nodes(otherwise)),
+                ..node.clone()
+            })),
+            Node::For(node) => output.push_back(Node::For(For {
+                children: self.nodes(&node.children),
+                ..node.clone()
+            })),
+            Node::Define(component) => output.push_back(Node::Define(Component {
+                children: self.nodes(&component.children),
+                ..component.clone()
+            })),
+            Node::Text(_, _) | Node::Comment(_, _) | Node::Raw(_, _) => {
+                output.push_back(node.clone())
+            }
+        }
+    }
+
+    // elementのattributeに書かれたファイルのパスと、そのノード(その中のinclude, import, extendsも解決済み)
+    // 読めない場合はエラーを追加してNoneを返す
+    fn load(&mut self, element: &Element, attribute: &str) -> Option<(PathBuf, VecDeque<Node>)> {
+        let span = self.site.unwrap_or(element.span);
+        // 属性がない場合は構文解析でエラーにしている
+        let src = element
+            .attributes
+            .iter()
+            .find(|written| written.name == attribute)
+            .and_then(|written| written.value.as_ref())?;
+        let (Some(resolver), Some(from)) = (self.resolver, self.stack.last()) else {
+            self.errors.push(TransformError::MissingResolver(
+                element.tag_name.clone(),
+                span,
+            ));
+            return None;
+        };
+
+        let path = resolver.resolve(from, src);
+        if let Some(start) = self.stack.iter().position(|file| *file == path) {
+            let chain = self.stack[start..]
+                .iter()
+                .chain([&path])
+                .map(|file| file.display().to_string())
+                .collect();
+            self.errors.push(TransformError::IncludeCycle(chain, span));
+            return None;
+        }
+        if element.tag_name == IMPORT_TAG && !self.imported.insert(path.clone()) {
+            return Some((path, VecDeque::new()));
+        }
+        let source = match resolver.read(&path) {
+            Ok(source) => source,
+            Err(e) => {
+                self.errors.push(TransformError::IncludeNotFound(
+                    path.display().to_string(),
+                    e.to_string(),
+                    span,
+                ));
+                return None;
+            }
+        };
+        if !self.dependencies.contains(&path) {
+            self.dependencies.push(path.clone());
+        }
+        let ast = match parse(&source) {
+            Ok(ast) => ast,
+            Err((offset, message)) => {
+                let position = LineIndex::new(&source).position(&source, offset);
+                self.errors.push(TransformError::InvalidInclude(
+                    path.display().to_string(),
+                    format!("{}:{}: {}", position.line + 1, position.column + 1, message),
+                    span,
+                ));
+                return None;
+            }
+        };
+
+        // 後のエラーがどのファイルの中かを区別できるよう、ファイルごとに位置をずらす
+        let base = self.sources.base(&path, &source, span);
+        let elements = ast
+            .elements
+            .iter()
+            .map(|node| node.map_spans(&|span| Span::new(span.start + base, span.end + base)))
+            .collect();
+
+        let outer = self.site.replace(span);
+        self.stack.push(path);
+        let nodes = self.file(&elements);
+        let path = self.stack.pop().unwrap();
+        self.site = outer;
+        Some((path, nodes))
+    }
+}
+
+// 読み込んだファイルの最初のエラーを、そのファイルの中の位置と共に返す
+fn parse(source: &str) -> Result<AstRoot, (usize, String)> {
+    let tokens = lexer(source.to_string()).map_err(|e| (e.span().start, e.to_string()))?;
+    let (ast, errors) = parse_recovering(&tokens);
+    match errors.first() {
+        Some(error) => Err((
+            error.span().map_or(source.len(), |span| span.start),
+            error.to_string(),
+        )),
+        None => Ok(ast),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use crate::{
+        html_converter::HtmlOptions,
+        jtml_lexer::test_utils::lexer,
+        jtml_parser::parse,
+        transformer::{transform, transform_file, MemoryResolver},
+    };
+
+    fn resolver() -> MemoryResolver {
+        let mut resolver = MemoryResolver::new();
+        resolver.insert(
+            "partials/header.jtml",
+            r#"header(){ include(src="nav.jtml") }"#,
+        );
+        resolver.insert("partials/nav.jtml", r#"nav(){"{{ title }}"}"#);
+        resolver.insert(
+            "lib/ui.jtml",
+            r#"define card(){ div(class="card"){ slot() } } p(){"not imported"}"#,
+        );
+        resolver.insert("a.jtml", r#"include(src="b.jtml")"#);
+        resolver.insert("b.jtml", r#"p(){ import(src="a.jtml") }"#);
+        resolver.insert("broken.jtml", "p(){\n  div(");
+        resolver.insert(
+            "partials/cards.jtml",
+            "import(src=\"../lib/ui.jtml\")\ndiv(){\n  card(size=\"1\"){}\n}",
+        );
+        resolver
+    }
+
+    fn transform_source(source: &str) -> Result<(String, Vec<PathBuf>), Vec<String>> {
+        let ast = parse(&lexer(source)).unwrap();
+        match transform_file(&ast, Path::new("pages/index.jtml"), &resolver()) {
+            Ok(transformed) => Ok((
+                transformed.ast.to_html(&HtmlOptions::default()),
+                transformed.dependencies,
+            )),
+            Err(errors) => Err(errors.iter().map(|e| e.to_string()).collect()),
+        }
+    }
+
+    #[test]
+    fn include_and_import() {
+        let (html, dependencies) = transform_source(
+            r#"import(src="../lib/ui.jtml") main(){ include(src="../partials/header.jtml") card(){"x"} }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            html,
+            r#"<main><header><nav>{{ title }}</nav></header><div class="card">x</div></main>"#
+        );
+        assert_eq!(
+            dependencies,
+            vec![
+                PathBuf::from("lib/ui.jtml"),
+                PathBuf::from("partials/header.jtml"),
+                PathBuf::from("partials/nav.jtml"),
+            ]
+        );
+    }
+
+    #[test]
+    fn diamond_import() {
+        // ページと読み込んだファイルが同じファイルをimportしても、部品は一度だけ定義される
+        let mut resolver = resolver();
+        resolver.insert(
+            "partials/list.jtml",
+            r#"import(src="../lib/ui.jtml") card(){"b"}"#,
+        );
+        resolver.insert("lib/other.jtml", r#"define card(){ p(){ slot() } }"#);
+        let transform = |source: &str| {
+            let ast = parse(&lexer(source)).unwrap();
+            transform_file(&ast, Path::new("pages/index.jtml"), &resolver)
+                .map(|transformed| transformed.ast.to_html(&HtmlOptions::default()))
+                .map_err(|errors| errors.iter().map(|e| e.to_string()).collect::<Vec<_>>())
+        };
+        assert_eq!(
+            transform(
+                r#"import(src="../lib/ui.jtml") card(){"a"} include(src="../partials/list.jtml") import(src="../lib/ui.jtml")"#
+            ),
+            Ok(r#"<div class="card">a</div><div class="card">b</div>"#.to_string())
+        );
+        // 同じファイルを二度includeした場合も、同じ定義は重複としない
+        assert_eq!(
+            transform(r#"include(src="../lib/ui.jtml") include(src="../lib/ui.jtml") card(){}"#),
+            Ok(r#"<p>not imported</p><p>not imported</p><div class="card"></div>"#.to_string())
+        );
+        // 別のファイルの同じ名前の部品は重複とする
+        assert_eq!(
+            transform(r#"import(src="../lib/ui.jtml") import(src="../lib/other.jtml")"#),
+            Err(vec![
+                "error in lib/other.jtml:1:8: component `card` is defined more than once"
+                    .to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            transform_source(
+                r#"include(src="../a.jtml") include(src="missing.jtml") include(src="../broken.jtml")"#
+            )
+            .unwrap_err(),
+            vec![
+                "include cycle: a.jtml -> b.jtml -> a.jtml",
+                "cannot read `pages/missing.jtml`: no such file in the resolver",
+                "error in broken.jtml:2:7: unexpected end of file: expected ')'"
+            ]
+        );
+        // 読み込んだファイルの中のエラーは、変換するファイルのincludeを指す
+        let source = r#"p(){} include(src="../a.jtml")"#;
+        let ast = parse(&lexer(source)).unwrap();
+        let errors = transform_file(&ast, Path::new("pages/index.jtml"), &resolver()).unwrap_err();
+        assert_eq!((errors[0].span().start, errors[0].span().end), (6, 30));
+
+        // 部品の展開などのエラーは、読み込んだファイルの中の行と列を示す
+        let source = "p(){}\ninclude(src=\"../partials/cards.jtml\")";
+        let ast = parse(&lexer(source)).unwrap();
+        let errors = transform_file(&ast, Path::new("pages/index.jtml"), &resolver()).unwrap_err();
+        assert_eq!(
+            errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            vec!["error in partials/cards.jtml:3:8: component `card` has no parameter `size`"]
+        );
+        assert_eq!(
+            &source[errors[0].span().range()],
+            r#"include(src="../partials/cards.jtml")"#
+        );
+
+        let ast = parse(&lexer(r#"include(src="a.jtml")"#)).unwrap();
+        assert_eq!(
+            transform(&ast).unwrap_err()[0].to_string(),
+            "`include` requires the path of the file being converted"
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::jtml_parser::{Component, Element, For, If, Node, BLOCK_TAG, EXTENDS_TAG};
+
+use super::TransformError;
+
+// ファイルの直下のextends。二つ目以降はエラーにする
+pub(super) fn extends<'a>(
+    nodes: &'a VecDeque<Node>,
+    errors: &mut Vec<TransformError>,
+) -> Option<&'a Element> {
+    let mut found = None;
+    for node in nodes {
+        let Node::Element(element) = node else {
+            continue;
+        };
+        if element.tag_name != EXTENDS_TAG {
+            continue;
+        }
+        match found {
+            Some(_) => errors.push(TransformError::MisplacedExtends(element.span)),
+            None => found = Some(element),
+        }
+    }
+    found
+}
+
+// レイアウトのblockの中身をページの同じ名前のblockの中身に置き換え、ページの部品の定義を加える
+// blockは外側のレイアウトからさらに置き換えられるように残し、最後にstripで取り除く
+pub(super) fn apply(
+    layout: &VecDeque<Node>,
+    page: &VecDeque<Node>,
+    layout_name: &str,
+    errors: &mut Vec<TransformError>,
+) -> VecDeque<Node> {
+    let mut blocks: Vec<(&str, &Element)> = Vec::new();
+    let mut definitions = VecDeque::new();
+    for node in page {
+        match node {
+            Node::Element(element) if element.tag_name == BLOCK_TAG => {
+                let name = block_name(element);
+                if blocks.iter().any(|(written, _)| *written == name) {
+                    errors.push(TransformError::DuplicateBlock(
+                        name.to_string(),
+                        element.span,
+                    ));
+                    continue;
+                }
+                blocks.push((name, element));
+            }
+            Node::Element(element) if element.tag_name == EXTENDS_TAG => (),
+            Node::Define(_) => definitions.push_back(node.clone()),
+            Node::Comment(_, _) => (),
+            _ => errors.push(TransformError::ContentOutsideBlock(node.span())),
+        }
+    }
+
+    let mut filler = Filler {
+        blocks: &blocks,
+        filling: Vec::new(),
+        used: HashSet::new(),
+    };
+    let mut output = filler.nodes(layout);
+    for (name, element) in &blocks {
+        if !filler.used.contains(name) {
+            errors.push(TransformError::UnknownBlock(
+                layout_name.to_string(),
+                name.to_string(),
+                element.span,
+            ));
+        }
+    }
+    output.extend(definitions);
+    output
+}
+
+fn block_name(element: &Element) -> &str {
+    // nameがない場合は構文解析でエラーにしている
+    element
+        .attributes
+        .iter()
+        .find(|attribute| attribute.name == "name")
+        .and_then(|attribute| attribute.value.as_deref())
+        .unwrap_or_default()
+}
+
+struct Filler<'a> {
+    blocks: &'a [(&'a str, &'a Element)],
+    // 置き換え中のblock。その中身にある同じ名前のblockは置き換えない
+    filling: Vec<&'a str>,
+    used: HashSet<&'a str>,
+}
+
+impl<'a> Filler<'a> {
+    fn nodes(&mut self, nodes: &VecDeque<Node>) -> VecDeque<Node> {
+        nodes.iter().map(|node| self.node(node)).collect()
+    }
+
+    fn node(&mut self, node: &Node) -> Node {
+        match node {
+            Node::Element(element) => {
+                let name = block_name(element);
+                let replacement = self
+                    .blocks
+                    .iter()
+                    .find(|(written, _)| element.tag_name == BLOCK_TAG && *written == name);
+                let children = match replacement {
+                    Some((name, page)) if !self.filling.contains(name) => {
+                        self.used.insert(name);
+                        self.filling.push(name);
+                        let children = self.nodes(&page.children);
+                        self.filling.pop();
+                        children
+                    }
+                    _ => self.nodes(&element.children),
+                };
+                Node::Element(Element {
+                    tag_name: element.tag_name.clone(),
+                    attributes: element.attributes.clone(),
+                    children,
+                    span: element.span,
+                })
+            }
+            Node::If(node) => Node::If(If {
+                then: self.nodes(&node.then),
+                otherwise: node
+                    .otherwise
+                    .as_ref()
+                    .map(|otherwise| self.nodes(otherwise)),
+                ..node.clone()
+            }),
+            Node::For(node) => Node::For(For {
+                children: self.nodes(&node.children),
+                ..node.clone()
+            }),
+            Node::Define(component) => Node::Define(Component {
+                children: self.nodes(&component.children),
+                ..component.clone()
+            }),
+            Node::Text(_, _) | Node::Comment(_, _) | Node::Raw(_, _) => node.clone(),
+        }
+    }
+}
+
+// blockを中身に置き換える。ファイルの直下以外に残ったextendsはエラーにする
+pub(super) fn strip(nodes: &VecDeque<Node>, errors: &mut Vec<TransformError>) -> VecDeque<Node> {
+    let mut output = VecDeque::new();
+    for node in nodes {
+        match node {
+            Node::Element(element) if element.tag_name == BLOCK_TAG => {
+                output.extend(strip(&element.children, errors))
+            }
+            Node::Element(element) if element.tag_name == EXTENDS_TAG => {
+                errors.push(TransformError::MisplacedExtends(element.span))
+            }
+            Node::Element(element) => output.push_back(Node::Element(Element {
+                tag_name: element.tag_name.clone(),
+                attributes: element.attributes.clone(),
+                children: strip(&element.children, errors),
+                span: element.span,
+            })),
+            Node::If(node) => output.push_back(Node::If(If {
+                then: strip(&node.then, errors),
+                otherwise: node
+                    .otherwise
+                    .as_ref()
+                    .map(|otherwise| strip(otherwise, errors)),
+                ..node.clone()
+            })),
+            Node::For(node) => output.push_back(Node::For(For {
+                children: strip(&node.children, errors),
+                ..node.clone()
+            })),
+            Node::Define(component) => output.push_back(Node::Define(Component {
+                children: strip(&component.children, errors),
+                ..component.clone()
+            })),
+            Node::Text(_, _) | Node::Comment(_, _) | Node::Raw(_, _) => {
+                output.push_back(node.clone())
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{
+        html_converter::HtmlOptions,
+        jtml_lexer::test_utils::lexer,
+        jtml_parser::parse,
+        transformer::{transform, transform_file, MemoryResolver},
+    };
+
+    fn extend(source: &str) -> Result<String, Vec<String>> {
+        let mut resolver = MemoryResolver::new();
+        resolver.insert(
+            "layouts/base.jtml",
+            r#"html(){ head(){ title(){ block(name="title"){"Site"} } } body(){ block(name="content") } }"#,
+        );
+        resolver.insert(
+            "layouts/article.jtml",
+            r#"extends(layout="base.jtml") define note(){ small(){ slot() } } block(name="content"){ article(){ block(name="body"){"empty"} } }"#,
+        );
+        resolver.insert(
+            "layouts/loop.jtml",
+            r#"extends(layout="../pages/index.jtml")"#,
+        );
+        let ast = parse(&lexer(source)).unwrap();
+        match transform_file(&ast, Path::new("pages/index.jtml"), &resolver) {
+            Ok(transformed) => Ok(transformed.ast.to_html(&HtmlOptions::default())),
+            Err(errors) => Err(errors.iter().map(|e| e.to_string()).collect()),
+        }
+    }
+
+    #[test]
+    fn layouts() {
+        assert_eq!(
+            extend(r#"extends(layout="../layouts/base.jtml") block(name="content"){"a"}"#).unwrap(),
+            "<html><head><title>Site</title></head><body>a</body></html>"
+        );
+        // 外側のレイアウトのblockもページから置き換えられ、レイアウトの部品も使える
+        assert_eq!(
+            extend(
+                r#"extends(layout="../layouts/article.jtml") // page
+block(name="body"){ note(){"b"} } block(name="title"){"Post"}"#
+            )
+            .unwrap(),
+            "<html><head><title>Post</title></head><body><article><small>b</small></article></body></html>"
+        );
+        let ast = parse(&lexer(r#"p(){ block(name="a"){"default"} }"#)).unwrap();
+        assert_eq!(
+            transform(&ast).unwrap().to_html(&HtmlOptions::default()),
+            "<p>default</p>"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            extend(
+                r#"extends(layout="../layouts/base.jtml") p(){} block(name="x"){} block(name="title"){} block(name="title"){} extends(layout="a.jtml")"#
+            )
+            .unwrap_err(),
+            vec![
+                "`extends` must be written once at the top level",
+                "content outside of a block in a page with a layout",
+                "block `title` is filled more than once",
+                "layout `layouts/base.jtml` has no block `x`",
+            ]
+        );
+        assert_eq!(
+            extend(r#"extends(layout="../layouts/loop.jtml")"#).unwrap_err(),
+            vec!["include cycle: pages/index.jtml -> layouts/loop.jtml -> pages/index.jtml"]
+        );
+        assert_eq!(
+            extend(r#"p(){ extends(layout="../layouts/base.jtml") }"#).unwrap_err(),
+            vec!["`extends` must be written once at the top level"]
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+// include, importのsrcをファイルに結び付け、その中身を読む
+// 既定ではsrcを読み込む側のファイルからの相対パスとして解決する
+pub trait Resolver {
+    fn resolve(&self, from: &Path, src: &str) -> PathBuf {
+        let directory = from.parent().unwrap_or(Path::new(""));
+        normalize(&directory.join(src))
+    }
+
+    fn read(&self, path: &Path) -> io::Result<String>;
+}
+
+// ファイルシステムから読む
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileResolver;
+
+impl Resolver for FileResolver {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+}
+
+// パスと中身の組から読む。テストやエディタの未保存のファイルに使う
+#[derive(Debug, Clone, Default)]
+pub struct MemoryResolver {
+    files: HashMap<PathBuf, String>,
+}
+
+impl MemoryResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, path: impl AsRef<Path>, source: impl Into<String>) {
+        self.files.insert(normalize(path.as_ref()), source.into());
+    }
+}
+
+impl Resolver for MemoryResolver {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such file in the resolver"))
+    }
+}
+
+// `.`と`..`をファイルシステムを見ずに取り除く
+// 先頭を越える`..`は残す
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => (),
+                Some(Component::CurDir | Component::ParentDir) | None => normalized.push(".."),
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use super::{normalize, MemoryResolver, Resolver};
+
+    #[test]
+    fn resolve() {
+        let resolver = MemoryResolver::new();
+        let resolve = |from: &str, src| resolver.resolve(Path::new(from), src);
+        assert_eq!(
+            resolve("pages/index.jtml", "../partials/./header.jtml"),
+            PathBuf::from("partials/header.jtml")
+        );
+        assert_eq!(resolve("index.jtml", "a.jtml"), PathBuf::from("a.jtml"));
+        assert_eq!(
+            resolve("index.jtml", "../a.jtml"),
+            PathBuf::from("../a.jtml")
+        );
+        assert_eq!(normalize(Path::new("/a/../../b")), PathBuf::from("/b"));
+    }
+
+    #[test]
+    fn read() {
+        let mut resolver = MemoryResolver::new();
+        resolver.insert("./partials/header.jtml", "p(){}");
+        assert_eq!(
+            resolver.read(Path::new("partials/header.jtml")).unwrap(),
+            "p(){}"
+        );
+        assert!(resolver.read(Path::new("header.jtml")).is_err());
+    }
+}
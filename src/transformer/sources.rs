@@ -0,0 +1,68 @@
+use std::path::{Path, PathBuf};
+
+use crate::span::{LineIndex, Span};
+
+// include, import, extendsで読み込んだファイルの中身
+// 読み込んだノードの位置は、変換するファイルの位置や他のファイルの位置と重ならないよう、ファイルごとにずらしてある
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Sources {
+    files: Vec<Source>,
+    // 次に読み込むファイルの位置に足す値
+    next_base: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Source {
+    pub(crate) path: PathBuf,
+    pub(crate) text: String,
+    // このファイルの中の位置に足した値
+    base: usize,
+    // 変換するファイルに書かれた、このファイルを読み込むinclude, import, extendsの位置
+    pub(crate) site: Span,
+}
+
+impl Source {
+    // ずらす前の、このファイルの中の位置
+    pub(crate) fn local(&self, span: Span) -> Span {
+        Span::new(span.start - self.base, span.end - self.base)
+    }
+
+    // このファイルの中の位置を`行:列`(1始まり)にする
+    pub(crate) fn position(&self, offset: usize) -> String {
+        let position = LineIndex::new(&self.text).position(&self.text, offset);
+        format!("{}:{}", position.line + 1, position.column + 1)
+    }
+}
+
+impl Sources {
+    // endは変換するファイルの位置の最大値
+    pub(super) fn new(end: usize) -> Self {
+        Sources {
+            files: Vec::new(),
+            next_base: end + 1,
+        }
+    }
+
+    // pathのファイルの位置に足す値。同じファイルは何度読み込んでも同じ位置にする
+    pub(super) fn base(&mut self, path: &Path, text: &str, site: Span) -> usize {
+        if let Some(file) = self.files.iter().find(|file| file.path == path) {
+            return file.base;
+        }
+        let base = self.next_base;
+        self.next_base += text.len() + 1;
+        self.files.push(Source {
+            path: path.to_path_buf(),
+            text: text.to_string(),
+            base,
+            site,
+        });
+        base
+    }
+
+    // spanを含む読み込んだファイル。変換するファイルの中の位置であればNone
+    pub(crate) fn locate(&self, span: Span) -> Option<&Source> {
+        self.files
+            .iter()
+            .find(|file| (file.base..=file.base + file.text.len()).contains(&span.start))
+    }
+}